@@ -13,6 +13,9 @@ use crate::types::*;
 /// `Consistency` is an enum which represents Cassandra's consistency levels.
 /// To find more details about each consistency level please refer to the following documentation:
 /// <https://docs.datastax.com/en/cql-oss/3.x/cql/cql_reference/cqlshConsistency.html>
+///
+/// Consistency levels are displayed using Cassandra names, e.g. `LOCAL_QUORUM`, and can be parsed
+/// back case-insensitively, with or without underscores.
 #[derive(Debug, PartialEq, Clone, Copy, Display, Ord, PartialOrd, Eq, Hash)]
 pub enum Consistency {
     /// Closest replica, as determined by the snitch.
@@ -20,23 +23,29 @@ pub enum Consistency {
     /// Provides low latency, guarantees writes never fail.
     /// Note: this consistency level can only be used for writes.
     /// It provides the lowest consistency and the highest availability.
+    #[display(fmt = "ANY")]
     Any,
     ///
     /// A write must be written to the commit log and memtable of at least one replica node.
     /// Satisfies the needs of most users because consistency requirements are not stringent.
+    #[display(fmt = "ONE")]
     One,
     /// A write must be written to the commit log and memtable of at least two replica nodes.
     /// Similar to ONE.
+    #[display(fmt = "TWO")]
     Two,
     /// A write must be written to the commit log and memtable of at least three replica nodes.
     /// Similar to TWO.
+    #[display(fmt = "THREE")]
     Three,
     /// A write must be written to the commit log and memtable on a quorum of replica nodes.
     /// Provides strong consistency if you can tolerate some level of failure.
+    #[display(fmt = "QUORUM")]
     Quorum,
     /// A write must be written to the commit log and memtable on all replica nodes in the cluster
     /// for that partition key.
     /// Provides the highest consistency and the lowest availability of any other level.
+    #[display(fmt = "ALL")]
     All,
     /// Strong consistency. A write must be written to the commit log and memtable on a quorum
     /// of replica nodes in the same data center as thecoordinator node.
@@ -45,6 +54,7 @@ pub enum Consistency {
     /// such as NetworkTopologyStrategy, and a properly configured snitch.
     /// Use to maintain consistency locally (within the single data center).
     /// Can be used with SimpleStrategy.
+    #[display(fmt = "LOCAL_QUORUM")]
     LocalQuorum,
     /// Strong consistency. A write must be written to the commit log and memtable on a quorum of
     /// replica nodes in all data center.
@@ -52,16 +62,19 @@ pub enum Consistency {
     /// in each data center. For example, choose this level
     /// if you want a read to fail when a data center is down and the QUORUM
     /// cannot be reached on that data center.
+    #[display(fmt = "EACH_QUORUM")]
     EachQuorum,
     /// Achieves linearizable consistency for lightweight transactions by preventing unconditional
     /// updates. You cannot configure this level as a normal consistency level,
     /// configured at the driver level using the consistency level field.
     /// You configure this level using the serial consistency field
     /// as part of the native protocol operation. See failure scenarios.
+    #[display(fmt = "SERIAL")]
     Serial,
     /// Same as SERIAL but confined to the data center. A write must be written conditionally
     /// to the commit log and memtable on a quorum of replica nodes in the same data center.
     /// Same as SERIAL. Used for disaster recovery. See failure scenarios.
+    #[display(fmt = "LOCAL_SERIAL")]
     LocalSerial,
     /// A write must be sent to, and successfully acknowledged by,
     /// at least one replica node in the local data center.
@@ -70,6 +83,7 @@ pub enum Consistency {
     /// For security and quality reasons, you can use this consistency level
     /// in an offline datacenter to prevent automatic connection
     /// to online nodes in other data centers if an offline node goes down.
+    #[display(fmt = "LOCAL_ONE")]
     LocalOne,
 }
 
@@ -83,18 +97,18 @@ impl FromStr for Consistency {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let consistency = match s {
-            "Any" => Consistency::Any,
-            "One" => Consistency::One,
-            "Two" => Consistency::Two,
-            "Three" => Consistency::Three,
-            "Quorum" => Consistency::Quorum,
-            "All" => Consistency::All,
-            "LocalQuorum" => Consistency::LocalQuorum,
-            "EachQuorum" => Consistency::EachQuorum,
-            "Serial" => Consistency::Serial,
-            "LocalSerial" => Consistency::LocalSerial,
-            "LocalOne" => Consistency::LocalOne,
+        let consistency = match s.to_ascii_uppercase().as_str() {
+            "ANY" => Consistency::Any,
+            "ONE" => Consistency::One,
+            "TWO" => Consistency::Two,
+            "THREE" => Consistency::Three,
+            "QUORUM" => Consistency::Quorum,
+            "ALL" => Consistency::All,
+            "LOCAL_QUORUM" | "LOCALQUORUM" => Consistency::LocalQuorum,
+            "EACH_QUORUM" | "EACHQUORUM" => Consistency::EachQuorum,
+            "SERIAL" => Consistency::Serial,
+            "LOCAL_SERIAL" | "LOCALSERIAL" => Consistency::LocalSerial,
+            "LOCAL_ONE" | "LOCALONE" => Consistency::LocalOne,
             _ => {
                 return Err(error::Error::General(format!(
                     "Invalid consistency provided: {}",
@@ -168,7 +182,7 @@ impl FromCursor for Consistency {
 }
 
 impl Consistency {
-    /// Does this consistency require local dc.
+    /// Does this consistency require local dc, i.e. cross-dc nodes should not be contacted.
     #[inline]
     pub fn is_dc_local(self) -> bool {
         matches!(
@@ -176,6 +190,87 @@ impl Consistency {
             Consistency::LocalOne | Consistency::LocalQuorum | Consistency::LocalSerial
         )
     }
+
+    /// Is this consistency a serial one, i.e. usable as serial consistency of a query.
+    #[inline]
+    pub fn is_serial(self) -> bool {
+        matches!(self, Consistency::Serial | Consistency::LocalSerial)
+    }
+
+    /// Verifies if given consistency can be used as serial consistency of a query.
+    pub fn verify_serial(serial_consistency: Option<Consistency>) -> error::Result<()> {
+        match serial_consistency {
+            Some(serial_consistency) if !serial_consistency.is_serial() => {
+                Err(error::Error::General(format!(
+                    "Invalid serial consistency provided: {}",
+                    serial_consistency
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifies if this consistency can be used as the main consistency of given operation.
+    /// Allowed combinations are:
+    ///
+    /// | Consistency              | Read | Write | Counter write |
+    /// |--------------------------|------|-------|---------------|
+    /// | `ANY`                    | no   | yes   | no            |
+    /// | `SERIAL`, `LOCAL_SERIAL` | yes  | no    | no            |
+    /// | other                    | yes  | yes   | yes           |
+    ///
+    /// Serial reads see in-flight lightweight transactions, while serial writes need to be
+    /// conditional updates using regular consistency, with the serial one verified separately by
+    /// `verify_serial`.
+    pub fn verify_for_operation(self, operation: OperationKind) -> error::Result<()> {
+        let supported = match operation {
+            OperationKind::Read => self != Consistency::Any,
+            OperationKind::Write => !self.is_serial(),
+            OperationKind::CounterWrite => self != Consistency::Any && !self.is_serial(),
+        };
+
+        if supported {
+            Ok(())
+        } else {
+            Err(error::Error::General(format!(
+                "Consistency {} is not supported for {} operations",
+                self, operation
+            )))
+        }
+    }
+}
+
+/// Kind of operation executed with a given consistency. See `Consistency::verify_for_operation`.
+#[derive(Debug, PartialEq, Clone, Copy, Display, Eq, Hash)]
+pub enum OperationKind {
+    /// Reading data, e.g. `SELECT`.
+    #[display(fmt = "read")]
+    Read,
+    /// Modifying data, e.g. `INSERT`, `UPDATE` or `DELETE`.
+    #[display(fmt = "write")]
+    Write,
+    /// Modifying counter columns, e.g. a counter batch.
+    #[display(fmt = "counter write")]
+    CounterWrite,
+}
+
+impl OperationKind {
+    /// Tries to determine operation kind of a CQL statement by its leading keyword. Returns
+    /// `None` for other statements, e.g. schema changes or `BEGIN BATCH` blocks. Counter updates
+    /// are reported as `Write`, since they cannot be told apart without knowing the schema.
+    pub fn from_cql(query: &str) -> Option<Self> {
+        let keyword = query.split_whitespace().next()?;
+        if keyword.eq_ignore_ascii_case("SELECT") {
+            Some(OperationKind::Read)
+        } else if ["INSERT", "UPDATE", "DELETE"]
+            .iter()
+            .any(|write| keyword.eq_ignore_ascii_case(write))
+        {
+            Some(OperationKind::Write)
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -211,6 +306,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_consistency_predicates() {
+        assert!(Consistency::LocalOne.is_dc_local());
+        assert!(Consistency::LocalQuorum.is_dc_local());
+        assert!(Consistency::LocalSerial.is_dc_local());
+        assert!(!Consistency::Quorum.is_dc_local());
+        assert!(!Consistency::Serial.is_dc_local());
+
+        assert!(Consistency::Serial.is_serial());
+        assert!(Consistency::LocalSerial.is_serial());
+        assert!(!Consistency::LocalQuorum.is_serial());
+    }
+
     #[test]
     fn test_consistency_from() {
         assert_eq!(Consistency::try_from(0).unwrap(), Consistency::Any);
@@ -310,4 +418,108 @@ mod tests {
             Consistency::LocalOne
         );
     }
+
+    #[test]
+    fn test_consistency_from_str() {
+        assert_eq!(
+            Consistency::from_str("LOCAL_QUORUM").unwrap(),
+            Consistency::LocalQuorum
+        );
+        assert_eq!(
+            Consistency::from_str("each_quorum").unwrap(),
+            Consistency::EachQuorum
+        );
+        assert_eq!(
+            Consistency::from_str("LocalOne").unwrap(),
+            Consistency::LocalOne
+        );
+        assert_eq!(Consistency::from_str("one").unwrap(), Consistency::One);
+        assert!(matches!(
+            Consistency::from_str("local-quorum"),
+            Err(error::Error::General(_))
+        ));
+        assert!(Consistency::from_str("").is_err());
+    }
+
+    #[test]
+    fn test_verify_serial() {
+        assert!(Consistency::verify_serial(None).is_ok());
+        assert!(Consistency::verify_serial(Some(Consistency::Serial)).is_ok());
+        assert!(Consistency::verify_serial(Some(Consistency::LocalSerial)).is_ok());
+        assert!(Consistency::verify_serial(Some(Consistency::Quorum)).is_err());
+        assert!(Consistency::verify_serial(Some(Consistency::One)).is_err());
+    }
+
+    #[test]
+    fn test_verify_for_operation() {
+        assert!(Consistency::Any
+            .verify_for_operation(OperationKind::Read)
+            .is_err());
+        assert!(Consistency::Any
+            .verify_for_operation(OperationKind::Write)
+            .is_ok());
+        assert!(Consistency::Any
+            .verify_for_operation(OperationKind::CounterWrite)
+            .is_err());
+        assert!(Consistency::Serial
+            .verify_for_operation(OperationKind::Read)
+            .is_ok());
+        assert!(Consistency::LocalSerial
+            .verify_for_operation(OperationKind::Write)
+            .is_err());
+        assert!(Consistency::Serial
+            .verify_for_operation(OperationKind::CounterWrite)
+            .is_err());
+        assert!(Consistency::Quorum
+            .verify_for_operation(OperationKind::CounterWrite)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_operation_kind_from_cql() {
+        assert_eq!(
+            OperationKind::from_cql("  select * from ks.t"),
+            Some(OperationKind::Read)
+        );
+        assert_eq!(
+            OperationKind::from_cql("INSERT INTO ks.t (a) VALUES (1)"),
+            Some(OperationKind::Write)
+        );
+        assert_eq!(
+            OperationKind::from_cql("Update ks.t SET a = 1"),
+            Some(OperationKind::Write)
+        );
+        assert_eq!(OperationKind::from_cql("CREATE TABLE ks.t (a int)"), None);
+        assert_eq!(OperationKind::from_cql(""), None);
+    }
+
+    #[test]
+    fn test_consistency_display_round_trip() {
+        for value in 0x0000..=0x000A {
+            let consistency = Consistency::try_from(value).unwrap();
+            assert_eq!(
+                Consistency::from_str(&consistency.to_string()).unwrap(),
+                consistency
+            );
+        }
+
+        assert_eq!(Consistency::LocalQuorum.to_string(), "LOCAL_QUORUM");
+    }
+
+    #[test]
+    fn test_consistency_code_round_trip() {
+        for value in 0x0000..=0x000A {
+            let consistency = Consistency::try_from(value).unwrap();
+            assert_eq!(CIntShort::from(consistency), value);
+        }
+
+        assert!(matches!(
+            Consistency::try_from(0x000B),
+            Err(error::Error::UnknownConsistency(0x000B))
+        ));
+        assert!(matches!(
+            Consistency::try_from(-1),
+            Err(error::Error::UnknownConsistency(-1))
+        ));
+    }
 }
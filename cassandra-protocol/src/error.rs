@@ -1,19 +1,65 @@
-use std::fmt::{Debug, Display};
+use derive_more::Display;
+use std::fmt::Debug;
 use std::io;
 use std::net::SocketAddr;
 use std::result;
 use std::str::Utf8Error;
 use std::string::FromUtf8Error;
+use std::time::Duration;
 use thiserror::Error as ThisError;
 use uuid::Error as UuidError;
 
 use crate::compression::CompressionError;
-use crate::frame::message_error::ErrorBody;
-use crate::frame::Opcode;
-use crate::types::{CInt, CIntShort};
+use crate::frame::message_error::{AlreadyExistsError, ErrorBody};
+use crate::frame::message_request::RequestBody;
+use crate::frame::{Envelope, Opcode};
+use crate::types::{CBytesShort, CInt, CIntShort};
 
 pub type Result<T> = result::Result<T, Error>;
 
+/// Request which was still being executed when an operation timed out.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PendingRequest {
+    pub opcode: Opcode,
+    /// Query string of `QUERY` and `PREPARE` requests.
+    pub query: Option<String>,
+    /// Prepared statement id of `EXECUTE` requests.
+    pub prepared_id: Option<CBytesShort>,
+}
+
+impl PendingRequest {
+    /// Describes the request contained in given envelope.
+    pub fn from_envelope(envelope: &Envelope) -> Self {
+        let (query, prepared_id) = match envelope.request_body() {
+            Ok(RequestBody::Query(body)) => (Some(body.query), None),
+            Ok(RequestBody::Prepare(body)) => (Some(body.query), None),
+            Ok(RequestBody::Execute(body)) => (None, Some(body.id().clone())),
+            _ => (None, None),
+        };
+
+        PendingRequest {
+            opcode: envelope.opcode,
+            query,
+            prepared_id,
+        }
+    }
+}
+
+/// Client-side operation which exceeded its time limit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display)]
+pub enum TimeoutOperation {
+    /// Establishing a connection to given node.
+    #[display(fmt = "connecting to {}", _0)]
+    Connect(SocketAddr),
+    /// Executing a request, including all retries and speculative executions.
+    #[display(fmt = "executing request")]
+    Request,
+    /// Waiting for in-flight requests to complete when shutting down, with given requests still
+    /// pending.
+    #[display(fmt = "shutting down with {} pending requests", "_0.len()")]
+    Shutdown(Vec<PendingRequest>),
+}
+
 /// CDRS custom error type. CDRS expects two types of error - errors returned by Server
 /// and internal errors occurred within the driver itself. Occasionally `io::Error`
 /// is a type that represent internal error because due to implementation IO errors only
@@ -43,8 +89,11 @@ pub enum Error {
     #[error("Server {addr} error: {body:?}")]
     Server { body: ErrorBody, addr: SocketAddr },
     /// Timed out waiting for an operation to complete.
-    #[error("Timeout: {0}")]
-    Timeout(String),
+    #[error("Timeout {operation} after {elapsed:?}")]
+    Timeout {
+        operation: TimeoutOperation,
+        elapsed: Duration,
+    },
     /// Unknown consistency.
     #[error("Unknown consistency: {0}")]
     UnknownConsistency(CIntShort),
@@ -67,6 +116,7 @@ pub enum Error {
     #[error("Unexpected additional error info: {0}")]
     UnexpectedAdditionalErrorInfo(CInt),
     /// Unexpected write type.
+    #[deprecated(note = "unknown write types parse as WriteType::Other")]
     #[error("Unexpected write type: {0}")]
     UnexpectedWriteType(String),
     /// Expected a request opcode, got something else.
@@ -90,9 +140,34 @@ pub enum Error {
     /// Unexpected startup response.
     #[error("Unexpected startup response: {0}")]
     UnexpectedStartupResponse(Opcode),
+    /// Declared envelope body length is negative or exceeds the maximum allowed by the protocol.
+    #[error("Invalid envelope body length: {length} (maximum is {max} bytes)")]
+    InvalidEnvelopeBodyLength { length: CInt, max: usize },
 }
 
-pub fn column_is_empty_err<T: Display>(column_name: T) -> Error {
+impl Error {
+    /// Checks if the operation which caused this error can be retried. Server errors are
+    /// classified by their error code (see `ErrorBody::is_retryable`); client-side timeouts and IO
+    /// errors are considered retryable, while other internal errors are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Server { body, .. } => body.is_retryable(),
+            Error::Io(_) | Error::Timeout { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the keyspace and table names if a server responded with an `AlreadyExists` error
+    /// to a schema change, e.g. a `CREATE TABLE` without `IF NOT EXISTS`.
+    pub fn already_exists(&self) -> Option<&AlreadyExistsError> {
+        match self {
+            Error::Server { body, .. } => body.already_exists(),
+            _ => None,
+        }
+    }
+}
+
+pub fn column_is_empty_err<T: std::fmt::Display>(column_name: T) -> Error {
     Error::General(format!("Column or Udt property '{}' is empty", column_name))
 }
 
@@ -108,6 +183,8 @@ impl From<&str> for Error {
     }
 }
 
+// deprecated variants still need to be cloned
+#[allow(deprecated)]
 impl Clone for Error {
     fn clone(&self) -> Self {
         match self {
@@ -127,7 +204,10 @@ impl Clone for Error {
                 body: body.clone(),
                 addr: *addr,
             },
-            Error::Timeout(error) => Error::Timeout(error.clone()),
+            Error::Timeout { operation, elapsed } => Error::Timeout {
+                operation: operation.clone(),
+                elapsed: *elapsed,
+            },
             Error::UnknownConsistency(value) => Error::UnknownConsistency(*value),
             Error::UnknownServerEvent(value) => Error::UnknownServerEvent(value.clone()),
             Error::UnexpectedTopologyChangeType(value) => {
@@ -155,6 +235,103 @@ impl Clone for Error {
             },
             Error::UnexpectedAuthResponse(value) => Error::UnexpectedAuthResponse(*value),
             Error::UnexpectedStartupResponse(value) => Error::UnexpectedStartupResponse(*value),
+            Error::InvalidEnvelopeBodyLength { length, max } => Error::InvalidEnvelopeBodyLength {
+                length: *length,
+                max: *max,
+            },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::message_error::AdditionalErrorInfo;
+    use crate::frame::Version;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn should_display_timeout_operation() {
+        let error = Error::Timeout {
+            operation: TimeoutOperation::Connect(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                9042,
+            )),
+            elapsed: Duration::from_secs(1),
+        };
+
+        assert!(error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "Timeout connecting to 127.0.0.1:9042 after 1s"
+        );
+    }
+
+    #[test]
+    fn should_describe_pending_request() {
+        let envelope = Envelope::new_req_query(
+            "SELECT * FROM system.local".into(),
+            Default::default(),
+            None,
+            false,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Default::default(),
+            Version::V4,
+        );
+
+        let request = PendingRequest::from_envelope(&envelope);
+        assert_eq!(request.opcode, Opcode::Query);
+        assert_eq!(request.query.as_deref(), Some("SELECT * FROM system.local"));
+
+        let id = CBytesShort::new(vec![1, 2, 3]);
+        let envelope = Envelope::new_req_execute(
+            &id,
+            None,
+            &Default::default(),
+            Default::default(),
+            Version::V4,
+        );
+
+        let request = PendingRequest::from_envelope(&envelope);
+        assert_eq!(request.opcode, Opcode::Execute);
+        assert_eq!(request.query, None);
+        assert_eq!(request.prepared_id, Some(id));
+
+        let error = Error::Timeout {
+            operation: TimeoutOperation::Shutdown(vec![request]),
+            elapsed: Duration::from_secs(1),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Timeout shutting down with 1 pending requests after 1s"
+        );
+    }
+
+    #[test]
+    fn should_return_already_exists_error() {
+        let error = Error::Server {
+            body: ErrorBody {
+                error_code: 0x2400,
+                message: "Table ks.t already exists".into(),
+                additional_info: AdditionalErrorInfo::AlreadyExists(AlreadyExistsError {
+                    ks: "ks".into(),
+                    table: "t".into(),
+                }),
+            },
+            addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 9042),
+        };
+
+        let already_exists = error.already_exists().unwrap();
+        assert_eq!(already_exists.ks, "ks");
+        assert_eq!(already_exists.table, "t");
+        assert!(!already_exists.is_keyspace());
+        assert!(!error.is_retryable());
+
+        assert!(Error::General("error".into()).already_exists().is_none());
+    }
+}
@@ -1,16 +1,22 @@
 use bitflags::bitflags;
 use derivative::Derivative;
 use derive_more::{Constructor, Display};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::io::Cursor;
 use thiserror::Error;
 use uuid::Uuid;
 
 use crate::compression::{Compression, CompressionError};
+use crate::consistency::Consistency;
 use crate::frame::message_request::RequestBody;
 use crate::frame::message_response::ResponseBody;
 use crate::types::data_serialization_types::decode_timeuuid;
-use crate::types::{from_cursor_string_list, try_i16_from_bytes, try_i32_from_bytes, UUID_LEN};
+use crate::types::{
+    from_cursor_bytes_map, from_cursor_string_list, serialize_bytes_map, try_i16_from_bytes,
+    try_i32_from_bytes, verify_bytes_map, UUID_LEN,
+};
 
 pub use crate::frame::traits::*;
 
@@ -20,6 +26,32 @@ const ENVELOPE_HEADER_LEN: usize = 9;
 pub const STREAM_LEN: usize = 2;
 /// Number of body length bytes in accordance to protocol.
 pub const LENGTH_LEN: usize = 4;
+/// Maximum length of an envelope body in accordance to protocol (256MB).
+pub const MAX_ENVELOPE_BODY_LEN: usize = 256 * 1024 * 1024;
+
+/// Custom payload of an envelope, available since protocol V4. Can be used to communicate with
+/// custom server-side query handlers.
+pub type CustomPayload = BTreeMap<String, Vec<u8>>;
+
+/// Returns envelope body length if it's within protocol limits.
+#[inline]
+pub fn checked_envelope_body_len(length: i32) -> Option<usize> {
+    usize::try_from(length)
+        .ok()
+        .filter(|length| *length <= MAX_ENVELOPE_BODY_LEN)
+}
+
+/// Returns length of an encoded envelope body, if it's within protocol limits.
+fn encoded_envelope_body_len(length: usize) -> error::Result<i32> {
+    if length > MAX_ENVELOPE_BODY_LEN {
+        return Err(error::Error::General(format!(
+            "Envelope body too long: {} bytes (maximum is {} bytes)",
+            length, MAX_ENVELOPE_BODY_LEN
+        )));
+    }
+
+    Ok(length as i32)
+}
 
 pub mod events;
 pub mod frame_decoder;
@@ -94,6 +126,9 @@ pub struct Envelope {
     pub body: Vec<u8>,
     pub tracing_id: Option<Uuid>,
     pub warnings: Vec<String>,
+    /// Custom payload. Encoded only for requests, since tracing id and warnings of responses are
+    /// expected to be a part of the body when encoding.
+    pub custom_payload: CustomPayload,
 }
 
 impl Envelope {
@@ -118,19 +153,52 @@ impl Envelope {
             body,
             tracing_id,
             warnings,
+            custom_payload: Default::default(),
         }
     }
 
+    /// Sets custom payload sent with a request. Ignored for protocol versions below V4.
+    #[must_use]
+    pub fn with_custom_payload(mut self, custom_payload: CustomPayload) -> Self {
+        self.custom_payload = custom_payload;
+        self
+    }
+
     #[inline]
     pub fn request_body(&self) -> error::Result<RequestBody> {
         RequestBody::try_from(self.body.as_slice(), self.opcode, self.version)
     }
 
+    /// Creates a copy of this query, execute or batch request envelope with given consistency.
+    pub fn with_consistency(&self, consistency: Consistency) -> error::Result<Envelope> {
+        let mut body = self.request_body()?;
+        match &mut body {
+            RequestBody::Query(query) => query.query_params.consistency = consistency,
+            RequestBody::Execute(execute) => {
+                execute.query_parameters_mut().consistency = consistency
+            }
+            RequestBody::Batch(batch) => batch.batch_params.consistency = consistency,
+            _ => {
+                return Err(format!("Cannot change consistency of {} request!", self.opcode).into())
+            }
+        }
+
+        Ok(Envelope {
+            body: body.serialize_to_vec(self.version),
+            tracing_id: self.tracing_id,
+            warnings: self.warnings.clone(),
+            custom_payload: self.custom_payload.clone(),
+            ..*self
+        })
+    }
+
     #[inline]
     pub fn response_body(&self) -> error::Result<ResponseBody> {
         ResponseBody::try_from(self.body.as_slice(), self.opcode, self.version)
     }
 
+    /// Returns the trace id of a response to a request with the `TRACING` flag set. Tracing
+    /// details can be found in `system_traces.sessions` and `system_traces.events`.
     #[inline]
     pub fn tracing_id(&self) -> &Option<Uuid> {
         &self.tracing_id
@@ -141,6 +209,11 @@ impl Envelope {
         &self.warnings
     }
 
+    #[inline]
+    pub fn custom_payload(&self) -> &CustomPayload {
+        &self.custom_payload
+    }
+
     /// Parses the raw bytes of a cassandra envelope returning a [`ParsedEnvelope`] struct.
     /// The typical use case is reading from a buffer that may contain 0 or more envelopes and where
     /// the last envelope may be incomplete. The possible return values are:
@@ -155,7 +228,12 @@ impl Envelope {
             return Err(ParseEnvelopeError::NotEnoughBytes);
         }
 
-        let body_len = try_i32_from_bytes(&data[5..9]).unwrap() as usize;
+        let length = try_i32_from_bytes(&data[5..9]).unwrap();
+        let body_len =
+            checked_envelope_body_len(length).ok_or(ParseEnvelopeError::InvalidBodyLength {
+                length,
+                max: MAX_ENVELOPE_BODY_LEN,
+            })?;
         let envelope_len = ENVELOPE_HEADER_LEN + body_len;
         if data.len() < envelope_len {
             return Err(ParseEnvelopeError::NotEnoughBytes);
@@ -171,19 +249,25 @@ impl Envelope {
 
         let body_bytes = &data[ENVELOPE_HEADER_LEN..envelope_len];
 
+        // uncompressed bodies are borrowed directly and copied only once, into the result
         let full_body = if flags.contains(Flags::COMPRESSION) {
-            compression.decode(body_bytes.to_vec())
+            Cow::Owned(
+                compression
+                    .decode(body_bytes.to_vec())
+                    .map_err(ParseEnvelopeError::DecompressionError)?,
+            )
         } else {
-            Compression::None.decode(body_bytes.to_vec())
-        }
-        .map_err(ParseEnvelopeError::DecompressionError)?;
+            Cow::Borrowed(body_bytes)
+        };
 
         let body_len = full_body.len();
 
-        // Use cursor to get tracing id, warnings and actual body
-        let mut body_cursor = Cursor::new(full_body.as_slice());
+        // Use cursor to get tracing id, warnings, custom payload and actual body; requests can have
+        // tracing and warning flags, but don't carry corresponding data
+        let mut body_cursor = Cursor::new(full_body.as_ref());
+        let is_response = direction == Direction::Response;
 
-        let tracing_id = if flags.contains(Flags::TRACING) {
+        let tracing_id = if is_response && flags.contains(Flags::TRACING) {
             let mut tracing_bytes = [0; UUID_LEN];
             std::io::Read::read_exact(&mut body_cursor, &mut tracing_bytes).unwrap();
 
@@ -192,13 +276,20 @@ impl Envelope {
             None
         };
 
-        let warnings = if flags.contains(Flags::WARNING) {
+        let warnings = if is_response && flags.contains(Flags::WARNING) {
             from_cursor_string_list(&mut body_cursor)
                 .map_err(ParseEnvelopeError::InvalidWarnings)?
         } else {
             vec![]
         };
 
+        let custom_payload = if flags.contains(Flags::CUSTOM_PAYLOAD) {
+            from_cursor_bytes_map(&mut body_cursor, version)
+                .map_err(ParseEnvelopeError::InvalidCustomPayload)?
+        } else {
+            Default::default()
+        };
+
         let mut body = Vec::with_capacity(body_len - body_cursor.position() as usize);
 
         std::io::Read::read_to_end(&mut body_cursor, &mut body)
@@ -215,6 +306,7 @@ impl Envelope {
                 body,
                 tracing_id,
                 warnings,
+                custom_payload,
             },
         ))
     }
@@ -224,7 +316,12 @@ impl Envelope {
             return Err(CheckEnvelopeSizeError::NotEnoughBytes);
         }
 
-        let body_len = try_i32_from_bytes(&data[5..9]).unwrap() as usize;
+        let length = try_i32_from_bytes(&data[5..9]).unwrap();
+        let body_len =
+            checked_envelope_body_len(length).ok_or(CheckEnvelopeSizeError::InvalidBodyLength {
+                length,
+                max: MAX_ENVELOPE_BODY_LEN,
+            })?;
         let envelope_len = ENVELOPE_HEADER_LEN + body_len;
         if data.len() < envelope_len {
             return Err(CheckEnvelopeSizeError::NotEnoughBytes);
@@ -240,12 +337,34 @@ impl Envelope {
         let is_compressed = self.version < Version::V5 && compressor.is_compressed();
 
         let combined_version_byte = u8::from(self.version) | u8::from(self.direction);
-        let flag_byte = (if is_compressed {
+
+        let mut flags = if is_compressed {
             self.flags | Flags::COMPRESSION
         } else {
-            self.flags.intersection(Flags::COMPRESSION)
-        })
-        .bits();
+            self.flags.difference(Flags::COMPRESSION)
+        };
+
+        // custom payload precedes the actual request body
+        let body = if self.direction == Direction::Request {
+            if self.version >= Version::V4 && !self.custom_payload.is_empty() {
+                verify_bytes_map(&self.custom_payload, "Custom payload")?;
+                flags.insert(Flags::CUSTOM_PAYLOAD);
+
+                let mut body = Vec::with_capacity(self.body.len());
+                let mut cursor = Cursor::new(&mut body);
+                serialize_bytes_map(&mut cursor, &self.custom_payload, self.version);
+                body.extend_from_slice(&self.body);
+
+                Cow::Owned(body)
+            } else {
+                flags.remove(Flags::CUSTOM_PAYLOAD);
+                Cow::Borrowed(self.body.as_slice())
+            }
+        } else {
+            Cow::Borrowed(self.body.as_slice())
+        };
+
+        let flag_byte = flags.bits();
 
         let opcode_byte = u8::from(self.opcode);
 
@@ -257,15 +376,15 @@ impl Envelope {
         v.push(opcode_byte);
 
         if is_compressed {
-            let mut encoded_body = compressor.encode(&self.body)?;
+            let mut encoded_body = compressor.encode(&body)?;
 
-            let body_len = encoded_body.len() as i32;
+            let body_len = encoded_envelope_body_len(encoded_body.len())?;
             v.extend_from_slice(&body_len.to_be_bytes());
             v.append(&mut encoded_body);
         } else {
-            let body_len = self.body.len() as i32;
+            let body_len = encoded_envelope_body_len(body.len())?;
             v.extend_from_slice(&body_len.to_be_bytes());
-            v.extend_from_slice(&self.body);
+            v.extend_from_slice(&body);
         }
 
         Ok(v)
@@ -280,6 +399,9 @@ pub enum CheckEnvelopeSizeError {
     UnsupportedVersion(u8),
     #[error("Unsupported opcode: {0}")]
     UnsupportedOpcode(u8),
+    /// Declared body length is negative or exceeds the maximum allowed by the protocol.
+    #[error("Invalid envelope body length: {length} (maximum is {max} bytes)")]
+    InvalidBodyLength { length: i32, max: usize },
 }
 
 #[derive(Debug, Error)]
@@ -298,6 +420,11 @@ pub enum ParseEnvelopeError {
     InvalidUuid(uuid::Error),
     #[error("Invalid warnings: {0}")]
     InvalidWarnings(error::Error),
+    #[error("Invalid custom payload: {0}")]
+    InvalidCustomPayload(error::Error),
+    /// Declared body length is negative or exceeds the maximum allowed by the protocol.
+    #[error("Invalid envelope body length: {length} (maximum is {max} bytes)")]
+    InvalidBodyLength { length: i32, max: usize },
 }
 
 /// Protocol version.
@@ -476,6 +603,7 @@ mod tests {
     use crate::query::query_values::QueryValues;
     use crate::types::value::Value;
     use crate::types::CBytes;
+    use std::sync::Arc;
 
     #[test]
     fn test_frame_version_as_byte() {
@@ -634,11 +762,179 @@ mod tests {
             body: vec![],
             tracing_id: None,
             warnings: vec![],
+            custom_payload: Default::default(),
         };
         let body = ResponseBody::Ready;
         test_encode_decode_roundtrip_response(&raw_envelope, envelope, body);
     }
 
+    #[test]
+    fn test_compressed_envelope_roundtrip() {
+        let envelope = Envelope {
+            version: Version::V4,
+            direction: Direction::Request,
+            flags: Flags::empty(),
+            opcode: Opcode::Query,
+            stream_id: 0,
+            body: vec![0, 0, 0, 4, 98, 108, 97, 104, 0, 0, 64],
+            tracing_id: None,
+            warnings: vec![],
+            custom_payload: Default::default(),
+        };
+
+        for compression in [Compression::Snappy, Compression::Lz4] {
+            let encoded_envelope = envelope.encode_with(compression).unwrap();
+            assert!(Flags::from_bits_truncate(encoded_envelope[1]).contains(Flags::COMPRESSION));
+
+            let parsed = Envelope::from_buffer(&encoded_envelope, compression).unwrap();
+            assert_eq!(parsed.envelope_len, encoded_envelope.len());
+            assert_eq!(parsed.envelope.opcode, envelope.opcode);
+            assert_eq!(parsed.envelope.body, envelope.body);
+        }
+    }
+
+    #[test]
+    fn test_reject_invalid_custom_payload() {
+        let custom_payload: CustomPayload = vec![("a".repeat(u16::MAX as usize + 1), vec![])]
+            .into_iter()
+            .collect();
+        let envelope = Envelope::new_req_options(Version::V4).with_custom_payload(custom_payload);
+
+        assert!(envelope.encode_with(Compression::None).is_err());
+    }
+
+    #[test]
+    fn test_custom_payload_roundtrip() {
+        let custom_payload: CustomPayload =
+            vec![("key".to_string(), vec![1, 2])].into_iter().collect();
+        let envelope = Envelope::new(
+            Version::V4,
+            Direction::Request,
+            Flags::empty(),
+            Opcode::Query,
+            0,
+            vec![0, 0, 0, 4, 98, 108, 97, 104, 0, 0, 64],
+            None,
+            vec![],
+        )
+        .with_custom_payload(custom_payload.clone());
+
+        let encoded_envelope = envelope.encode_with(Compression::None).unwrap();
+        assert!(Flags::from_bits_truncate(encoded_envelope[1]).contains(Flags::CUSTOM_PAYLOAD));
+
+        let parsed = Envelope::from_buffer(&encoded_envelope, Compression::None).unwrap();
+        assert_eq!(parsed.envelope.custom_payload, custom_payload);
+        assert_eq!(parsed.envelope.body, envelope.body);
+
+        // not supported by older protocols
+        let envelope = Envelope {
+            version: Version::V3,
+            ..envelope
+        };
+        let encoded_envelope = envelope.encode_with(Compression::None).unwrap();
+        assert!(!Flags::from_bits_truncate(encoded_envelope[1]).contains(Flags::CUSTOM_PAYLOAD));
+        assert_eq!(encoded_envelope[9..], envelope.body[..]);
+    }
+
+    #[test]
+    fn test_tracing_id_only_in_responses() {
+        let tracing_id = Uuid::parse_str("8ac72ac0-0b67-11ec-8cb4-0d27dd45ba33").unwrap();
+        let mut body = tracing_id.as_bytes().to_vec();
+        body.extend_from_slice(&[1, 2, 3]);
+
+        let envelope = Envelope::new(
+            Version::V4,
+            Direction::Response,
+            Flags::TRACING,
+            Opcode::Ready,
+            0,
+            body.clone(),
+            None,
+            vec![],
+        );
+        let encoded_envelope = envelope.encode_with(Compression::None).unwrap();
+        let parsed = Envelope::from_buffer(&encoded_envelope, Compression::None).unwrap();
+        assert_eq!(parsed.envelope.tracing_id(), &Some(tracing_id));
+        assert_eq!(parsed.envelope.body, vec![1, 2, 3]);
+
+        // requests only ask for tracing
+        let envelope = Envelope {
+            direction: Direction::Request,
+            ..envelope
+        };
+        let encoded_envelope = envelope.encode_with(Compression::None).unwrap();
+        let parsed = Envelope::from_buffer(&encoded_envelope, Compression::None).unwrap();
+        assert_eq!(parsed.envelope.tracing_id(), &None);
+        assert_eq!(parsed.envelope.body, body);
+    }
+
+    #[test]
+    fn test_encoded_envelope_body_len() {
+        assert_eq!(
+            encoded_envelope_body_len(MAX_ENVELOPE_BODY_LEN).unwrap(),
+            MAX_ENVELOPE_BODY_LEN as i32
+        );
+        assert!(encoded_envelope_body_len(MAX_ENVELOPE_BODY_LEN + 1).is_err());
+    }
+
+    #[test]
+    fn test_reject_oversized_envelope() {
+        let mut data = vec![4, 0, 0, 0, 8];
+        data.extend_from_slice(&i32::MAX.to_be_bytes());
+
+        assert!(matches!(
+            Envelope::from_buffer(&data, Compression::None),
+            Err(ParseEnvelopeError::InvalidBodyLength {
+                length: i32::MAX,
+                max: MAX_ENVELOPE_BODY_LEN
+            })
+        ));
+        assert!(matches!(
+            Envelope::check_envelope_size(&data),
+            Err(CheckEnvelopeSizeError::InvalidBodyLength {
+                length: i32::MAX,
+                ..
+            })
+        ));
+
+        data[5..9].copy_from_slice(&(-1i32).to_be_bytes());
+        assert!(matches!(
+            Envelope::from_buffer(&data, Compression::None),
+            Err(ParseEnvelopeError::InvalidBodyLength { length: -1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_with_consistency() {
+        let envelope = Envelope::new_req_query(
+            "blah".into(),
+            Consistency::Quorum,
+            None,
+            false,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Flags::empty(),
+            Version::V4,
+        );
+
+        let changed = envelope.with_consistency(Consistency::One).unwrap();
+        match changed.request_body().unwrap() {
+            RequestBody::Query(query) => {
+                assert_eq!(query.query, "blah");
+                assert_eq!(query.query_params.consistency, Consistency::One);
+            }
+            body => panic!("Unexpected body: {:?}", body),
+        }
+
+        assert!(Envelope::new_req_options(Version::V4)
+            .with_consistency(Consistency::One)
+            .is_err());
+    }
+
     #[test]
     fn test_query_minimal() {
         let raw_envelope = [
@@ -653,6 +949,7 @@ mod tests {
             body: vec![0, 0, 0, 4, 98, 108, 97, 104, 0, 0, 64],
             tracing_id: None,
             warnings: vec![],
+            custom_payload: Default::default(),
         };
         let body = RequestBody::Query(BodyReqQuery {
             query: "blah".into(),
@@ -689,16 +986,17 @@ mod tests {
             ],
             tracing_id: None,
             warnings: vec![],
+            custom_payload: Default::default(),
         };
         let body = RequestBody::Query(BodyReqQuery {
             query: "some query".into(),
             query_params: QueryParams {
                 consistency: Consistency::Serial,
                 with_names: false,
-                values: Some(QueryValues::SimpleValues(vec![
+                values: Some(Arc::new(QueryValues::SimpleValues(vec![
                     Value::Some(vec![1, 2, 3]),
                     Value::Null,
-                ])),
+                ]))),
                 page_size: None,
                 paging_state: None,
                 serial_consistency: None,
@@ -721,13 +1019,14 @@ mod tests {
             body: vec![],
             tracing_id: None,
             warnings: vec![],
+            custom_payload: Default::default(),
         };
         let body = RequestBody::Query(BodyReqQuery {
             query: "another query".into(),
             query_params: QueryParams {
                 consistency: Consistency::Three,
                 with_names: true,
-                values: Some(QueryValues::NamedValues(
+                values: Some(Arc::new(QueryValues::NamedValues(
                     vec![
                         ("foo".to_string(), Value::Some(vec![11, 12, 13])),
                         ("bar".to_string(), Value::NotSet),
@@ -735,7 +1034,7 @@ mod tests {
                     ]
                     .into_iter()
                     .collect(),
-                )),
+                ))),
                 page_size: Some(4),
                 paging_state: Some(CBytes::new(vec![0, 1, 2, 3])),
                 serial_consistency: Some(Consistency::One),
@@ -806,6 +1105,7 @@ mod tests {
             ],
             tracing_id: None,
             warnings: vec![],
+            custom_payload: Default::default(),
         };
         let body = ResponseBody::Result(ResResultBody::Prepared(BodyResResultPrepared {
             id: CBytesShort::new(vec![
@@ -875,6 +1175,7 @@ mod tests {
             ],
             tracing_id: None,
             warnings: vec![],
+            custom_payload: Default::default(),
         };
 
         (envelope, raw_envelope)
@@ -898,6 +1199,7 @@ mod tests {
             body,
             tracing_id: None,
             warnings: vec![],
+            custom_payload: Default::default(),
         };
 
         (envelope, raw_envelope)
@@ -1,27 +1,20 @@
 use derive_more::{Constructor, Display};
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::io::{Cursor, Read};
 
-use crate::consistency::Consistency;
 use crate::frame::{Direction, Envelope, Flags, FromCursor, Opcode, Serialize, Version};
-use crate::query::QueryFlags;
-use crate::query::QueryValues;
+use crate::query::{BatchParams, QueryValues};
 use crate::types::value::Value;
-use crate::types::{
-    from_cursor_str, from_cursor_str_long, serialize_str, serialize_str_long, CBytesShort, CInt,
-    CIntShort, CLong,
-};
+use crate::types::{from_cursor_str_long, serialize_str_long, CBytesShort, CIntShort};
 use crate::{error, Error};
 
+/// Structure which represents body of Batch request. Batch-level parameters apply to all batched
+/// queries, which only carry their values.
 #[derive(Debug, Clone, Constructor, PartialEq, Eq)]
 pub struct BodyReqBatch {
     pub batch_type: BatchType,
     pub queries: Vec<BatchQuery>,
-    pub consistency: Consistency,
-    pub serial_consistency: Option<Consistency>,
-    pub timestamp: Option<CLong>,
-    pub keyspace: Option<String>,
-    pub now_in_seconds: Option<CInt>,
+    pub batch_params: BatchParams,
 }
 
 impl Serialize for BodyReqBatch {
@@ -36,44 +29,7 @@ impl Serialize for BodyReqBatch {
             query.serialize(cursor, version);
         }
 
-        let consistency: CIntShort = self.consistency.into();
-        consistency.serialize(cursor, version);
-
-        let mut flags = QueryFlags::empty();
-        if self.serial_consistency.is_some() {
-            flags.insert(QueryFlags::WITH_SERIAL_CONSISTENCY)
-        }
-
-        if self.timestamp.is_some() {
-            flags.insert(QueryFlags::WITH_DEFAULT_TIMESTAMP)
-        }
-
-        if self.keyspace.is_some() {
-            flags.insert(QueryFlags::WITH_KEYSPACE)
-        }
-
-        if self.now_in_seconds.is_some() {
-            flags.insert(QueryFlags::WITH_NOW_IN_SECONDS)
-        }
-
-        flags.serialize(cursor, version);
-
-        if let Some(serial_consistency) = self.serial_consistency {
-            let serial_consistency: CIntShort = serial_consistency.into();
-            serial_consistency.serialize(cursor, version);
-        }
-
-        if let Some(timestamp) = self.timestamp {
-            timestamp.serialize(cursor, version);
-        }
-
-        if let Some(keyspace) = &self.keyspace {
-            serialize_str(cursor, keyspace.as_str(), version);
-        }
-
-        if let Some(now_in_seconds) = self.now_in_seconds {
-            now_in_seconds.serialize(cursor, version);
-        }
+        self.batch_params.serialize(cursor, version);
     }
 }
 
@@ -90,42 +46,9 @@ impl FromCursor for BodyReqBatch {
             queries.push(BatchQuery::from_cursor(cursor, version)?);
         }
 
-        let consistency = CIntShort::from_cursor(cursor, version).and_then(TryInto::try_into)?;
-        let query_flags = QueryFlags::from_cursor(cursor, version)?;
+        let batch_params = BatchParams::from_cursor(cursor, version)?;
 
-        let serial_consistency = if query_flags.contains(QueryFlags::WITH_SERIAL_CONSISTENCY) {
-            Some(CIntShort::from_cursor(cursor, version).and_then(TryInto::try_into)?)
-        } else {
-            None
-        };
-
-        let timestamp = if query_flags.contains(QueryFlags::WITH_DEFAULT_TIMESTAMP) {
-            Some(CLong::from_cursor(cursor, version)?)
-        } else {
-            None
-        };
-
-        let keyspace = if query_flags.contains(QueryFlags::WITH_KEYSPACE) {
-            Some(from_cursor_str(cursor).map(|keyspace| keyspace.to_string())?)
-        } else {
-            None
-        };
-
-        let now_in_seconds = if query_flags.contains(QueryFlags::WITH_NOW_IN_SECONDS) {
-            Some(CInt::from_cursor(cursor, version)?)
-        } else {
-            None
-        };
-
-        Ok(BodyReqBatch::new(
-            batch_type,
-            queries,
-            consistency,
-            serial_consistency,
-            timestamp,
-            keyspace,
-            now_in_seconds,
-        ))
+        Ok(BodyReqBatch::new(batch_type, queries, batch_params))
     }
 }
 
@@ -257,7 +180,7 @@ mod tests {
     use crate::frame::message_batch::{BatchQuery, BatchQuerySubj, BatchType, BodyReqBatch};
     use crate::frame::traits::Serialize;
     use crate::frame::{FromCursor, Version};
-    use crate::query::QueryValues;
+    use crate::query::{BatchParams, QueryValues};
     use crate::types::prelude::Value;
 
     #[test]
@@ -278,9 +201,9 @@ mod tests {
         let body = BodyReqBatch::from_cursor(&mut cursor, Version::V4).unwrap();
         assert_eq!(body.batch_type, BatchType::Logged);
         assert!(body.queries.is_empty());
-        assert_eq!(body.consistency, Consistency::Any);
-        assert_eq!(body.serial_consistency, Some(Consistency::One));
-        assert_eq!(body.timestamp, Some(0x0102030405060708));
+        assert_eq!(body.batch_params.consistency, Consistency::Any);
+        assert_eq!(body.batch_params.serial_consistency, Some(Consistency::One));
+        assert_eq!(body.batch_params.timestamp, Some(0x0102030405060708));
     }
 
     #[test]
@@ -289,17 +212,17 @@ mod tests {
         let body = BodyReqBatch::new(
             BatchType::Logged,
             vec![],
-            Consistency::Any,
-            None,
-            None,
-            Some(keyspace.into()),
-            None,
+            BatchParams {
+                consistency: Consistency::Any,
+                keyspace: Some(keyspace.into()),
+                ..Default::default()
+            },
         );
 
         let data = body.serialize_to_vec(Version::V5);
         let body =
             BodyReqBatch::from_cursor(&mut Cursor::new(data.as_slice()), Version::V5).unwrap();
-        assert_eq!(body.keyspace, Some(keyspace.to_string()));
+        assert_eq!(body.batch_params.keyspace, Some(keyspace.to_string()));
     }
 
     #[test]
@@ -308,16 +231,16 @@ mod tests {
         let body = BodyReqBatch::new(
             BatchType::Logged,
             vec![],
-            Consistency::Any,
-            None,
-            None,
-            None,
-            Some(now_in_seconds),
+            BatchParams {
+                consistency: Consistency::Any,
+                now_in_seconds: Some(now_in_seconds),
+                ..Default::default()
+            },
         );
 
         let data = body.serialize_to_vec(Version::V5);
         let body =
             BodyReqBatch::from_cursor(&mut Cursor::new(data.as_slice()), Version::V5).unwrap();
-        assert_eq!(body.now_in_seconds, Some(now_in_seconds));
+        assert_eq!(body.batch_params.now_in_seconds, Some(now_in_seconds));
     }
 }
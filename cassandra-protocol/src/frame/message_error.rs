@@ -48,6 +48,32 @@ impl FromCursor for ErrorBody {
     }
 }
 
+impl ErrorBody {
+    /// Checks if the request which caused this error can be retried, possibly on another node.
+    /// True for timeouts, unavailable replicas and overloaded or bootstrapping coordinators; false
+    /// for errors which would repeat, e.g. syntax or authentication ones. Based only on the
+    /// error code. Note: retrying a timed out write is only safe for idempotent statements.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.error_code,
+            0x1000 // unavailable
+                | 0x1001 // overloaded
+                | 0x1002 // is bootstrapping
+                | 0x1100 // write timeout
+                | 0x1200 // read timeout
+        )
+    }
+
+    /// Returns the keyspace and table names if the request tried to create an already existing
+    /// keyspace or table.
+    pub fn already_exists(&self) -> Option<&AlreadyExistsError> {
+        match &self.additional_info {
+            AdditionalErrorInfo::AlreadyExists(already_exists) => Some(already_exists),
+            _ => None,
+        }
+    }
+}
+
 /// Protocol-dependent failure information. V5 contains a map of endpoint->code entries, while
 /// previous versions contain only error count.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -112,6 +138,8 @@ pub enum AdditionalErrorInfo {
     ReadFailure(ReadFailureError),
     FunctionFailure(FunctionFailureError),
     WriteFailure(WriteFailureError),
+    CdcWriteFailure,
+    CasWriteUnknown(CasWriteUnknownError),
     Syntax,
     Unauthorized,
     Invalid,
@@ -139,6 +167,9 @@ impl Serialize for AdditionalErrorInfo {
             AdditionalErrorInfo::WriteFailure(write_failure) => {
                 write_failure.serialize(cursor, version)
             }
+            AdditionalErrorInfo::CasWriteUnknown(cas_write_unknown) => {
+                cas_write_unknown.serialize(cursor, version)
+            }
             AdditionalErrorInfo::AlreadyExists(already_exists) => {
                 already_exists.serialize(cursor, version)
             }
@@ -176,6 +207,9 @@ impl AdditionalErrorInfo {
                 .map(AdditionalErrorInfo::FunctionFailure),
             0x1500 => WriteFailureError::from_cursor(cursor, version)
                 .map(AdditionalErrorInfo::WriteFailure),
+            0x1600 => Ok(AdditionalErrorInfo::CdcWriteFailure),
+            0x1700 => CasWriteUnknownError::from_cursor(cursor, version)
+                .map(AdditionalErrorInfo::CasWriteUnknown),
             0x2000 => Ok(AdditionalErrorInfo::Syntax),
             0x2100 => Ok(AdditionalErrorInfo::Unauthorized),
             0x2200 => Ok(AdditionalErrorInfo::Invalid),
@@ -229,7 +263,7 @@ impl FromCursor for UnavailableError {
 }
 
 /// Timeout exception during a write request.
-#[derive(Debug, PartialEq, Copy, Clone, Ord, PartialOrd, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Ord, PartialOrd, Eq, Hash)]
 pub struct WriteTimeoutError {
     /// Consistency level of query.
     pub cl: Consistency,
@@ -460,9 +494,47 @@ impl FromCursor for WriteFailureError {
     }
 }
 
+/// An exception occurred due to contended Compare And Set write/update. The CAS operation was only
+/// partially completed and the operation may or may not get completed by the contending CAS
+/// write or SERIAL/LOCAL_SERIAL read. Available since protocol V5.
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Copy, Clone, Hash)]
+pub struct CasWriteUnknownError {
+    /// Consistency level of query.
+    pub cl: Consistency,
+    /// The number of nodes having acknowledged the request.
+    pub received: CInt,
+    /// The number of replicas whose acknowledgement is required to achieve `cl`.
+    pub block_for: CInt,
+}
+
+impl Serialize for CasWriteUnknownError {
+    fn serialize(&self, cursor: &mut Cursor<&mut Vec<u8>>, version: Version) {
+        self.cl.serialize(cursor, version);
+        self.received.serialize(cursor, version);
+        self.block_for.serialize(cursor, version);
+    }
+}
+
+impl FromCursor for CasWriteUnknownError {
+    fn from_cursor(
+        cursor: &mut Cursor<&[u8]>,
+        version: Version,
+    ) -> error::Result<CasWriteUnknownError> {
+        let cl = Consistency::from_cursor(cursor, version)?;
+        let received = CInt::from_cursor(cursor, version)?;
+        let block_for = CInt::from_cursor(cursor, version)?;
+
+        Ok(CasWriteUnknownError {
+            cl,
+            received,
+            block_for,
+        })
+    }
+}
+
 /// Describes the type of the write that failed.
-/// [Read more...](https://github.com/apache/cassandra/blob/trunk/doc/native_protocol_v4.spec#L1118)
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Display)]
+/// [Read more...](https://github.com/apache/cassandra/blob/trunk/doc/native_protocol_v5.spec#L1277)
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Clone, Display)]
 pub enum WriteType {
     /// The write was a non-batched non-counter write
     Simple,
@@ -477,6 +549,16 @@ pub enum WriteType {
     /// The failure occurred during the write to the batch log when a (logged) batch
     /// write was requested.
     BatchLog,
+    /// The timeout occurred during the Compare And Set write/update.
+    Cas,
+    /// The timeout occurred when a write involves a materialized view update and failure to
+    /// acquire the local view lock.
+    View,
+    /// The timeout occurred when CDC space is exhausted.
+    Cdc,
+    /// Write type unknown to this version of the protocol implementation.
+    #[display(fmt = "{}", _0)]
+    Other(String),
 }
 
 impl Serialize for WriteType {
@@ -487,20 +569,27 @@ impl Serialize for WriteType {
             WriteType::UnloggedBatch => serialize_str(cursor, "UNLOGGED_BATCH", version),
             WriteType::Counter => serialize_str(cursor, "COUNTER", version),
             WriteType::BatchLog => serialize_str(cursor, "BATCH_LOG", version),
+            WriteType::Cas => serialize_str(cursor, "CAS", version),
+            WriteType::View => serialize_str(cursor, "VIEW", version),
+            WriteType::Cdc => serialize_str(cursor, "CDC", version),
+            WriteType::Other(write_type) => serialize_str(cursor, write_type, version),
         }
     }
 }
 
 impl FromCursor for WriteType {
     fn from_cursor(cursor: &mut Cursor<&[u8]>, _version: Version) -> error::Result<WriteType> {
-        match from_cursor_str(cursor)? {
-            "SIMPLE" => Ok(WriteType::Simple),
-            "BATCH" => Ok(WriteType::Batch),
-            "UNLOGGED_BATCH" => Ok(WriteType::UnloggedBatch),
-            "COUNTER" => Ok(WriteType::Counter),
-            "BATCH_LOG" => Ok(WriteType::BatchLog),
-            wt => Err(Error::UnexpectedWriteType(wt.into())),
-        }
+        Ok(match from_cursor_str(cursor)? {
+            "SIMPLE" => WriteType::Simple,
+            "BATCH" => WriteType::Batch,
+            "UNLOGGED_BATCH" => WriteType::UnloggedBatch,
+            "COUNTER" => WriteType::Counter,
+            "BATCH_LOG" => WriteType::BatchLog,
+            "CAS" => WriteType::Cas,
+            "VIEW" => WriteType::View,
+            "CDC" => WriteType::Cdc,
+            wt => WriteType::Other(wt.into()),
+        })
     }
 }
 
@@ -511,10 +600,19 @@ pub struct AlreadyExistsError {
     /// Represents either the keyspace that already exists,
     /// or the keyspace in which the table that already exists is.
     pub ks: String,
-    /// Represents the name of the table that already exists.
+    /// Represents the name of the table that already exists. Empty if the keyspace itself already
+    /// exists.
     pub table: String,
 }
 
+impl AlreadyExistsError {
+    /// Checks if it's the keyspace which already exists, rather than a table in it.
+    #[inline]
+    pub fn is_keyspace(&self) -> bool {
+        self.table.is_empty()
+    }
+}
+
 impl Serialize for AlreadyExistsError {
     fn serialize(&self, cursor: &mut Cursor<&mut Vec<u8>>, version: Version) {
         serialize_str(cursor, &self.ks, version);
@@ -709,6 +807,55 @@ mod error_tests {
         test_encode_decode(bytes, expected);
     }
 
+    #[test]
+    fn write_timeout_write_types() {
+        for (name, write_type) in [
+            ("CAS", WriteType::Cas),
+            ("VIEW", WriteType::View),
+            ("CDC", WriteType::Cdc),
+            ("NEW_TYPE", WriteType::Other("NEW_TYPE".into())),
+        ] {
+            let mut bytes = vec![
+                0,
+                0,
+                17,
+                0, // write timeout
+                0,
+                3,
+                102,
+                111,
+                111, // message - foo
+                //
+                // timeout error
+                0,
+                0, // consistency any
+                0,
+                0,
+                0,
+                1, // received
+                0,
+                0,
+                0,
+                1, // block_for
+                0,
+                name.len() as u8,
+            ];
+            bytes.extend_from_slice(name.as_bytes());
+
+            let expected = ErrorBody {
+                error_code: 0x1100,
+                message: "foo".into(),
+                additional_info: AdditionalErrorInfo::WriteTimeout(WriteTimeoutError {
+                    cl: Consistency::Any,
+                    received: 1,
+                    block_for: 1,
+                    write_type,
+                }),
+            };
+            test_encode_decode(&bytes, expected);
+        }
+    }
+
     #[test]
     fn read_timeout() {
         let bytes = &[
@@ -761,6 +908,41 @@ mod error_tests {
         test_encode_decode(bytes, expected);
     }
 
+    #[test]
+    fn cdc_write_failure() {
+        let bytes = &[
+            0, 0, 22, 0, // cdc write failure
+            0, 3, 102, 111, 111, // message - foo
+        ];
+        let expected = ErrorBody {
+            error_code: 0x1600,
+            message: "foo".into(),
+            additional_info: AdditionalErrorInfo::CdcWriteFailure,
+        };
+        test_encode_decode(bytes, expected);
+    }
+
+    #[test]
+    fn cas_write_unknown() {
+        let bytes = &[
+            0, 0, 23, 0, // cas write unknown
+            0, 3, 102, 111, 111, // message - foo
+            0, 8, // consistency serial
+            0, 0, 0, 1, // received
+            0, 0, 0, 2, // block_for
+        ];
+        let expected = ErrorBody {
+            error_code: 0x1700,
+            message: "foo".into(),
+            additional_info: AdditionalErrorInfo::CasWriteUnknown(CasWriteUnknownError {
+                cl: Consistency::Serial,
+                received: 1,
+                block_for: 2,
+            }),
+        };
+        test_encode_decode(bytes, expected);
+    }
+
     #[test]
     fn syntax() {
         let bytes = &[
@@ -816,4 +998,34 @@ mod error_tests {
         };
         test_encode_decode(bytes, expected);
     }
+
+    #[test]
+    fn is_retryable() {
+        let error = |error_code| ErrorBody {
+            error_code,
+            message: "foo".into(),
+            additional_info: AdditionalErrorInfo::Server,
+        };
+
+        for error_code in [0x1000, 0x1001, 0x1002, 0x1100, 0x1200] {
+            assert!(error(error_code).is_retryable());
+        }
+
+        for error_code in [0x000A, 0x0100, 0x2000, 0x2100, 0x2200, 0x2400] {
+            assert!(!error(error_code).is_retryable());
+        }
+
+        let addr = "127.0.0.1:9042".parse().unwrap();
+        assert!(Error::Server {
+            body: error(0x1200),
+            addr
+        }
+        .is_retryable());
+        assert!(!Error::Server {
+            body: error(0x2000),
+            addr
+        }
+        .is_retryable());
+        assert!(!Error::General("foo".into()).is_retryable());
+    }
 }
@@ -48,6 +48,23 @@ pub struct BodyReqExecuteOwned {
     query_parameters: QueryParams,
 }
 
+impl BodyReqExecuteOwned {
+    #[inline]
+    pub fn id(&self) -> &CBytesShort {
+        &self.id
+    }
+
+    #[inline]
+    pub fn query_parameters(&self) -> &QueryParams {
+        &self.query_parameters
+    }
+
+    #[inline]
+    pub fn query_parameters_mut(&mut self) -> &mut QueryParams {
+        &mut self.query_parameters
+    }
+}
+
 impl FromCursor for BodyReqExecuteOwned {
     fn from_cursor(cursor: &mut Cursor<&[u8]>, version: Version) -> error::Result<Self> {
         let id = CBytesShort::from_cursor(cursor, version)?;
@@ -1,4 +1,5 @@
 use std::io::Cursor;
+use std::sync::Arc;
 
 use crate::consistency::Consistency;
 use crate::error;
@@ -35,7 +36,7 @@ impl BodyReqQuery {
             query_params: QueryParams {
                 consistency,
                 with_names,
-                values,
+                values: values.map(Arc::new),
                 page_size,
                 paging_state,
                 serial_consistency,
@@ -91,8 +92,6 @@ impl Envelope {
         flags: Flags,
         version: Version,
     ) -> Envelope {
-        let direction = Direction::Request;
-        let opcode = Opcode::Query;
         let body = BodyReqQuery::new(
             query,
             consistency,
@@ -106,33 +105,29 @@ impl Envelope {
             now_in_seconds,
         );
 
+        Envelope::new_req_query_body(body, flags, version)
+    }
+
+    #[inline]
+    pub fn new_query(query: Query, flags: Flags, version: Version) -> Envelope {
+        let body = BodyReqQuery {
+            query: query.query,
+            query_params: query.params,
+        };
+
+        Envelope::new_req_query_body(body, flags, version)
+    }
+
+    fn new_req_query_body(body: BodyReqQuery, flags: Flags, version: Version) -> Envelope {
         Envelope::new(
             version,
-            direction,
+            Direction::Request,
             flags,
-            opcode,
+            Opcode::Query,
             0,
             body.serialize_to_vec(version),
             None,
             vec![],
         )
     }
-
-    #[inline]
-    pub fn new_query(query: Query, flags: Flags, version: Version) -> Envelope {
-        Envelope::new_req_query(
-            query.query,
-            query.params.consistency,
-            query.params.values,
-            query.params.with_names,
-            query.params.page_size,
-            query.params.paging_state,
-            query.params.serial_consistency,
-            query.params.timestamp,
-            query.params.keyspace,
-            query.params.now_in_seconds,
-            flags,
-            version,
-        )
-    }
 }
@@ -127,6 +127,14 @@ impl ResponseBody {
         }
     }
 
+    /// Unwraps body and returns BodyResSupported, which contains options supported by the server.
+    pub fn into_supported(self) -> Option<BodyResSupported> {
+        match self {
+            ResponseBody::Supported(supported) => Some(supported),
+            _ => None,
+        }
+    }
+
     /// Unwraps body and returns BodyResEvent.
     pub fn into_server_event(self) -> Option<BodyResEvent> {
         match self {
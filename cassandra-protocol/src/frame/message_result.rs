@@ -352,7 +352,9 @@ impl FromCursor for RowsMetadata {
         let columns_count = CInt::from_cursor(cursor, version)?;
 
         let paging_state = if flags.contains(RowsMetadataFlags::HAS_MORE_PAGES) {
-            Some(CBytes::from_cursor(cursor, version)?)
+            // paging state is usually passed around to subsequent requests, so make it cheap to
+            // clone
+            Some(CBytes::shared_from_cursor(cursor, version)?)
         } else {
             None
         };
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::io::{Cursor, Read};
 
+use crate::compression::Compression;
 use crate::error;
 use crate::frame::{FromCursor, Version};
 use crate::types::{from_cursor_str, from_cursor_string_list, serialize_str, CIntShort, SHORT_LEN};
@@ -12,6 +14,50 @@ pub struct BodyResSupported {
     pub data: HashMap<String, Vec<String>>,
 }
 
+pub const COMPRESSION: &str = "COMPRESSION";
+pub const CQL_VERSION: &str = "CQL_VERSION";
+pub const PROTOCOL_VERSIONS: &str = "PROTOCOL_VERSIONS";
+
+impl BodyResSupported {
+    /// Returns CQL versions supported by the server, e.g. `3.4.5`.
+    pub fn cql_versions(&self) -> &[String] {
+        self.values(CQL_VERSION)
+    }
+
+    /// Returns names of compression algorithms supported by the server, e.g. `lz4`.
+    pub fn compressions(&self) -> &[String] {
+        self.values(COMPRESSION)
+    }
+
+    /// Returns protocol versions supported by the server and known to the driver. Beta versions,
+    /// which require the beta protocol flag, are not included. Older servers don't report
+    /// supported protocol versions at all.
+    pub fn protocol_versions(&self) -> Vec<Version> {
+        self.values(PROTOCOL_VERSIONS)
+            .iter()
+            .filter(|version| !version.ends_with("-beta"))
+            .filter_map(|version| version.split('/').next()?.parse::<u8>().ok())
+            .filter_map(|version| Version::try_from(version).ok())
+            .collect()
+    }
+
+    /// Checks if the server supports given compression. No compression is always supported.
+    pub fn supports_compression(&self, compression: Compression) -> bool {
+        match compression.as_str() {
+            Some(compression) => self
+                .data
+                .get(COMPRESSION)
+                .map(|supported| supported.iter().any(|supported| supported == compression))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+
+    fn values(&self, key: &str) -> &[String] {
+        self.data.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
 impl Serialize for BodyResSupported {
     fn serialize(&self, cursor: &mut Cursor<&mut Vec<u8>>, version: Version) {
         (self.data.len() as CIntShort).serialize(cursor, version);
@@ -77,4 +123,39 @@ mod tests {
             assert_eq!(buffer, bytes);
         }
     }
+
+    #[test]
+    fn should_check_supported_compression() {
+        let mut data: HashMap<String, Vec<String>> = HashMap::new();
+        data.insert(COMPRESSION.into(), vec!["snappy".into()]);
+        let supported = BodyResSupported { data };
+
+        assert!(supported.supports_compression(Compression::Snappy));
+        assert!(supported.supports_compression(Compression::None));
+        assert!(!supported.supports_compression(Compression::Lz4));
+        assert!(!BodyResSupported::default().supports_compression(Compression::Snappy));
+    }
+
+    #[test]
+    fn should_return_supported_options() {
+        let mut data: HashMap<String, Vec<String>> = HashMap::new();
+        data.insert(CQL_VERSION.into(), vec!["3.4.5".into()]);
+        data.insert(
+            PROTOCOL_VERSIONS.into(),
+            vec![
+                "3/v3".into(),
+                "4/v4".into(),
+                "5/v5".into(),
+                "6/v6-beta".into(),
+            ],
+        );
+        let supported = BodyResSupported { data };
+
+        assert_eq!(supported.cql_versions(), ["3.4.5".to_string()]);
+        assert!(supported.compressions().is_empty());
+        assert_eq!(
+            supported.protocol_versions(),
+            vec![Version::V3, Version::V4, Version::V5]
+        );
+    }
 }
@@ -1,8 +1,21 @@
 #[macro_export]
 
-/// Transforms arguments to values consumed by queries.
+/// Transforms arguments to values consumed by queries. Positional arguments build
+/// `QueryValues::SimpleValues`, while `name => value` pairs build `QueryValues::NamedValues`. Each
+/// value is converted using `Into<Value>`.
+///
+/// ```
+/// use cassandra_protocol::query::QueryValues;
+/// use cassandra_protocol::query_values;
+///
+/// let values = query_values!(1i32, "text", 2.5f64);
+/// assert_eq!(values.len(), 3);
+///
+/// let values = query_values!("id" => 1i32, "name" => "text",);
+/// assert!(matches!(values, QueryValues::NamedValues(_)));
+/// ```
 macro_rules! query_values {
-    ($($value:expr),*) => {
+    ($($value:expr),* $(,)?) => {
         {
             use cassandra_protocol::types::value::Value;
             use cassandra_protocol::query::QueryValues;
@@ -13,7 +26,7 @@ macro_rules! query_values {
             QueryValues::SimpleValues(values)
         }
     };
-    ($($name:expr => $value:expr),*) => {
+    ($($name:expr => $value:expr),* $(,)?) => {
         {
             use cassandra_protocol::types::value::Value;
             use cassandra_protocol::query::QueryValues;
@@ -1376,6 +1389,32 @@ macro_rules! as_rust_type {
             ))),
         }
     };
+    ($data_type_option:ident, $data_value:ident, NaiveDate) => {
+        match $data_type_option.id {
+            ColType::Date => match $data_value.as_slice() {
+                Some(ref bytes) => decode_naive_date(bytes).map(Some).map_err(Into::into),
+                None => Ok(None),
+            },
+            _ => Err(Error::General(format!(
+                "Invalid conversion. \
+                 Cannot convert {:?} into NaiveDate (valid types: Date).",
+                $data_type_option.id
+            ))),
+        }
+    };
+    ($data_type_option:ident, $data_value:ident, NaiveTime) => {
+        match $data_type_option.id {
+            ColType::Time => match $data_value.as_slice() {
+                Some(ref bytes) => decode_naive_time(bytes).map(Some).map_err(Into::into),
+                None => Ok(None),
+            },
+            _ => Err(Error::General(format!(
+                "Invalid conversion. \
+                 Cannot convert {:?} into NaiveTime (valid types: Time).",
+                $data_type_option.id
+            ))),
+        }
+    };
     ($data_type_option:ident, $data_value:ident, DateTime<Utc>) => {
         match $data_type_option.id {
             ColType::Timestamp => match $data_value.as_slice() {
@@ -1,3 +1,4 @@
+pub mod batch_params;
 pub mod batch_query_builder;
 pub mod prepare_flags;
 pub mod prepared_query;
@@ -5,8 +6,10 @@ pub mod query_flags;
 pub mod query_params;
 pub mod query_params_builder;
 pub mod query_values;
+pub mod timestamp_generator;
 pub mod utils;
 
+pub use crate::query::batch_params::BatchParams;
 pub use crate::query::batch_query_builder::{BatchQueryBuilder, QueryBatch};
 pub use crate::query::prepare_flags::PrepareFlags;
 pub use crate::query::prepared_query::PreparedQuery;
@@ -14,6 +17,7 @@ pub use crate::query::query_flags::QueryFlags;
 pub use crate::query::query_params::QueryParams;
 pub use crate::query::query_params_builder::QueryParamsBuilder;
 pub use crate::query::query_values::QueryValues;
+pub use crate::query::timestamp_generator::{MonotonicTimestampGenerator, TimestampGenerator};
 
 /// Structure that represents CQL query and parameters which will be applied during
 /// its execution
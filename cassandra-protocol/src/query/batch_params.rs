@@ -0,0 +1,175 @@
+use std::convert::TryInto;
+use std::io::Cursor;
+
+use crate::consistency::Consistency;
+use crate::frame::traits::FromCursor;
+use crate::frame::{Serialize, Version};
+use crate::query::query_flags::QueryFlags;
+use crate::types::{from_cursor_str, serialize_str, verify_short_string, CInt, CIntShort, CLong};
+use crate::Error;
+
+/// Parameters of a batch, applied to all contained statements. Batched statements only carry
+/// their values, so these are the only consistency, serial consistency, timestamp, keyspace and
+/// "now" sent with a batch.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BatchParams {
+    /// Cassandra consistency level.
+    pub consistency: Consistency,
+    /// Serial `Consistency`, used for conditional updates.
+    pub serial_consistency: Option<Consistency>,
+    /// Timestamp in microseconds since the Unix epoch. See `TimestampGenerator`.
+    pub timestamp: Option<CLong>,
+    /// Keyspace the batch should be executed in. Only sent with protocol V5 and above.
+    pub keyspace: Option<String>,
+    /// Represents the current time (now) for the batch. Only sent with protocol V5 and above.
+    pub now_in_seconds: Option<CInt>,
+    /// Can the batch be safely executed multiple times, e.g. when retrying. Not sent to the
+    /// server. Computed by `BatchQueryBuilder` from contained statements.
+    pub is_idempotent: bool,
+}
+
+impl BatchParams {
+    /// Returns flags which will be sent to the server along with these parameters when using
+    /// given protocol version.
+    pub fn flags(&self, version: Version) -> QueryFlags {
+        let mut flags = QueryFlags::empty();
+
+        if self.serial_consistency.is_some() {
+            flags.insert(QueryFlags::WITH_SERIAL_CONSISTENCY);
+        }
+
+        if self.timestamp.is_some() {
+            flags.insert(QueryFlags::WITH_DEFAULT_TIMESTAMP);
+        }
+
+        if version >= Version::V5 {
+            if self.keyspace.is_some() {
+                flags.insert(QueryFlags::WITH_KEYSPACE);
+            }
+
+            if self.now_in_seconds.is_some() {
+                flags.insert(QueryFlags::WITH_NOW_IN_SECONDS);
+            }
+        }
+
+        flags
+    }
+
+    /// Verifies parameters which would be rejected by the server or couldn't be serialized, i.e.
+    /// serial consistency other than `SERIAL` or `LOCAL_SERIAL`, and too long keyspace name.
+    pub fn verify(&self) -> Result<(), Error> {
+        Consistency::verify_serial(self.serial_consistency)?;
+
+        match &self.keyspace {
+            Some(keyspace) => verify_short_string(keyspace, "Keyspace name"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Serialize for BatchParams {
+    fn serialize(&self, cursor: &mut Cursor<&mut Vec<u8>>, version: Version) {
+        let consistency: CIntShort = self.consistency.into();
+        consistency.serialize(cursor, version);
+
+        let flags = self.flags(version);
+        flags.serialize(cursor, version);
+
+        if let Some(serial_consistency) = self.serial_consistency {
+            let serial_consistency: CIntShort = serial_consistency.into();
+            serial_consistency.serialize(cursor, version);
+        }
+
+        if let Some(timestamp) = self.timestamp {
+            timestamp.serialize(cursor, version);
+        }
+
+        if let Some(keyspace) = &self.keyspace {
+            if flags.contains(QueryFlags::WITH_KEYSPACE) {
+                serialize_str(cursor, keyspace.as_str(), version);
+            }
+        }
+
+        if let Some(now_in_seconds) = self.now_in_seconds {
+            if flags.contains(QueryFlags::WITH_NOW_IN_SECONDS) {
+                now_in_seconds.serialize(cursor, version);
+            }
+        }
+    }
+}
+
+impl FromCursor for BatchParams {
+    fn from_cursor(cursor: &mut Cursor<&[u8]>, version: Version) -> Result<BatchParams, Error> {
+        let consistency = CIntShort::from_cursor(cursor, version).and_then(TryInto::try_into)?;
+        let flags = QueryFlags::from_cursor(cursor, version)?;
+
+        let serial_consistency = if flags.contains(QueryFlags::WITH_SERIAL_CONSISTENCY) {
+            Some(CIntShort::from_cursor(cursor, version).and_then(TryInto::try_into)?)
+        } else {
+            None
+        };
+
+        let timestamp = if flags.contains(QueryFlags::WITH_DEFAULT_TIMESTAMP) {
+            Some(CLong::from_cursor(cursor, version)?)
+        } else {
+            None
+        };
+
+        let keyspace = if flags.contains(QueryFlags::WITH_KEYSPACE) {
+            Some(from_cursor_str(cursor).map(|keyspace| keyspace.to_string())?)
+        } else {
+            None
+        };
+
+        let now_in_seconds = if flags.contains(QueryFlags::WITH_NOW_IN_SECONDS) {
+            Some(CInt::from_cursor(cursor, version)?)
+        } else {
+            None
+        };
+
+        Ok(BatchParams {
+            consistency,
+            serial_consistency,
+            timestamp,
+            keyspace,
+            now_in_seconds,
+            is_idempotent: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_send_v5_params_with_v5() {
+        let params = BatchParams {
+            consistency: Consistency::Quorum,
+            timestamp: Some(1),
+            keyspace: Some("ks".into()),
+            now_in_seconds: Some(2),
+            ..Default::default()
+        };
+
+        let data = params.serialize_to_vec(Version::V4);
+        assert_eq!(data, vec![0, 4, 0x20, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let data = params.serialize_to_vec(Version::V5);
+        assert_eq!(
+            BatchParams::from_cursor(&mut Cursor::new(data.as_slice()), Version::V5).unwrap(),
+            params
+        );
+    }
+
+    #[test]
+    fn should_verify_keyspace_length() {
+        let params = |keyspace: String| BatchParams {
+            keyspace: Some(keyspace),
+            ..Default::default()
+        };
+
+        assert!(params("ks".into()).verify().is_ok());
+        assert!(params("k".repeat(u16::MAX as usize + 1)).verify().is_err());
+    }
+}
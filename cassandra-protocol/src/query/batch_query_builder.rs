@@ -1,8 +1,8 @@
-use crate::consistency::Consistency;
+use crate::consistency::{Consistency, OperationKind};
 use crate::error::{Error as CError, Result as CResult};
 use crate::frame::message_batch::{BatchQuery, BatchQuerySubj, BatchType, BodyReqBatch};
-use crate::query::{PreparedQuery, QueryValues};
-use crate::types::{CInt, CLong};
+use crate::query::{BatchParams, PreparedQuery, QueryValues};
+use crate::types::{verify_short_count, CInt, CLong};
 
 pub type QueryBatch = BodyReqBatch;
 
@@ -10,11 +10,9 @@ pub type QueryBatch = BodyReqBatch;
 pub struct BatchQueryBuilder {
     batch_type: BatchType,
     queries: Vec<BatchQuery>,
-    consistency: Consistency,
-    serial_consistency: Option<Consistency>,
-    timestamp: Option<CLong>,
-    keyspace: Option<String>,
-    now_in_seconds: Option<CInt>,
+    batch_params: BatchParams,
+    non_counter_query: Option<String>,
+    is_idempotent: bool,
 }
 
 impl Default for BatchQueryBuilder {
@@ -22,11 +20,9 @@ impl Default for BatchQueryBuilder {
         BatchQueryBuilder {
             batch_type: BatchType::Logged,
             queries: vec![],
-            consistency: Consistency::One,
-            serial_consistency: None,
-            timestamp: None,
-            keyspace: None,
-            now_in_seconds: None,
+            batch_params: BatchParams::default(),
+            non_counter_query: None,
+            is_idempotent: true,
         }
     }
 }
@@ -36,69 +32,111 @@ impl BatchQueryBuilder {
         Default::default()
     }
 
+    /// Sets batch type. Defaults to `BatchType::Logged`.
     #[must_use]
     pub fn with_batch_type(mut self, batch_type: BatchType) -> Self {
         self.batch_type = batch_type;
         self
     }
 
-    /// Add a query (non-prepared one)
+    /// Add a query (non-prepared one), which makes the whole batch non-idempotent.
     #[must_use]
-    pub fn add_query<T: Into<String>>(mut self, query: T, values: QueryValues) -> Self {
-        self.queries.push(BatchQuery {
-            subject: BatchQuerySubj::QueryString(query.into()),
-            values,
-        });
-        self
+    pub fn add_query<T: Into<String>>(self, query: T, values: QueryValues) -> Self {
+        self.add_query_with_idempotence(query, values, false)
     }
 
-    /// Add a query (prepared one)
+    /// Add an idempotent query (non-prepared one). See `query::utils::infer_idempotence`.
     #[must_use]
-    pub fn add_query_prepared(mut self, query: &PreparedQuery, values: QueryValues) -> Self {
-        self.queries.push(BatchQuery {
-            subject: BatchQuerySubj::PreparedId(query.id.clone()),
-            values,
-        });
-        self
+    pub fn add_idempotent_query<T: Into<String>>(self, query: T, values: QueryValues) -> Self {
+        self.add_query_with_idempotence(query, values, true)
+    }
+
+    /// Add a query (prepared one), which makes the whole batch non-idempotent.
+    #[must_use]
+    pub fn add_query_prepared(self, query: &PreparedQuery, values: QueryValues) -> Self {
+        self.add_query_prepared_with_idempotence(query, values, false)
+    }
+
+    /// Add an idempotent query (prepared one). See `query::utils::infer_idempotence`.
+    #[must_use]
+    pub fn add_idempotent_query_prepared(self, query: &PreparedQuery, values: QueryValues) -> Self {
+        self.add_query_prepared_with_idempotence(query, values, true)
     }
 
     #[must_use]
     pub fn clear_queries(mut self) -> Self {
         self.queries = vec![];
+        self.non_counter_query = None;
+        self.is_idempotent = true;
         self
     }
 
     #[must_use]
     pub fn with_consistency(mut self, consistency: Consistency) -> Self {
-        self.consistency = consistency;
+        self.batch_params.consistency = consistency;
         self
     }
 
     #[must_use]
     pub fn with_serial_consistency(mut self, serial_consistency: Consistency) -> Self {
-        self.serial_consistency = Some(serial_consistency);
+        self.batch_params.serial_consistency = Some(serial_consistency);
         self
     }
 
     #[must_use]
     pub fn with_timestamp(mut self, timestamp: CLong) -> Self {
-        self.timestamp = Some(timestamp);
+        self.batch_params.timestamp = Some(timestamp);
         self
     }
 
     #[must_use]
     pub fn with_keyspace(mut self, keyspace: String) -> Self {
-        self.keyspace = Some(keyspace);
+        self.batch_params.keyspace = Some(keyspace);
         self
     }
 
     #[must_use]
     pub fn with_now_in_seconds(mut self, now_in_seconds: CInt) -> Self {
-        self.now_in_seconds = Some(now_in_seconds);
+        self.batch_params.now_in_seconds = Some(now_in_seconds);
         self
     }
 
-    pub fn build(self) -> CResult<BodyReqBatch> {
+    /// Sets all batch-level parameters at once.
+    #[must_use]
+    pub fn with_batch_params(mut self, batch_params: BatchParams) -> Self {
+        self.batch_params = batch_params;
+        self
+    }
+
+    /// Builds the batch, verifying its parameters. Counter batches are checked not to contain
+    /// `INSERT` statements, which can never modify counters; other mixing of counter and
+    /// non-counter statements can't be detected without the schema and is rejected by the server.
+    ///
+    /// The batch is idempotent only if all its statements are, and counter batches are never
+    /// idempotent, since counter updates cannot be safely applied twice.
+    pub fn build(mut self) -> CResult<BodyReqBatch> {
+        self.batch_params.verify()?;
+
+        verify_short_count(self.queries.len(), "batch statements")?;
+        for query in &self.queries {
+            query.values.verify("batch statement values")?;
+        }
+
+        if let (BatchType::Counter, Some(query)) = (self.batch_type, &self.non_counter_query) {
+            return Err(CError::General(format!(
+                "Counter batch cannot contain non-counter statement: {}",
+                query
+            )));
+        }
+
+        self.batch_params.consistency.verify_for_operation(
+            if self.batch_type == BatchType::Counter {
+                OperationKind::CounterWrite
+            } else {
+                OperationKind::Write
+            },
+        )?;
+
         let with_names_for_values = self.queries.iter().all(|q| q.values.has_names());
 
         if !with_names_for_values {
@@ -112,14 +150,134 @@ impl BatchQueryBuilder {
             }
         }
 
+        self.batch_params.is_idempotent =
+            self.is_idempotent && self.batch_type != BatchType::Counter;
+
         Ok(BodyReqBatch {
             batch_type: self.batch_type,
             queries: self.queries,
-            consistency: self.consistency,
-            serial_consistency: self.serial_consistency,
-            timestamp: self.timestamp,
-            keyspace: self.keyspace,
-            now_in_seconds: self.now_in_seconds,
+            batch_params: self.batch_params,
         })
     }
+
+    fn add_query_with_idempotence<T: Into<String>>(
+        mut self,
+        query: T,
+        values: QueryValues,
+        is_idempotent: bool,
+    ) -> Self {
+        let query = query.into();
+        self.track_non_counter_query(&query);
+        self.is_idempotent &= is_idempotent;
+
+        self.queries.push(BatchQuery {
+            subject: BatchQuerySubj::QueryString(query),
+            values,
+        });
+        self
+    }
+
+    fn add_query_prepared_with_idempotence(
+        mut self,
+        query: &PreparedQuery,
+        values: QueryValues,
+        is_idempotent: bool,
+    ) -> Self {
+        self.track_non_counter_query(&query.query);
+        self.is_idempotent &= is_idempotent;
+
+        self.queries.push(BatchQuery {
+            subject: BatchQuerySubj::PreparedId(query.id.clone()),
+            values,
+        });
+        self
+    }
+
+    fn track_non_counter_query(&mut self, query: &str) {
+        let is_insert = query
+            .split_whitespace()
+            .next()
+            .map(|keyword| keyword.eq_ignore_ascii_case("INSERT"))
+            .unwrap_or(false);
+
+        if is_insert && self.non_counter_query.is_none() {
+            self.non_counter_query = Some(query.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_default_to_logged_batch() {
+        let batch = BatchQueryBuilder::new().build().unwrap();
+        assert_eq!(batch.batch_type, BatchType::Logged);
+        assert_eq!(u8::from(batch.batch_type), 0);
+    }
+
+    #[test]
+    fn should_reject_inserts_in_counter_batch() {
+        let builder = BatchQueryBuilder::new()
+            .with_batch_type(BatchType::Counter)
+            .add_query(
+                "UPDATE t SET c = c + 1 WHERE id = 1",
+                QueryValues::SimpleValues(vec![]),
+            )
+            .add_query(
+                "insert INTO t (id) VALUES (1)",
+                QueryValues::SimpleValues(vec![]),
+            );
+        assert!(builder.build().is_err());
+
+        let batch = BatchQueryBuilder::new()
+            .with_batch_type(BatchType::Unlogged)
+            .add_query(
+                "INSERT INTO t (id) VALUES (1)",
+                QueryValues::SimpleValues(vec![]),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(u8::from(batch.batch_type), 1);
+    }
+
+    #[test]
+    fn should_be_idempotent_only_with_idempotent_statements() {
+        let batch = BatchQueryBuilder::new()
+            .add_idempotent_query(
+                "INSERT INTO t (id) VALUES (1)",
+                QueryValues::SimpleValues(vec![]),
+            )
+            .add_idempotent_query(
+                "DELETE FROM t WHERE id = 2",
+                QueryValues::SimpleValues(vec![]),
+            )
+            .build()
+            .unwrap();
+        assert!(batch.batch_params.is_idempotent);
+
+        let batch = BatchQueryBuilder::new()
+            .add_idempotent_query(
+                "INSERT INTO t (id) VALUES (1)",
+                QueryValues::SimpleValues(vec![]),
+            )
+            .add_query(
+                "INSERT INTO t (id, u) VALUES (2, uuid())",
+                QueryValues::SimpleValues(vec![]),
+            )
+            .build()
+            .unwrap();
+        assert!(!batch.batch_params.is_idempotent);
+
+        let batch = BatchQueryBuilder::new()
+            .with_batch_type(BatchType::Counter)
+            .add_idempotent_query(
+                "UPDATE t SET c = c + 1 WHERE id = 1",
+                QueryValues::SimpleValues(vec![]),
+            )
+            .build()
+            .unwrap();
+        assert!(!batch.batch_params.is_idempotent);
+    }
 }
@@ -2,6 +2,9 @@ use arc_swap::ArcSwapOption;
 use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 
+use crate::error::Result;
+use crate::query::utils::serialize_routing_key_with_indexes;
+use crate::query::QueryValues;
 use crate::types::CBytesShort;
 
 #[derive(Debug)]
@@ -13,6 +16,20 @@ pub struct PreparedQuery {
     pub result_metadata_id: ArcSwapOption<CBytesShort>,
 }
 
+impl PreparedQuery {
+    /// Extracts the routing key from given bound values, using partition key indexes returned by
+    /// the server when preparing the statement. Returns `None` if the indexes are unknown, values
+    /// are named or some partition key value is missing.
+    pub fn routing_key(&self, values: &QueryValues) -> Result<Option<Vec<u8>>> {
+        match values {
+            QueryValues::SimpleValues(values) => {
+                serialize_routing_key_with_indexes(values, &self.pk_indexes)
+            }
+            QueryValues::NamedValues(_) => Ok(None),
+        }
+    }
+}
+
 impl Clone for PreparedQuery {
     fn clone(&self) -> Self {
         Self {
@@ -67,3 +84,44 @@ impl Hash for PreparedQuery {
         self.result_metadata_id.load().hash(state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use arc_swap::ArcSwapOption;
+    use std::collections::HashMap;
+
+    use crate::query::{PreparedQuery, QueryValues};
+    use crate::types::value::Value;
+    use crate::types::CBytesShort;
+
+    fn prepared_query(pk_indexes: Vec<i16>) -> PreparedQuery {
+        PreparedQuery {
+            id: CBytesShort::new(vec![1]),
+            query: "".into(),
+            keyspace: None,
+            pk_indexes,
+            result_metadata_id: ArcSwapOption::empty(),
+        }
+    }
+
+    #[test]
+    fn should_extract_routing_key_from_bound_values() {
+        let values = QueryValues::SimpleValues(vec![Value::new(2i32), Value::new(1i32)]);
+
+        assert_eq!(
+            prepared_query(vec![1]).routing_key(&values).unwrap(),
+            Some(vec![0, 0, 0, 1])
+        );
+        assert_eq!(
+            prepared_query(vec![1, 0]).routing_key(&values).unwrap(),
+            Some(vec![0, 4, 0, 0, 0, 1, 0, 0, 4, 0, 0, 0, 2, 0])
+        );
+        assert_eq!(prepared_query(vec![]).routing_key(&values).unwrap(), None);
+        assert_eq!(
+            prepared_query(vec![0])
+                .routing_key(&QueryValues::NamedValues(HashMap::new()))
+                .unwrap(),
+            None
+        );
+    }
+}
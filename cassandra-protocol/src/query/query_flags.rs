@@ -1,4 +1,5 @@
 use bitflags::bitflags;
+use std::fmt::{Display, Formatter};
 use std::io::{Cursor, Read};
 
 use crate::error;
@@ -35,6 +36,46 @@ impl Default for QueryFlags {
     }
 }
 
+const FLAG_NAMES: [(QueryFlags, &str); 9] = [
+    (QueryFlags::VALUE, "VALUE"),
+    (QueryFlags::SKIP_METADATA, "SKIP_METADATA"),
+    (QueryFlags::PAGE_SIZE, "PAGE_SIZE"),
+    (QueryFlags::WITH_PAGING_STATE, "WITH_PAGING_STATE"),
+    (
+        QueryFlags::WITH_SERIAL_CONSISTENCY,
+        "WITH_SERIAL_CONSISTENCY",
+    ),
+    (QueryFlags::WITH_DEFAULT_TIMESTAMP, "WITH_DEFAULT_TIMESTAMP"),
+    (QueryFlags::WITH_NAMES_FOR_VALUES, "WITH_NAMES_FOR_VALUES"),
+    (QueryFlags::WITH_KEYSPACE, "WITH_KEYSPACE"),
+    (QueryFlags::WITH_NOW_IN_SECONDS, "WITH_NOW_IN_SECONDS"),
+];
+
+impl Display for QueryFlags {
+    /// Lists names of active flags separated by `|`, or `(empty)` if no flags are set.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut names = FLAG_NAMES
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .peekable();
+
+        if names.peek().is_none() {
+            return write!(f, "(empty)");
+        }
+
+        for (index, name) in names.enumerate() {
+            if index > 0 {
+                write!(f, " | ")?;
+            }
+
+            write!(f, "{}", name)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Serialize for QueryFlags {
     fn serialize(&self, cursor: &mut Cursor<&mut Vec<u8>>, version: Version) {
         if version >= Version::V5 {
@@ -60,3 +101,19 @@ impl FromCursor for QueryFlags {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_display_flags() {
+        assert_eq!(QueryFlags::empty().to_string(), "(empty)");
+        assert_eq!(QueryFlags::PAGE_SIZE.to_string(), "PAGE_SIZE");
+        assert_eq!(
+            (QueryFlags::VALUE | QueryFlags::WITH_NAMES_FOR_VALUES | QueryFlags::WITH_KEYSPACE)
+                .to_string(),
+            "VALUE | WITH_NAMES_FOR_VALUES | WITH_KEYSPACE"
+        );
+    }
+}
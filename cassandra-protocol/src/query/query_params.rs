@@ -1,12 +1,15 @@
 use std::collections::HashMap;
 use std::io::Cursor;
+use std::sync::Arc;
 
 use crate::consistency::Consistency;
 use crate::frame::traits::FromCursor;
 use crate::frame::{Serialize, Version};
 use crate::query::query_flags::QueryFlags;
 use crate::query::query_values::QueryValues;
-use crate::types::{from_cursor_str, serialize_str, value::Value, CInt, CIntShort};
+use crate::types::{
+    from_cursor_str, serialize_str, value::Value, verify_short_string, CInt, CIntShort,
+};
 use crate::types::{CBytes, CLong};
 use crate::Error;
 
@@ -17,15 +20,16 @@ pub struct QueryParams {
     pub consistency: Consistency,
     /// Were values provided with names
     pub with_names: bool,
-    /// Array of values.
-    pub values: Option<QueryValues>,
+    /// Array of values. Values are kept in shared storage, so cloning parameters, e.g. for
+    /// subsequent pages or retries, doesn't copy potentially large values.
+    pub values: Option<Arc<QueryValues>>,
     /// Page size.
     pub page_size: Option<CInt>,
     /// Array of bytes which represents paging state.
     pub paging_state: Option<CBytes>,
     /// Serial `Consistency`.
     pub serial_consistency: Option<Consistency>,
-    /// Timestamp.
+    /// Timestamp in microseconds since the Unix epoch. See `TimestampGenerator`.
     pub timestamp: Option<CLong>,
     /// Keyspace indicating the keyspace that the query should be executed in. It supersedes the
     /// keyspace that the connection is bound to, if any.
@@ -36,7 +40,51 @@ pub struct QueryParams {
 }
 
 impl QueryParams {
-    fn flags(&self) -> QueryFlags {
+    /// Verifies parameters which would be rejected by the server or result in undefined behavior:
+    /// serial consistency other than `SERIAL` or `LOCAL_SERIAL`, serial reads (`SERIAL` or
+    /// `LOCAL_SERIAL` as the main consistency) with a different serial consistency, non-positive
+    /// page size, and more bound values, or longer value names or keyspace name than the protocol
+    /// allows.
+    pub fn verify(&self) -> Result<(), Error> {
+        Consistency::verify_serial(self.serial_consistency)?;
+
+        if let Some(values) = &self.values {
+            values.verify("query values")?;
+        }
+
+        if let Some(keyspace) = &self.keyspace {
+            verify_short_string(keyspace, "Keyspace name")?;
+        }
+
+        if let Some(serial_consistency) = self.serial_consistency {
+            if self.consistency.is_serial() && serial_consistency != self.consistency {
+                return Err(Error::General(format!(
+                    "Serial read at {} conflicts with serial consistency {}",
+                    self.consistency, serial_consistency
+                )));
+            }
+        }
+
+        match self.page_size {
+            Some(page_size) if page_size <= 0 => {
+                Err(Error::General("page size must be positive".into()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Clears the paging state, so the query starts from the first page when executed again. All
+    /// other parameters are preserved, as should be any routing information kept alongside (e.g.
+    /// the token used by the load balancer), since the query still targets the same partition.
+    #[inline]
+    pub fn reset_paging(&mut self) {
+        self.paging_state = None;
+    }
+
+    /// Returns flags which will be sent to the server along with these parameters when using
+    /// given protocol version. Keyspace and "now" are only supported by protocol V5 and above,
+    /// and are not sent otherwise.
+    pub fn flags(&self, version: Version) -> QueryFlags {
         let mut flags = QueryFlags::empty();
 
         if self.values.is_some() {
@@ -63,12 +111,14 @@ impl QueryParams {
             flags.insert(QueryFlags::WITH_DEFAULT_TIMESTAMP);
         }
 
-        if self.keyspace.is_some() {
-            flags.insert(QueryFlags::WITH_KEYSPACE);
-        }
+        if version >= Version::V5 {
+            if self.keyspace.is_some() {
+                flags.insert(QueryFlags::WITH_KEYSPACE);
+            }
 
-        if self.now_in_seconds.is_some() {
-            flags.insert(QueryFlags::WITH_NOW_IN_SECONDS);
+            if self.now_in_seconds.is_some() {
+                flags.insert(QueryFlags::WITH_NOW_IN_SECONDS);
+            }
         }
 
         flags
@@ -80,7 +130,8 @@ impl Serialize for QueryParams {
         let consistency: CIntShort = self.consistency.into();
         consistency.serialize(cursor, version);
 
-        let flag_bits = self.flags().bits();
+        let flags = self.flags(version);
+        let flag_bits = flags.bits();
         if version >= Version::V5 {
             flag_bits.serialize(cursor, version);
         } else {
@@ -111,11 +162,15 @@ impl Serialize for QueryParams {
         }
 
         if let Some(keyspace) = &self.keyspace {
-            serialize_str(cursor, keyspace.as_str(), version);
+            if flags.contains(QueryFlags::WITH_KEYSPACE) {
+                serialize_str(cursor, keyspace.as_str(), version);
+            }
         }
 
         if let Some(now_in_seconds) = self.now_in_seconds {
-            now_in_seconds.serialize(cursor, version);
+            if flags.contains(QueryFlags::WITH_NOW_IN_SECONDS) {
+                now_in_seconds.serialize(cursor, version);
+            }
         }
     }
 }
@@ -136,13 +191,13 @@ impl FromCursor for QueryParams {
                         Value::from_cursor(cursor, version)?,
                     );
                 }
-                Some(QueryValues::NamedValues(map))
+                Some(Arc::new(QueryValues::NamedValues(map)))
             } else {
                 let mut vec = Vec::with_capacity(number_of_values as usize);
                 for _ in 0..number_of_values {
                     vec.push(Value::from_cursor(cursor, version)?);
                 }
-                Some(QueryValues::SimpleValues(vec))
+                Some(Arc::new(QueryValues::SimpleValues(vec)))
             }
         } else {
             None
@@ -199,3 +254,166 @@ impl FromCursor for QueryParams {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::QueryParamsBuilder;
+    use crate::types::MAX_SHORT_COUNT;
+
+    #[test]
+    fn should_compute_flags() {
+        assert_eq!(
+            QueryParams::default().flags(Version::V4),
+            QueryFlags::empty()
+        );
+
+        let params = QueryParams {
+            with_names: true,
+            values: Some(Arc::new(QueryValues::NamedValues(Default::default()))),
+            paging_state: Some(CBytes::new(vec![1])),
+            timestamp: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            params.flags(Version::V4),
+            QueryFlags::VALUE
+                | QueryFlags::WITH_NAMES_FOR_VALUES
+                | QueryFlags::WITH_PAGING_STATE
+                | QueryFlags::WITH_DEFAULT_TIMESTAMP
+        );
+    }
+
+    #[test]
+    fn should_compare_by_value() {
+        let params = |page_size| {
+            QueryParamsBuilder::new()
+                .with_consistency(Consistency::Quorum)
+                .with_values(QueryValues::SimpleValues(vec![Value::new(1)]))
+                .with_page_size(page_size)
+                .with_paging_state(CBytes::new(vec![1, 2]))
+                .build()
+                .unwrap()
+        };
+
+        assert_eq!(params(10), params(10));
+        assert_ne!(params(10), params(20));
+    }
+
+    #[test]
+    fn should_send_serial_read_consistency_as_main_consistency() {
+        let params = QueryParamsBuilder::new()
+            .with_consistency(Consistency::LocalSerial)
+            .build()
+            .unwrap();
+
+        assert_eq!(params.serialize_to_vec(Version::V4), vec![0, 9, 0]);
+    }
+
+    #[test]
+    fn should_verify_serial_read() {
+        let params = |serial_consistency| QueryParams {
+            consistency: Consistency::Serial,
+            serial_consistency,
+            ..Default::default()
+        };
+
+        assert!(params(None).verify().is_ok());
+        assert!(params(Some(Consistency::Serial)).verify().is_ok());
+        assert!(params(Some(Consistency::LocalSerial)).verify().is_err());
+    }
+
+    #[test]
+    fn should_only_send_keyspace_with_v5() {
+        let params = QueryParams {
+            keyspace: Some("ks".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(params.serialize_to_vec(Version::V4), vec![0, 1, 0]);
+        assert_eq!(
+            params.serialize_to_vec(Version::V5),
+            vec![0, 1, 0, 0, 0, 0x80, 0, 2, b'k', b's']
+        );
+
+        let data = params.serialize_to_vec(Version::V5);
+        assert_eq!(
+            QueryParams::from_cursor(&mut Cursor::new(data.as_slice()), Version::V5).unwrap(),
+            params
+        );
+    }
+
+    #[test]
+    fn should_send_now_in_seconds_after_timestamp_with_v5() {
+        let params = QueryParams {
+            timestamp: Some(1),
+            now_in_seconds: Some(2),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            params.serialize_to_vec(Version::V4),
+            vec![0, 1, 0x20, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+        assert_eq!(
+            params.serialize_to_vec(Version::V5),
+            vec![0, 1, 0, 0, 1, 0x20, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2]
+        );
+    }
+
+    #[test]
+    fn should_reset_paging() {
+        let mut params = QueryParams {
+            page_size: Some(10),
+            paging_state: Some(CBytes::new(vec![1])),
+            ..Default::default()
+        };
+
+        params.reset_paging();
+        assert_eq!(
+            params,
+            QueryParams {
+                page_size: Some(10),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn should_verify_page_size() {
+        let params = |page_size| QueryParams {
+            page_size,
+            ..Default::default()
+        };
+
+        assert!(params(None).verify().is_ok());
+        assert!(params(Some(1)).verify().is_ok());
+        assert!(params(Some(0)).verify().is_err());
+        assert!(params(Some(-1)).verify().is_err());
+    }
+
+    #[test]
+    fn should_verify_values_count() {
+        let params = |count| QueryParams {
+            values: Some(Arc::new(QueryValues::SimpleValues(vec![
+                Value::NotSet;
+                count
+            ]))),
+            ..Default::default()
+        };
+
+        assert!(params(MAX_SHORT_COUNT).verify().is_ok());
+        assert!(params(MAX_SHORT_COUNT + 1).verify().is_err());
+    }
+
+    #[test]
+    fn should_verify_keyspace_length() {
+        let params = |keyspace: String| QueryParams {
+            keyspace: Some(keyspace),
+            ..Default::default()
+        };
+
+        assert!(params("ks".into()).verify().is_ok());
+        assert!(params("k".repeat(MAX_SHORT_COUNT + 1)).verify().is_err());
+    }
+}
@@ -1,12 +1,14 @@
-use super::{QueryFlags, QueryParams, QueryValues};
+use super::{QueryFlags, QueryParams, QueryValues, TimestampGenerator};
 use crate::consistency::Consistency;
+use crate::error;
 use crate::types::{CBytes, CInt, CLong};
+use std::sync::Arc;
 
 #[derive(Debug, Default)]
 pub struct QueryParamsBuilder {
     consistency: Consistency,
     flags: Option<QueryFlags>,
-    values: Option<QueryValues>,
+    values: Option<Arc<QueryValues>>,
     with_names: bool,
     page_size: Option<CInt>,
     paging_state: Option<CBytes>,
@@ -38,7 +40,14 @@ impl QueryParamsBuilder {
 
     /// Sets new query values.
     #[must_use]
-    pub fn with_values(mut self, values: QueryValues) -> Self {
+    pub fn with_values(self, values: QueryValues) -> Self {
+        self.with_shared_values(Arc::new(values))
+    }
+
+    /// Sets new query values kept in shared storage, which can be reused by multiple queries
+    /// without copying.
+    #[must_use]
+    pub fn with_shared_values(mut self, values: Arc<QueryValues>) -> Self {
         self.with_names = values.has_names();
         self.values = Some(values);
         self.flags = self.flags.or_else(|| {
@@ -84,13 +93,19 @@ impl QueryParamsBuilder {
         self
     }
 
-    /// Sets new timestamp.
+    /// Sets new timestamp, in microseconds since the Unix epoch.
     #[must_use]
     pub fn with_timestamp(mut self, timestamp: CLong) -> Self {
         self.timestamp = Some(timestamp);
         self
     }
 
+    /// Sets new timestamp taken from given generator.
+    #[must_use]
+    pub fn with_generated_timestamp(self, generator: &dyn TimestampGenerator) -> Self {
+        self.with_timestamp(generator.next_timestamp())
+    }
+
     /// Overrides used keyspace.
     #[must_use]
     pub fn with_keyspace(mut self, keyspace: String) -> Self {
@@ -105,10 +120,22 @@ impl QueryParamsBuilder {
         self
     }
 
-    /// Finalizes query building process and returns query itself
-    #[must_use]
-    pub fn build(self) -> QueryParams {
-        QueryParams {
+    /// Finalizes query building process and returns query itself. Fails if given parameters are
+    /// inconsistent with each other, e.g. names for values are requested without named values, or
+    /// invalid, e.g. serial consistency is neither `SERIAL` nor `LOCAL_SERIAL` or page size is not
+    /// positive.
+    pub fn build(self) -> error::Result<QueryParams> {
+        match &self.values {
+            Some(values) if values.has_names() != self.with_names => {
+                return Err("Values with names flag doesn't match given values!".into());
+            }
+            None if self.with_names => {
+                return Err("Values with names flag set, but no values given!".into());
+            }
+            _ => {}
+        }
+
+        let params = QueryParams {
             consistency: self.consistency,
             values: self.values,
             with_names: self.with_names,
@@ -118,6 +145,91 @@ impl QueryParamsBuilder {
             timestamp: self.timestamp,
             keyspace: self.keyspace,
             now_in_seconds: self.now_in_seconds,
-        }
+        };
+
+        params.verify()?;
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Version;
+    use crate::types::value::Value;
+    use std::collections::HashMap;
+
+    #[test]
+    fn should_build_params_with_values() {
+        let params = QueryParamsBuilder::new()
+            .with_consistency(Consistency::Quorum)
+            .with_values(QueryValues::SimpleValues(vec![Value::Null]))
+            .with_page_size(10)
+            .build()
+            .unwrap();
+
+        assert_eq!(params.consistency, Consistency::Quorum);
+        assert!(!params.with_names);
+        assert_eq!(params.page_size, Some(10));
+    }
+
+    #[test]
+    fn should_share_values_between_clones() {
+        let values = Arc::new(QueryValues::SimpleValues(vec![Value::Some(vec![0; 1024])]));
+        let params = QueryParamsBuilder::new()
+            .with_shared_values(values.clone())
+            .build()
+            .unwrap();
+        let cloned = params.clone();
+
+        assert!(Arc::ptr_eq(params.values.as_ref().unwrap(), &values));
+        assert!(Arc::ptr_eq(cloned.values.as_ref().unwrap(), &values));
+        assert!(params.flags(Version::V4).contains(QueryFlags::VALUE));
+    }
+
+    #[test]
+    fn should_reject_names_without_values() {
+        assert!(QueryParamsBuilder::new().with_names(true).build().is_err());
+    }
+
+    #[test]
+    fn should_reject_names_mismatch() {
+        assert!(QueryParamsBuilder::new()
+            .with_values(QueryValues::SimpleValues(vec![Value::Null]))
+            .with_names(true)
+            .build()
+            .is_err());
+
+        let mut values = HashMap::new();
+        values.insert("a".to_string(), Value::Null);
+
+        assert!(QueryParamsBuilder::new()
+            .with_values(QueryValues::NamedValues(values))
+            .with_names(false)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn should_reject_non_positive_page_size() {
+        assert!(QueryParamsBuilder::new().with_page_size(0).build().is_err());
+        assert!(QueryParamsBuilder::new()
+            .with_page_size(-5)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn should_reject_invalid_serial_consistency() {
+        assert!(QueryParamsBuilder::new()
+            .with_serial_consistency(Consistency::Quorum)
+            .build()
+            .is_err());
+
+        let params = QueryParamsBuilder::new()
+            .with_serial_consistency(Consistency::LocalSerial)
+            .build()
+            .unwrap();
+        assert_eq!(params.serial_consistency, Some(Consistency::LocalSerial));
     }
 }
@@ -1,10 +1,12 @@
 use itertools::Itertools;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::io::Cursor;
 
+use crate::error::Result;
 use crate::frame::{Serialize, Version};
-use crate::types::serialize_str;
 use crate::types::value::Value;
+use crate::types::{serialize_str, verify_bytes_len, verify_short_count, verify_short_string};
 
 /// Enum that represents two types of query values:
 /// * values without name
@@ -34,6 +36,51 @@ impl QueryValues {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Verifies the number of values, their names and lengths fit in protocol limits. `elements`
+    /// describes the values in the error message.
+    pub fn verify(&self, elements: &str) -> Result<()> {
+        verify_short_count(self.len(), elements)?;
+
+        match self {
+            QueryValues::SimpleValues(values) => values.iter().try_for_each(verify_value),
+            QueryValues::NamedValues(values) => {
+                for name in values.keys() {
+                    verify_short_string(name, "Value name")?;
+                }
+
+                values.values().try_for_each(verify_value)
+            }
+        }
+    }
+}
+
+/// Verifies a bound value fits in `[bytes]`. Collections with more elements than an `[int]` can
+/// hold are always longer than that, so their element count needs no separate check.
+fn verify_value(value: &Value) -> Result<()> {
+    match value {
+        Value::Some(bytes) => verify_bytes_len(bytes.len(), "Value"),
+        _ => Ok(()),
+    }
+}
+
+impl Display for QueryValues {
+    /// Displays values for logging purposes, e.g. `[0x01, NULL]` or `{a: 0x01, b: UNSET}`. Named
+    /// values are sorted by name. Long values are truncated.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryValues::SimpleValues(values) => write!(f, "[{}]", values.iter().join(", ")),
+            QueryValues::NamedValues(values) => write!(
+                f,
+                "{{{}}}",
+                values
+                    .iter()
+                    .sorted_by(|(a, _), (b, _)| a.cmp(b))
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .join(", ")
+            ),
+        }
+    }
 }
 
 impl<T: Into<Value>> From<Vec<T>> for QueryValues {
@@ -81,3 +128,52 @@ impl Serialize for QueryValues {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_serialize_unset_and_null_values() {
+        let values = QueryValues::SimpleValues(vec![Value::NotSet, Value::Null]);
+        assert_eq!(
+            values.serialize_to_vec(Version::V4),
+            vec![0xFF, 0xFF, 0xFF, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+
+        let values =
+            QueryValues::NamedValues(vec![("a".to_string(), Value::NotSet)].into_iter().collect());
+        assert_eq!(
+            values.serialize_to_vec(Version::V4),
+            vec![0, 1, b'a', 0xFF, 0xFF, 0xFF, 0xFE]
+        );
+    }
+
+    #[test]
+    fn should_display_values() {
+        let values = QueryValues::SimpleValues(vec![Value::Some(vec![1]), Value::Null]);
+        assert_eq!(values.to_string(), "[0x01, NULL]");
+
+        let values = QueryValues::NamedValues(
+            vec![
+                ("b".to_string(), Value::NotSet),
+                ("a".to_string(), Value::Some(vec![1])),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(values.to_string(), "{a: 0x01, b: UNSET}");
+    }
+
+    #[test]
+    fn should_verify_value_names() {
+        let values = |name: String| {
+            QueryValues::NamedValues(vec![(name, Value::NotSet)].into_iter().collect())
+        };
+
+        assert!(values("a".into()).verify("values").is_ok());
+        assert!(values("a".repeat(u16::MAX as usize + 1))
+            .verify("values")
+            .is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::types::CLong;
+
+/// Generator of client-side query timestamps, in **microseconds** since the Unix epoch, as
+/// expected by the server.
+pub trait TimestampGenerator {
+    /// Returns the next timestamp in microseconds since the Unix epoch.
+    fn next_timestamp(&self) -> CLong;
+}
+
+/// Generator producing strictly increasing timestamps, even when the system clock goes backwards
+/// or multiple timestamps are requested within the same microsecond. In such cases, the last
+/// returned timestamp is incremented by one microsecond.
+#[derive(Debug, Default)]
+pub struct MonotonicTimestampGenerator {
+    last: AtomicI64,
+}
+
+impl MonotonicTimestampGenerator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl TimestampGenerator for MonotonicTimestampGenerator {
+    fn next_timestamp(&self) -> CLong {
+        let now = current_time_micros();
+        let previous = self
+            .last
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |last| {
+                Some(now.max(last + 1))
+            })
+            .unwrap_or_else(|last| last);
+
+        now.max(previous + 1)
+    }
+}
+
+/// Returns current system time in microseconds since the Unix epoch.
+pub fn current_time_micros() -> CLong {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros() as CLong)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::timestamp_generator::{
+        current_time_micros, MonotonicTimestampGenerator, TimestampGenerator,
+    };
+
+    #[test]
+    fn should_generate_strictly_increasing_timestamps() {
+        let generator = MonotonicTimestampGenerator::new();
+        let start = current_time_micros();
+
+        let mut last = generator.next_timestamp();
+        assert!(last >= start);
+
+        for _ in 0..1000 {
+            let next = generator.next_timestamp();
+            assert!(next > last);
+            last = next;
+        }
+    }
+}
@@ -1,5 +1,445 @@
+use itertools::Itertools;
+use std::convert::TryFrom;
+
+use crate::error::{Error, Result};
+use crate::query::QueryValues;
+use crate::token::Murmur3Token;
+use crate::types::value::Value;
+
 /// Returns the identifier in a format appropriate for concatenation in a CQL query.
 #[inline]
 pub fn quote(text: &str) -> String {
     format!("\"{}\"", text.replace('"', "\"\""))
 }
+
+/// Serializes given partition key values into a routing key, as used by the partitioner to
+/// compute a token. A single value is used as-is, while multiple values form a composite key, where
+/// each component is a `[short]` length followed by the value and a trailing `0` byte. Returns an
+/// error if a component of a composite key is too long for its length.
+pub fn serialize_routing_key(values: &[Value]) -> Result<Vec<u8>> {
+    match values {
+        [] => Ok(vec![]),
+        [value] => Ok(value_bytes(value).to_vec()),
+        _ => {
+            let mut buf = vec![];
+            for value in values {
+                serialize_routing_value(&mut buf, value_bytes(value))?;
+            }
+
+            Ok(buf)
+        }
+    }
+}
+
+/// Serializes partition key values found at given indexes into a routing key. Returns `None` if
+/// there are no indexes or some value is missing. See `serialize_routing_key`.
+pub fn serialize_routing_key_with_indexes(
+    values: &[Value],
+    pk_indexes: &[i16],
+) -> Result<Option<Vec<u8>>> {
+    match pk_indexes {
+        [] => Ok(None),
+        [index] => Ok(values
+            .get(*index as usize)
+            .map(|value| value_bytes(value).to_vec())),
+        _ => {
+            let mut buf = vec![];
+            for index in pk_indexes {
+                match values.get(*index as usize) {
+                    Some(value) => serialize_routing_value(&mut buf, value_bytes(value))?,
+                    None => return Ok(None),
+                }
+            }
+
+            Ok(Some(buf))
+        }
+    }
+}
+
+/// Builds `SELECT` statements reading rows with partition key tokens in the `(start, end]` range,
+/// along with bound range bounds. A range wrapping around the ring, i.e. with `start >= end`,
+/// results in two statements, since a single `token(...)` restriction cannot express it. The
+/// table name is used as-is, so it can be qualified with a keyspace, while column names are
+/// quoted. Empty `columns` select all columns.
+pub fn token_range_select(
+    table: &str,
+    columns: &[&str],
+    partition_key: &[&str],
+    start: Murmur3Token,
+    end: Murmur3Token,
+) -> Vec<(String, QueryValues)> {
+    let columns = if columns.is_empty() {
+        "*".to_string()
+    } else {
+        columns.iter().map(|column| quote(column)).join(", ")
+    };
+
+    let token = format!(
+        "token({})",
+        partition_key.iter().map(|column| quote(column)).join(", ")
+    );
+
+    let select = |start: Murmur3Token, end: Murmur3Token| {
+        (
+            format!(
+                "SELECT {} FROM {} WHERE {} > ? AND {} <= ?",
+                columns, table, token, token
+            ),
+            QueryValues::SimpleValues(vec![start.value.into(), end.value.into()]),
+        )
+    };
+
+    if start < end {
+        vec![select(start, end)]
+    } else if end == Murmur3Token::MIN {
+        // nothing can be less or equal to the minimum token
+        vec![select(start, Murmur3Token::MAX)]
+    } else {
+        vec![
+            select(start, Murmur3Token::MAX),
+            select(Murmur3Token::MIN, end),
+        ]
+    }
+}
+
+/// Token replacing string literals, which cannot clash with identifiers.
+const STRING_LITERAL: &str = "''";
+
+/// Functions returning a different value on every invocation.
+const NON_DETERMINISTIC_FUNCTIONS: [&str; 6] = [
+    "now",
+    "uuid",
+    "currenttimestamp",
+    "currentdate",
+    "currenttime",
+    "currenttimeuuid",
+];
+
+/// Infers if given CQL statement is idempotent, i.e. can be safely executed multiple times, e.g.
+/// when retrying or using speculative execution. This is a best-effort heuristic, which treats as
+/// idempotent:
+///
+/// * `SELECT` statements,
+/// * `INSERT`, `UPDATE` and `DELETE` statements without conditions (lightweight transactions),
+///   non-deterministic functions like `now()` or `uuid()`, counter updates, collection appends
+///   and removals, and deletions of collection elements by anything other than a string literal,
+///   since these may be list indexes.
+///
+/// Everything else, including batches, is treated as non-idempotent. Since the schema is not
+/// known, the heuristic cannot verify that the whole primary key is specified, nor detect
+/// counters updated with bind markers only. The result should be passed explicitly to the
+/// statement parameters, which remain authoritative.
+pub fn infer_idempotence(query: &str) -> bool {
+    let tokens = tokenize(query);
+    let keyword = match tokens.first() {
+        Some(keyword) => keyword.as_str(),
+        None => return false,
+    };
+
+    if keyword == "select" {
+        return true;
+    }
+
+    if !matches!(keyword, "insert" | "update" | "delete") {
+        return false;
+    }
+
+    let is_conditional = tokens.iter().any(|token| token == "if");
+    let is_non_deterministic = tokens.windows(2).any(|tokens| {
+        tokens[1] == "(" && NON_DETERMINISTIC_FUNCTIONS.contains(&tokens[0].as_str())
+    });
+
+    if is_conditional || is_non_deterministic {
+        return false;
+    }
+
+    match keyword {
+        "update" => !has_self_referencing_assignment(&tokens),
+        // deleting list elements by index shifts the remaining ones
+        "delete" => !tokens
+            .iter()
+            .take_while(|token| *token != "from")
+            .tuple_windows()
+            .any(|(token, key)| token == "[" && key != STRING_LITERAL),
+        _ => true,
+    }
+}
+
+/// Checks if any `SET` assignment references the assigned column, e.g. `c = c + 1` or
+/// `l = [1] + l`.
+fn has_self_referencing_assignment(tokens: &[String]) -> bool {
+    let assignments = tokens
+        .iter()
+        .skip_while(|token| *token != "set")
+        .skip(1)
+        .take_while(|token| *token != "where");
+
+    let mut depth = 0;
+    let mut column: Option<&str> = None;
+    let mut in_value = false;
+
+    for token in assignments {
+        match token.as_str() {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth -= 1,
+            "," if depth == 0 => {
+                column = None;
+                in_value = false;
+            }
+            "=" if depth == 0 => in_value = true,
+            token if in_value => {
+                if Some(token) == column {
+                    return true;
+                }
+            }
+            token => {
+                if column.is_none() {
+                    column = Some(token);
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Splits given statement into lowercase identifiers and punctuation, replacing literal strings
+/// with `STRING_LITERAL` and skipping comments.
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '\'' => {
+                // quotes inside strings are escaped by doubling them
+                while let Some(c) = chars.next() {
+                    if c == '\'' && chars.next_if_eq(&'\'').is_none() {
+                        break;
+                    }
+                }
+
+                tokens.push(STRING_LITERAL.to_string());
+            }
+            '$' if chars.next_if_eq(&'$').is_some() => {
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '$' && c == '$' {
+                        break;
+                    }
+
+                    previous = c;
+                }
+
+                tokens.push(STRING_LITERAL.to_string());
+            }
+            '"' => {
+                let mut identifier = String::new();
+                while let Some(c) = chars.next() {
+                    if c == '"' && chars.next_if_eq(&'"').is_none() {
+                        break;
+                    }
+
+                    identifier.push(c);
+                }
+
+                tokens.push(identifier.to_lowercase());
+            }
+            '-' if chars.next_if_eq(&'-').is_some() => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+
+                    previous = c;
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut identifier = c.to_lowercase().to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    identifier.extend(c.to_lowercase());
+                }
+
+                tokens.push(identifier);
+            }
+            c => tokens.push(c.to_string()),
+        }
+    }
+
+    tokens
+}
+
+// https://github.com/apache/cassandra/blob/3a950b45c321e051a9744721408760c568c05617/src/java/org/apache/cassandra/db/marshal/CompositeType.java#L39
+fn serialize_routing_value(buf: &mut Vec<u8>, value: &[u8]) -> Result<()> {
+    let len = u16::try_from(value.len()).map_err(|_| {
+        Error::General(format!(
+            "Partition key component too long: {} bytes (maximum is {} bytes)",
+            value.len(),
+            u16::MAX
+        ))
+    })?;
+
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(value);
+    buf.push(0);
+    Ok(())
+}
+
+#[inline]
+fn value_bytes(value: &Value) -> &[u8] {
+    match value {
+        Value::Some(value) => value,
+        Value::Null | Value::NotSet => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::utils::{
+        infer_idempotence, serialize_routing_key, serialize_routing_key_with_indexes,
+        token_range_select,
+    };
+    use crate::query::QueryValues;
+    use crate::token::Murmur3Token;
+    use crate::types::value::Value;
+
+    #[test]
+    fn should_serialize_single_routing_key_without_length() {
+        assert_eq!(
+            serialize_routing_key(&[Value::new(1i32)]).unwrap(),
+            vec![0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn should_serialize_composite_routing_key() {
+        let expected = vec![0, 4, 0, 0, 0, 1, 0, 0, 1, 2, 0];
+        assert_eq!(
+            serialize_routing_key(&[Value::new(1i32), Value::Some(vec![2])]).unwrap(),
+            expected
+        );
+        assert_eq!(
+            serialize_routing_key_with_indexes(
+                &[Value::Some(vec![2]), Value::new(5i32), Value::new(1i32)],
+                &[2, 0]
+            )
+            .unwrap(),
+            Some(expected)
+        );
+        assert_eq!(
+            serialize_routing_key_with_indexes(&[Value::new(1i32)], &[0, 1]).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn should_reject_too_long_routing_key_component() {
+        let values = [
+            Value::new(1i32),
+            Value::Some(vec![0; u16::MAX as usize + 1]),
+        ];
+        assert!(serialize_routing_key(&values).is_err());
+        assert!(serialize_routing_key_with_indexes(&values, &[0, 1]).is_err());
+
+        // single values are used as-is, without a length
+        assert!(serialize_routing_key(&values[1..]).is_ok());
+    }
+
+    #[test]
+    fn should_select_token_range() {
+        let queries = token_range_select(
+            "ks.table",
+            &["a", "b"],
+            &["pk"],
+            Murmur3Token::new(-10),
+            Murmur3Token::new(20),
+        );
+
+        assert_eq!(
+            queries,
+            vec![(
+                "SELECT \"a\", \"b\" FROM ks.table WHERE token(\"pk\") > ? AND token(\"pk\") <= ?"
+                    .to_string(),
+                QueryValues::SimpleValues(vec![Value::new(-10i64), Value::new(20i64)])
+            )]
+        );
+    }
+
+    #[test]
+    fn should_split_wrapping_token_range() {
+        let queries = token_range_select(
+            "table",
+            &[],
+            &["pk1", "pk2"],
+            Murmur3Token::new(20),
+            Murmur3Token::new(-10),
+        );
+
+        let query =
+            "SELECT * FROM table WHERE token(\"pk1\", \"pk2\") > ? AND token(\"pk1\", \"pk2\") <= ?";
+        assert_eq!(
+            queries,
+            vec![
+                (
+                    query.to_string(),
+                    QueryValues::SimpleValues(vec![Value::new(20i64), Value::new(i64::MAX)])
+                ),
+                (
+                    query.to_string(),
+                    QueryValues::SimpleValues(vec![Value::new(i64::MIN), Value::new(-10i64)])
+                )
+            ]
+        );
+
+        // the whole ring
+        let queries =
+            token_range_select("table", &[], &["pk"], Murmur3Token::MIN, Murmur3Token::MIN);
+        assert_eq!(queries.len(), 1);
+        assert_eq!(
+            queries[0].1,
+            QueryValues::SimpleValues(vec![Value::new(i64::MIN), Value::new(i64::MAX)])
+        );
+    }
+
+    #[test]
+    fn should_infer_idempotent_statements() {
+        for query in [
+            "SELECT * FROM ks.t WHERE id = now()",
+            "  select a FROM t",
+            "INSERT INTO t (id, v) VALUES (?, 'now()')",
+            "INSERT INTO t (id, v) VALUES (1, 'it''s if') USING TTL 10",
+            "UPDATE t SET v = ?, l[0] = 1, m = {1: 2} WHERE id = ?",
+            "UPDATE t SET \"V\" = 'v + v' WHERE id = 1 -- v = v + 1",
+            "DELETE v, m['k'] FROM t WHERE id = ?",
+        ] {
+            assert!(infer_idempotence(query), "{}", query);
+        }
+    }
+
+    #[test]
+    fn should_infer_non_idempotent_statements() {
+        for query in [
+            "",
+            "BEGIN BATCH INSERT INTO t (id) VALUES (1); APPLY BATCH",
+            "TRUNCATE t",
+            "INSERT INTO t (id, v) VALUES (?, now())",
+            "INSERT INTO t (id) VALUES (uuid ())",
+            "INSERT INTO t (id) VALUES (?) IF NOT EXISTS",
+            "UPDATE t SET c = c + 1 WHERE id = ?",
+            "UPDATE t SET v = ?, l = [1] + l WHERE id = ?",
+            "UPDATE t SET s = s - {1} WHERE id = ?",
+            "UPDATE t SET v = ? WHERE id = ? IF v = ?",
+            "UPDATE t SET v = currentTimestamp() WHERE id = ?",
+            "DELETE l[1] FROM t WHERE id = ?",
+            "DELETE m[?] FROM t WHERE id = ?",
+            "DELETE FROM t WHERE id = ? IF EXISTS",
+        ] {
+            assert!(!infer_idempotence(query), "{}", query);
+        }
+    }
+}
@@ -1,36 +1,327 @@
-use crate::error::Error;
-use derive_more::Constructor;
-use std::cmp::min;
+use crate::error::{Error, Result};
+use derive_more::{Constructor, Display};
+use num::{BigInt, Signed};
+use std::cmp::{min, Ordering};
 use std::convert::TryFrom;
 use std::convert::TryInto;
+use std::fmt::{Display, Formatter};
 use std::num::Wrapping;
+use std::str::FromStr;
 
 const C1: Wrapping<i64> = Wrapping(0x87c3_7b91_1142_53d5_u64 as i64);
 const C2: Wrapping<i64> = Wrapping(0x4cf5_ad43_2745_937f_u64 as i64);
 
-/// A token on the ring. Only Murmur3 tokens are supported for now.
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default, Debug, Hash, Constructor)]
+const MURMUR3_PARTITIONER: &str = "org.apache.cassandra.dht.Murmur3Partitioner";
+const RANDOM_PARTITIONER: &str = "org.apache.cassandra.dht.RandomPartitioner";
+const BYTE_ORDERED_PARTITIONER: &str = "org.apache.cassandra.dht.ByteOrderedPartitioner";
+
+/// Partitioner used by the cluster to distribute data across the ring.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default, Debug, Hash, Display)]
+pub enum Partitioner {
+    #[default]
+    #[display(fmt = "{}", MURMUR3_PARTITIONER)]
+    Murmur3,
+    #[display(fmt = "{}", RANDOM_PARTITIONER)]
+    Random,
+    #[display(fmt = "{}", BYTE_ORDERED_PARTITIONER)]
+    ByteOrdered,
+}
+
+impl Partitioner {
+    /// Returns a partitioner for given fully qualified class name, as reported by the server in
+    /// `system.local`. Short names, e.g. `Murmur3Partitioner`, are also accepted.
+    pub fn from_class_name(class_name: &str) -> Option<Self> {
+        match class_name.rsplit('.').next() {
+            Some("Murmur3Partitioner") => Some(Partitioner::Murmur3),
+            Some("RandomPartitioner") => Some(Partitioner::Random),
+            Some("ByteOrderedPartitioner") => Some(Partitioner::ByteOrdered),
+            _ => None,
+        }
+    }
+
+    /// Computes the token for given routing key.
+    pub fn generate_token(&self, routing_key: &[u8]) -> Token {
+        match self {
+            Partitioner::Murmur3 => Murmur3Token::generate(routing_key).into(),
+            Partitioner::Random => RandomToken::generate(routing_key).into(),
+            Partitioner::ByteOrdered => ByteOrderedToken::new(routing_key.to_vec()).into(),
+        }
+    }
+
+    /// Parses textual token representation, as returned by the server.
+    pub fn parse_token(&self, value: &str) -> Result<Token> {
+        match self {
+            Partitioner::Murmur3 => value
+                .parse()
+                .map(|value: i64| Murmur3Token::new(value).into())
+                .map_err(|error| format!("Error parsing token: {}", error).into()),
+            Partitioner::Random => BigInt::from_str(value)
+                .map(|value| RandomToken::new(value).into())
+                .map_err(|error| format!("Error parsing token: {}", error).into()),
+            Partitioner::ByteOrdered => ByteOrderedToken::from_hex(value).map(Token::ByteOrdered),
+        }
+    }
+}
+
+impl FromStr for Partitioner {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Partitioner::from_class_name(s)
+            .ok_or_else(|| format!("Unsupported partitioner: {}", s).into())
+    }
+}
+
+/// A token on the ring, generated by one of the supported partitioners. Tokens are ordered
+/// according to their partitioner; tokens produced by different partitioners are never placed on
+/// the same ring, but are ordered by partitioner to keep the ordering total.
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Display)]
+pub enum Token {
+    Murmur3(Murmur3Token),
+    Random(RandomToken),
+    ByteOrdered(ByteOrderedToken),
+}
+
+impl Token {
+    /// Returns the partitioner which generates this kind of token.
+    pub fn partitioner(&self) -> Partitioner {
+        match self {
+            Token::Murmur3(_) => Partitioner::Murmur3,
+            Token::Random(_) => Partitioner::Random,
+            Token::ByteOrdered(_) => Partitioner::ByteOrdered,
+        }
+    }
+}
+
+impl Ord for Token {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Token::Murmur3(this), Token::Murmur3(other)) => this.cmp(other),
+            (Token::Random(this), Token::Random(other)) => this.cmp(other),
+            (Token::ByteOrdered(this), Token::ByteOrdered(other)) => this.cmp(other),
+            _ => self.partitioner().cmp(&other.partitioner()),
+        }
+    }
+}
+
+impl PartialOrd for Token {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Default for Token {
+    fn default() -> Self {
+        Token::Murmur3(Default::default())
+    }
+}
+
+impl From<Murmur3Token> for Token {
+    #[inline]
+    fn from(token: Murmur3Token) -> Self {
+        Token::Murmur3(token)
+    }
+}
+
+impl From<RandomToken> for Token {
+    #[inline]
+    fn from(token: RandomToken) -> Self {
+        Token::Random(token)
+    }
+}
+
+impl From<ByteOrderedToken> for Token {
+    #[inline]
+    fn from(token: ByteOrderedToken) -> Self {
+        Token::ByteOrdered(token)
+    }
+}
+
+impl From<i64> for Token {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Token::Murmur3(value.into())
+    }
+}
+
+impl TryFrom<String> for Token {
+    type Error = Error;
+
+    /// Parses a token without knowing the partitioner - values fitting in 64 bits are assumed to
+    /// be Murmur3 tokens, while larger ones are assumed to be RandomPartitioner tokens.
+    /// ByteOrderedPartitioner tokens cannot be distinguished, so use `Partitioner::parse_token`
+    /// when the partitioner is known.
+    fn try_from(value: String) -> Result<Self> {
+        Partitioner::Murmur3
+            .parse_token(&value)
+            .or_else(|_| Partitioner::Random.parse_token(&value))
+    }
+}
+
+/// A token generated by the RandomPartitioner - absolute value of the MD5 hash of the key.
+#[derive(Clone, Eq, PartialEq, Default, Debug, Hash, Constructor, Display)]
+pub struct RandomToken {
+    pub value: BigInt,
+}
+
+impl Ord for RandomToken {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl PartialOrd for RandomToken {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl RandomToken {
+    pub fn generate(routing_key: &[u8]) -> Self {
+        RandomToken::new(BigInt::from_signed_bytes_be(&md5::compute(routing_key).0).abs())
+    }
+}
+
+impl TryFrom<String> for RandomToken {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        BigInt::from_str(&value)
+            .map_err(|error| format!("Error parsing token: {}", error).into())
+            .map(RandomToken::new)
+    }
+}
+
+/// A token generated by the ByteOrderedPartitioner - the raw key bytes, ordered
+/// lexicographically as unsigned bytes. Textual representation is hex-encoded.
+#[derive(Clone, Eq, PartialEq, Default, Debug, Hash, Constructor)]
+pub struct ByteOrderedToken {
+    pub value: Vec<u8>,
+}
+
+impl ByteOrderedToken {
+    /// Parses a hex-encoded token, as returned by the server.
+    pub fn from_hex(value: &str) -> Result<Self> {
+        value
+            .as_bytes()
+            .chunks(2)
+            .map(|byte| {
+                std::str::from_utf8(byte)
+                    .ok()
+                    .filter(|byte| byte.len() == 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(|| format!("Error parsing token: invalid hex: {}", value).into())
+            })
+            .collect::<Result<Vec<u8>>>()
+            .map(ByteOrderedToken::new)
+    }
+}
+
+impl Ord for ByteOrderedToken {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.as_slice().cmp(other.value.as_slice())
+    }
+}
+
+impl PartialOrd for ByteOrderedToken {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for ByteOrderedToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for byte in &self.value {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A token generated by the Murmur3Partitioner.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Hash, Constructor, Display)]
 pub struct Murmur3Token {
     pub value: i64,
 }
 
+impl Ord for Murmur3Token {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl PartialOrd for Murmur3Token {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Murmur3Token {
-    // based on buggy Cassandra implementation
+    /// The minimum token, which is never assigned to data, but marks the start of the ring.
+    pub const MIN: Murmur3Token = Murmur3Token { value: i64::MIN };
+    /// The maximum token.
+    pub const MAX: Murmur3Token = Murmur3Token { value: i64::MAX };
+
+    /// Returns the next token on the ring, wrapping around to `MIN` after `MAX`.
+    #[inline]
+    pub fn successor(self) -> Self {
+        Murmur3Token::new(self.value.wrapping_add(1))
+    }
+
+    /// Returns the previous token on the ring, wrapping around to `MAX` before `MIN`.
+    #[inline]
+    pub fn predecessor(self) -> Self {
+        Murmur3Token::new(self.value.wrapping_sub(1))
+    }
+
+    /// Checks if given token is in the `(start, end]` range. The range wraps around the ring if
+    /// `start >= end`, and `start == end` denotes the whole ring.
+    pub fn range_contains(start: Self, end: Self, token: Self) -> bool {
+        if start < end {
+            start < token && token <= end
+        } else {
+            start < token || token <= end
+        }
+    }
+
+    /// Generates the token for given routing key. See `from_partition_key`.
+    #[inline]
     pub fn generate(routing_key: &[u8]) -> Self {
-        let length = routing_key.len();
+        Self::from_partition_key(routing_key)
+    }
+
+    /// Returns the token Cassandra assigns to given serialized partition key. The key is hashed
+    /// with Cassandra's variant of Murmur3 x64 128-bit (which sign-extends trailing bytes) and the
+    /// low 64 bits are used as the token. As in Cassandra, an empty key maps to `MIN` and a hash
+    /// equal to `MIN` is replaced by `MAX`, since `MIN` is never assigned to data.
+    pub fn from_partition_key(partition_key: &[u8]) -> Self {
+        if partition_key.is_empty() {
+            return Self::MIN;
+        }
+
+        let value = Self::hash(partition_key);
+        Murmur3Token::new(if value == i64::MIN { i64::MAX } else { value })
+    }
+
+    // based on buggy Cassandra implementation
+    fn hash(key: &[u8]) -> i64 {
+        let length = key.len();
 
         let mut h1: Wrapping<i64> = Wrapping(0);
         let mut h2: Wrapping<i64> = Wrapping(0);
 
-        let mut pos = 0;
-        while routing_key.len() >= 16 {
-            let mut k1 = Wrapping(i64::from_le_bytes(
-                routing_key[pos..pos + 8].try_into().unwrap(),
-            ));
-            pos += 8;
-            let mut k2 = Wrapping(i64::from_le_bytes(
-                routing_key[pos..pos + 8].try_into().unwrap(),
-            ));
+        let mut blocks = key.chunks_exact(16);
+        for block in &mut blocks {
+            let mut k1 = Wrapping(i64::from_le_bytes(block[..8].try_into().unwrap()));
+            let mut k2 = Wrapping(i64::from_le_bytes(block[8..].try_into().unwrap()));
 
             k1 *= C1;
             k1 = rotl64(k1, 31);
@@ -51,14 +342,14 @@ impl Murmur3Token {
             h2 = h2 * Wrapping(5) + Wrapping(0x38495ab5);
         }
 
+        let tail = blocks.remainder();
+
         let mut k1 = Wrapping(0_i64);
         let mut k2 = Wrapping(0_i64);
 
-        debug_assert!(routing_key.len() < 16);
-
-        if routing_key.len() > 8 {
-            for i in (8..routing_key.len()).rev() {
-                k2 ^= Wrapping(routing_key[i] as i8 as i64) << ((i - 8) * 8);
+        if tail.len() > 8 {
+            for i in (8..tail.len()).rev() {
+                k2 ^= Wrapping(tail[i] as i8 as i64) << ((i - 8) * 8);
             }
 
             k2 *= C2;
@@ -67,9 +358,9 @@ impl Murmur3Token {
             h2 ^= k2;
         }
 
-        if !routing_key.is_empty() {
-            for i in (0..min(8, routing_key.len())).rev() {
-                k1 ^= Wrapping(routing_key[i] as i8 as i64) << (i * 8);
+        if !tail.is_empty() {
+            for i in (0..min(8, tail.len())).rev() {
+                k1 ^= Wrapping(tail[i] as i8 as i64) << (i * 8);
             }
 
             k1 *= C1;
@@ -89,14 +380,23 @@ impl Murmur3Token {
 
         h1 += h2;
 
-        Murmur3Token::new(h1.0)
+        h1.0
     }
 }
 
 impl TryFrom<String> for Murmur3Token {
     type Error = Error;
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
+    #[inline]
+    fn try_from(value: String) -> Result<Self> {
+        Murmur3Token::try_from(value.as_str())
+    }
+}
+
+impl TryFrom<&str> for Murmur3Token {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
         value
             .parse()
             .map_err(|error| format!("Error parsing token: {}", error).into())
@@ -142,4 +442,163 @@ mod test {
             assert_eq!(generated_token.value, s.1);
         }
     }
+
+    #[test]
+    fn test_murmur3_token_from_long_partition_key() {
+        for s in [
+            ("0123456789abcdef", 5467490433528156583),
+            ("a_key_longer_than_sixteen_bytes", -9189752561891058604),
+            ("0123456789abcdef0123456789abcdef!", 2117779540659012405),
+        ] {
+            let token = Murmur3Token::from_partition_key(s.0.as_bytes());
+            assert_eq!(token.value, s.1);
+        }
+
+        assert_eq!(Murmur3Token::from_partition_key(&[]), Murmur3Token::MIN);
+    }
+
+    #[test]
+    fn test_generate_random_token() {
+        for s in [
+            ("", "58332598431525814501020785164969033090"),
+            ("testvalue", "29416761314838505200589635796249845380"),
+            ("example_key", "125683033245568201169531869465475303949"),
+            ("château", "79688325475171764737175894082428338403"),
+        ] {
+            let generated_token = RandomToken::generate(s.0.as_bytes());
+            assert_eq!(generated_token.value.to_string(), s.1);
+        }
+    }
+
+    #[test]
+    fn test_partitioner_from_class_name() {
+        assert_eq!(
+            Partitioner::from_class_name("org.apache.cassandra.dht.Murmur3Partitioner"),
+            Some(Partitioner::Murmur3)
+        );
+        assert_eq!(
+            Partitioner::from_class_name("org.apache.cassandra.dht.RandomPartitioner"),
+            Some(Partitioner::Random)
+        );
+        assert_eq!(
+            Partitioner::from_class_name("org.apache.cassandra.dht.ByteOrderedPartitioner"),
+            Some(Partitioner::ByteOrdered)
+        );
+        assert_eq!(
+            Partitioner::from_class_name("org.apache.cassandra.dht.OrderPreservingPartitioner"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_partitioner_generate_token() {
+        assert_eq!(
+            Partitioner::Murmur3.generate_token(b"testvalue"),
+            Token::Murmur3(Murmur3Token::new(5965290492934326460))
+        );
+        assert_eq!(
+            Partitioner::Random.generate_token(b"testvalue"),
+            Token::Random(RandomToken::generate(b"testvalue"))
+        );
+    }
+
+    #[test]
+    fn test_parse_token() {
+        assert_eq!(
+            Partitioner::Murmur3
+                .parse_token("-7813763279771224608")
+                .unwrap(),
+            Token::Murmur3(Murmur3Token::new(-7813763279771224608))
+        );
+        assert!(Partitioner::Murmur3
+            .parse_token("125683033245568201169531869465475303949")
+            .is_err());
+        assert_eq!(
+            Partitioner::Random.parse_token("42").unwrap(),
+            Token::Random(RandomToken::new(42.into()))
+        );
+
+        assert_eq!(
+            Token::try_from("42".to_string()).unwrap(),
+            Token::Murmur3(Murmur3Token::new(42))
+        );
+        assert_eq!(
+            Token::try_from("125683033245568201169531869465475303949".to_string()).unwrap(),
+            Token::Random(RandomToken::generate(b"example_key"))
+        );
+        assert!(Token::try_from("abc".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_parse_byte_ordered_token() {
+        let token = Partitioner::ByteOrdered.parse_token("00ff7a").unwrap();
+        assert_eq!(
+            token,
+            Token::ByteOrdered(ByteOrderedToken::new(vec![0, 255, 122]))
+        );
+        assert_eq!(token.to_string(), "00ff7a");
+
+        assert!(Partitioner::ByteOrdered.parse_token("0").is_err());
+        assert!(Partitioner::ByteOrdered.parse_token("zz").is_err());
+        assert!(Partitioner::ByteOrdered.parse_token("é0").is_err());
+    }
+
+    #[test]
+    fn test_byte_ordered_token_ordering() {
+        let generate = |key: &[u8]| Partitioner::ByteOrdered.generate_token(key);
+
+        assert!(generate(b"") < generate(&[0]));
+        assert!(generate(&[0x7f]) < generate(&[0x80]));
+        assert!(generate(&[1, 2]) < generate(&[1, 2, 0]));
+        assert!(generate(&[1, 255]) < generate(&[2]));
+    }
+
+    #[test]
+    fn test_murmur3_token_ordering() {
+        assert!(Token::from(-1) < Token::from(0));
+        assert!(Token::from(i64::MIN) < Token::from(i64::MAX));
+    }
+
+    #[test]
+    fn test_murmur3_token_arithmetic() {
+        assert_eq!(Murmur3Token::new(1).successor(), Murmur3Token::new(2));
+        assert_eq!(Murmur3Token::MAX.successor(), Murmur3Token::MIN);
+        assert_eq!(Murmur3Token::new(1).predecessor(), Murmur3Token::new(0));
+        assert_eq!(Murmur3Token::MIN.predecessor(), Murmur3Token::MAX);
+    }
+
+    #[test]
+    fn test_murmur3_token_range_contains() {
+        let contains = |start: i64, end: i64, token: i64| {
+            Murmur3Token::range_contains(start.into(), end.into(), token.into())
+        };
+
+        assert!(contains(0, 10, 10));
+        assert!(contains(0, 10, 5));
+        assert!(!contains(0, 10, 0));
+        assert!(!contains(0, 10, 11));
+
+        // wrapping range
+        assert!(contains(10, 0, 11));
+        assert!(contains(10, 0, i64::MIN));
+        assert!(contains(10, 0, 0));
+        assert!(!contains(10, 0, 5));
+        assert!(!contains(10, 0, 10));
+
+        // whole ring
+        assert!(contains(5, 5, 5));
+        assert!(contains(5, 5, 100));
+        assert!(contains(i64::MIN, i64::MIN, 0));
+    }
+
+    #[test]
+    fn test_murmur3_token_try_from_str() {
+        assert_eq!(Murmur3Token::try_from("-5").unwrap(), Murmur3Token::new(-5));
+        assert_eq!(
+            Murmur3Token::try_from("x").unwrap_err().to_string(),
+            Murmur3Token::try_from("x".to_string())
+                .unwrap_err()
+                .to_string()
+        );
+    }
 }
@@ -1,8 +1,13 @@
 use derive_more::Constructor;
-use std::convert::TryInto;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
 use std::io::{self, Write};
 use std::io::{Cursor, Read};
 use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
 use crate::error::{column_is_empty_err, Error as CdrsError, Result as CDRSResult};
 use crate::frame::traits::FromCursor;
@@ -16,6 +21,9 @@ pub const INT_LEN: usize = 4;
 pub const LONG_LEN: usize = 8;
 pub const UUID_LEN: usize = 16;
 
+/// Maximum number of elements which can be preceded by a `[short]` count, e.g. bound values.
+pub const MAX_SHORT_COUNT: usize = u16::MAX as usize;
+
 const NULL_INT_LEN: CInt = -1;
 const NULL_SHORT_LEN: CIntShort = -1;
 
@@ -32,6 +40,7 @@ pub mod rows;
 pub mod tuple;
 pub mod udt;
 pub mod value;
+pub mod vint;
 
 pub mod prelude {
     pub use crate::error::{Error, Result};
@@ -43,7 +52,7 @@ pub mod prelude {
     pub use crate::types::map::Map;
     pub use crate::types::rows::Row;
     pub use crate::types::tuple::Tuple;
-    pub use crate::types::udt::Udt;
+    pub use crate::types::udt::{Udt, UdtValue};
     pub use crate::types::value::{Bytes, Value};
     pub use crate::types::AsRustType;
 }
@@ -215,6 +224,61 @@ pub fn to_float_big(f: f64) -> Vec<u8> {
     f.to_be_bytes().into()
 }
 
+/// Verifies given number of elements fits in a `[short]` count. Serializing more would silently
+/// truncate the count and result in a malformed envelope.
+pub fn verify_short_count(count: usize, elements: &str) -> CDRSResult<()> {
+    if count > MAX_SHORT_COUNT {
+        Err(CdrsError::General(format!(
+            "Too many {}: {} (maximum is {})",
+            elements, count, MAX_SHORT_COUNT
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Verifies given string fits in a `[string]`, whose length is a `[short]`.
+pub fn verify_short_string(value: &str, name: &str) -> CDRSResult<()> {
+    if value.len() > MAX_SHORT_COUNT {
+        Err(CdrsError::General(format!(
+            "{} too long: {} bytes (maximum is {} bytes)",
+            name,
+            value.len(),
+            MAX_SHORT_COUNT
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Verifies given map can be serialized as a `[bytes map]`, e.g. a custom payload.
+pub fn verify_bytes_map(map: &BTreeMap<String, Vec<u8>>, name: &str) -> CDRSResult<()> {
+    verify_short_count(map.len(), &format!("{} entries", name))?;
+
+    for (key, value) in map {
+        verify_short_string(key, &format!("{} key", name))?;
+        verify_bytes_len(value.len(), &format!("{} value", name))?;
+    }
+
+    Ok(())
+}
+
+/// Verifies given byte length fits in the `[int]` length of `[bytes]`, e.g. a bound value.
+pub fn verify_bytes_len(len: usize, name: &str) -> CDRSResult<()> {
+    if CInt::try_from(len).is_err() {
+        Err(CdrsError::General(format!(
+            "{} too long: {} bytes (maximum is {} bytes)",
+            name,
+            len,
+            CInt::MAX
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Serializes given string as a `[string]`. Its length should be checked with
+/// `verify_short_string` beforehand, since longer strings get their length truncated.
 pub fn serialize_str(cursor: &mut Cursor<&mut Vec<u8>>, value: &str, version: Version) {
     let len = value.len() as CIntShort;
     len.serialize(cursor, version);
@@ -273,16 +337,84 @@ pub fn from_cursor_string_list(cursor: &mut Cursor<&[u8]>) -> CDRSResult<Vec<Str
     Ok(list)
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Ord, PartialOrd)]
-/// The structure that represents Cassandra byte type.
+/// Serializes given map as a `[bytes map]`. It should be checked with `verify_bytes_map`
+/// beforehand, since longer lengths get truncated.
+pub fn serialize_bytes_map(
+    cursor: &mut Cursor<&mut Vec<u8>>,
+    map: &BTreeMap<String, Vec<u8>>,
+    version: Version,
+) {
+    let len = map.len() as CIntShort;
+    len.serialize(cursor, version);
+
+    for (key, value) in map {
+        serialize_str(cursor, key, version);
+
+        let value_len = value.len() as CInt;
+        value_len.serialize(cursor, version);
+        let _ = cursor.write(value);
+    }
+}
+
+pub fn from_cursor_bytes_map(
+    cursor: &mut Cursor<&[u8]>,
+    version: Version,
+) -> CDRSResult<BTreeMap<String, Vec<u8>>> {
+    let mut buff = [0; SHORT_LEN];
+    cursor.read_exact(&mut buff)?;
+
+    let len = i16::from_be_bytes(buff);
+    let mut map = BTreeMap::new();
+    for _ in 0..len {
+        let key = from_cursor_str(cursor)?.to_string();
+        let value = CBytes::from_cursor(cursor, version)?
+            .into_bytes()
+            .unwrap_or_default();
+
+        map.insert(key, value);
+    }
+
+    Ok(map)
+}
+
+#[derive(Debug, Clone)]
+enum BytesStorage {
+    Owned(Vec<u8>),
+    Shared(Arc<[u8]>),
+}
+
+impl BytesStorage {
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            BytesStorage::Owned(bytes) => bytes,
+            BytesStorage::Shared(bytes) => bytes,
+        }
+    }
+}
+
+/// The structure that represents Cassandra byte type. Bytes can be backed by shared storage,
+/// which makes cloning cheap - useful for values passed around multiple times, e.g. paging
+/// state.
+#[derive(Clone)]
 pub struct CBytes {
-    bytes: Option<Vec<u8>>,
+    bytes: Option<BytesStorage>,
 }
 
 impl CBytes {
     #[inline]
     pub fn new(bytes: Vec<u8>) -> CBytes {
-        CBytes { bytes: Some(bytes) }
+        CBytes {
+            bytes: Some(BytesStorage::Owned(bytes)),
+        }
+    }
+
+    /// Creates Cassandra bytes backed by shared storage.
+    #[inline]
+    pub fn new_shared(bytes: Arc<[u8]>) -> CBytes {
+        CBytes {
+            bytes: Some(BytesStorage::Shared(bytes)),
+        }
     }
 
     /// Creates Cassandra bytes that represent empty or null value
@@ -291,20 +423,53 @@ impl CBytes {
         CBytes { bytes: None }
     }
 
-    /// Converts `CBytes` into a plain array of bytes
+    /// Reads Cassandra bytes directly into shared storage.
+    pub fn shared_from_cursor(cursor: &mut Cursor<&[u8]>, version: Version) -> CDRSResult<CBytes> {
+        let len = CInt::from_cursor(cursor, version)?;
+        // null or not set value
+        if len < 0 {
+            return Ok(CBytes { bytes: None });
+        }
+
+        cursor_next_value_ref(cursor, len as usize).map(|bytes| CBytes::new_shared(bytes.into()))
+    }
+
+    /// Converts `CBytes` into a plain array of bytes. Copies the data if it's backed by shared
+    /// storage.
     #[inline]
     pub fn into_bytes(self) -> Option<Vec<u8>> {
-        self.bytes
+        self.bytes.map(|bytes| match bytes {
+            BytesStorage::Owned(bytes) => bytes,
+            BytesStorage::Shared(bytes) => bytes.to_vec(),
+        })
+    }
+
+    /// Converts `CBytes` into shared storage, so subsequent clones don't copy the data.
+    #[inline]
+    pub fn into_shared(self) -> CBytes {
+        match self.bytes {
+            Some(BytesStorage::Owned(bytes)) => CBytes::new_shared(bytes.into()),
+            _ => self,
+        }
     }
 
+    /// Returns the bytes, or `None` for null (negative length) values.
     #[inline]
     pub fn as_slice(&self) -> Option<&[u8]> {
-        self.bytes.as_deref()
+        self.bytes.as_ref().map(BytesStorage::as_slice)
     }
 
+    /// Checks if the value is null, i.e. was sent with a negative length. Zero-length values
+    /// are not null.
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        self.bytes.is_none()
+    }
+
+    /// Checks if the value is null or has zero length. Use `is_null()` to distinguish both.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        match &self.bytes {
+        match self.as_slice() {
             None => true,
             Some(bytes) => bytes.is_empty(),
         }
@@ -313,7 +478,45 @@ impl CBytes {
     #[inline]
     #[deprecated(note = "Use into_bytes().")]
     pub fn into_plain(self) -> Option<Vec<u8>> {
-        self.bytes
+        self.into_bytes()
+    }
+}
+
+impl Debug for CBytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CBytes")
+            .field("bytes", &self.as_slice())
+            .finish()
+    }
+}
+
+impl PartialEq for CBytes {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for CBytes {}
+
+impl Hash for CBytes {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl Ord for CBytes {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(&other.as_slice())
+    }
+}
+
+impl PartialOrd for CBytes {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -331,7 +534,7 @@ impl FromCursor for CBytes {
 
 impl Serialize for CBytes {
     fn serialize(&self, cursor: &mut Cursor<&mut Vec<u8>>, version: Version) {
-        match &self.bytes {
+        match self.as_slice() {
             Some(bytes) => {
                 let len = bytes.len() as CInt;
                 len.serialize(cursor, version);
@@ -492,16 +695,15 @@ pub fn cursor_next_value_ref<'a>(
     len: usize,
 ) -> CDRSResult<&'a [u8]> {
     let start = cursor.position() as usize;
-    let result = &cursor.get_ref()[start..start + len];
+    let result = start
+        .checked_add(len)
+        .and_then(|end| cursor.get_ref().get(start..end))
+        .ok_or_else(|| {
+            CdrsError::General("cursor_next_value_ref could not retrieve a full slice".into())
+        })?;
     cursor.set_position(cursor.position() + len as u64);
 
-    if result.len() != len {
-        Err(CdrsError::General(
-            "cursor_next_value_ref could not retrieve a full slice".into(),
-        ))
-    } else {
-        Ok(result)
-    }
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -539,6 +741,33 @@ mod tests {
         assert_eq!(cstring, "foo");
     }
 
+    #[test]
+    fn test_verify_short_string() {
+        assert!(verify_short_string(&"a".repeat(MAX_SHORT_COUNT), "Keyspace").is_ok());
+        assert!(verify_short_string(&"a".repeat(MAX_SHORT_COUNT + 1), "Keyspace").is_err());
+    }
+
+    #[test]
+    fn test_verify_bytes_len() {
+        assert!(verify_bytes_len(CInt::MAX as usize, "Value").is_ok());
+        assert!(verify_bytes_len(CInt::MAX as usize + 1, "Value").is_err());
+    }
+
+    #[test]
+    fn test_verify_bytes_map() {
+        let mut map = BTreeMap::new();
+        map.insert("key".to_string(), vec![1]);
+        assert!(verify_bytes_map(&map, "Custom payload").is_ok());
+
+        map.insert("a".repeat(MAX_SHORT_COUNT + 1), vec![]);
+        assert!(verify_bytes_map(&map, "Custom payload").is_err());
+
+        let map: BTreeMap<_, _> = (0..=MAX_SHORT_COUNT)
+            .map(|index| (index.to_string(), vec![]))
+            .collect();
+        assert!(verify_bytes_map(&map, "Custom payload").is_err());
+    }
+
     #[test]
     fn test_serialize_str() {
         let input = "foo";
@@ -589,6 +818,23 @@ mod tests {
         assert_eq!(cbytes.into_bytes().unwrap(), vec![1, 2, 3]);
     }
 
+    #[test]
+    fn test_cbytes_null_and_empty() {
+        let a = &[255, 255, 255, 255, 0, 0, 0, 0];
+        let mut cursor: Cursor<&[u8]> = Cursor::new(a);
+
+        let null = CBytes::from_cursor(&mut cursor, Version::V4).unwrap();
+        assert!(null.is_null());
+        assert!(null.is_empty());
+        assert_eq!(null.as_slice(), None);
+
+        let empty = CBytes::from_cursor(&mut cursor, Version::V4).unwrap();
+        assert!(!empty.is_null());
+        assert!(empty.is_empty());
+        assert_eq!(empty.as_slice(), Some(&[][..]));
+        assert_eq!(empty.serialize_to_vec(Version::V4), vec![0, 0, 0, 0]);
+    }
+
     #[test]
     fn test_cbytes_serialize() {
         let bytes_vec = vec![1, 2, 3];
@@ -599,6 +845,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cbytes_shared() {
+        let a = &[0, 0, 0, 3, 1, 2, 3];
+        let mut cursor: Cursor<&[u8]> = Cursor::new(a);
+        let cbytes = CBytes::shared_from_cursor(&mut cursor, Version::V4).unwrap();
+        assert_eq!(cbytes, CBytes::new(vec![1, 2, 3]));
+        assert_eq!(cbytes.as_slice(), Some(&[1, 2, 3][..]));
+        assert_eq!(cbytes.serialize_to_vec(Version::V4), a);
+
+        let null = &[255, 255, 255, 255];
+        let mut cursor: Cursor<&[u8]> = Cursor::new(null);
+        let cbytes = CBytes::shared_from_cursor(&mut cursor, Version::V4).unwrap();
+        assert_eq!(cbytes, CBytes::new_empty());
+
+        let truncated = &[0, 0, 0, 3, 1];
+        let mut cursor: Cursor<&[u8]> = Cursor::new(truncated);
+        assert!(CBytes::shared_from_cursor(&mut cursor, Version::V4).is_err());
+
+        let cbytes = CBytes::new(vec![1, 2, 3]).into_shared();
+        assert_eq!(cbytes.clone().into_bytes(), Some(vec![1, 2, 3]));
+        assert!(CBytes::new_shared(vec![1].into()) < CBytes::new(vec![2]));
+    }
+
     // CBytesShort
     #[test]
     fn test_cbytesshort_new() {
@@ -1,6 +1,7 @@
 use arrayref::array_ref;
-use integer_encoding::VarInt;
+use chrono::prelude::*;
 use num::BigInt;
+use std::convert::TryFrom;
 use std::io;
 use std::net;
 use std::string::FromUtf8Error;
@@ -8,6 +9,7 @@ use std::string::FromUtf8Error;
 use super::blob::Blob;
 use super::decimal::Decimal;
 use super::duration::Duration;
+use super::vint::decode_vint;
 use crate::error;
 use crate::frame::{FromCursor, Version};
 use crate::types::{
@@ -78,8 +80,50 @@ pub fn decode_date(bytes: &[u8]) -> Result<i32, io::Error> {
     try_i32_from_bytes(bytes)
 }
 
+/// Offset of the Unix epoch in `date` values, which are unsigned days with 1970-01-01 at 2^31.
+pub const DATE_EPOCH_OFFSET: i64 = 1 << 31;
+
+/// Maximum `time` value - the last nanosecond of a day.
+pub const MAX_TIME_NANOS: i64 = 86_399_999_999_999;
+
+// Number of days from 0001-01-01 (CE) to the Unix epoch.
+const UNIX_EPOCH_DAYS_FROM_CE: i64 = 719_163;
+
+// Decodes Cassandra `date` data (bytes) into signed days since Unix epoch
+#[inline]
+pub fn decode_date_days(bytes: &[u8]) -> Result<i64, io::Error> {
+    decode_date(bytes).map(|date| date as u32 as i64 - DATE_EPOCH_OFFSET)
+}
+
+// Decodes Cassandra `date` data (bytes) into a calendar date
+pub fn decode_naive_date(bytes: &[u8]) -> Result<NaiveDate, io::Error> {
+    let days = decode_date_days(bytes)?;
+    i32::try_from(days + UNIX_EPOCH_DAYS_FROM_CE)
+        .ok()
+        .and_then(NaiveDate::from_num_days_from_ce_opt)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Date out of range: {} days since epoch", days),
+            )
+        })
+}
+
+// Encodes a calendar date as Cassandra `date` data (bytes)
+pub fn encode_naive_date(date: NaiveDate) -> [u8; 4] {
+    let days = date.num_days_from_ce() as i64 - UNIX_EPOCH_DAYS_FROM_CE;
+    ((days + DATE_EPOCH_OFFSET) as u32).to_be_bytes()
+}
+
 // Decodes Cassandra `decimal` data (bytes)
 pub fn decode_decimal(bytes: &[u8]) -> Result<Decimal, io::Error> {
+    if bytes.len() < INT_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("Decimal too short: {} bytes", bytes.len()),
+        ));
+    }
+
     let lr = bytes.split_at(INT_LEN);
 
     let scale = try_i32_from_bytes(lr.0)?;
@@ -142,7 +186,8 @@ pub fn decode_list(bytes: &[u8], version: Version) -> Result<Vec<CBytes>, io::Er
     let mut cursor = io::Cursor::new(bytes);
     let l = CInt::from_cursor(&mut cursor, version)
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-    let mut list = Vec::with_capacity(l as usize);
+    // every element takes at least INT_LEN bytes, so don't trust the count for preallocation
+    let mut list = Vec::with_capacity((l.max(0) as usize).min(bytes.len() / INT_LEN));
     for _ in 0..l {
         let b = CBytes::from_cursor(&mut cursor, version)
             .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
@@ -162,7 +207,7 @@ pub fn decode_map(bytes: &[u8], version: Version) -> Result<Vec<(CBytes, CBytes)
     let mut cursor = io::Cursor::new(bytes);
     let l = CInt::from_cursor(&mut cursor, version)
         .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
-    let mut map = Vec::with_capacity(l as usize);
+    let mut map = Vec::with_capacity((l.max(0) as usize).min(bytes.len() / (2 * INT_LEN)));
     for _ in 0..l {
         let k = CBytes::from_cursor(&mut cursor, version)
             .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
@@ -182,7 +227,10 @@ pub fn decode_smallint(bytes: &[u8]) -> Result<i16, io::Error> {
 // Decodes Cassandra `tinyint` data (bytes)
 #[inline]
 pub fn decode_tinyint(bytes: &[u8]) -> Result<i8, io::Error> {
-    Ok(bytes[0] as i8)
+    bytes
+        .first()
+        .map(|byte| *byte as i8)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Missing tinyint value byte"))
 }
 
 // Decodes Cassandra `text` data (bytes)
@@ -197,6 +245,30 @@ pub fn decode_time(bytes: &[u8]) -> Result<i64, io::Error> {
     try_i64_from_bytes(bytes)
 }
 
+// Decodes Cassandra `time` data (bytes) into a time of day
+pub fn decode_naive_time(bytes: &[u8]) -> Result<NaiveTime, io::Error> {
+    let nanos = decode_time(bytes)?;
+    if !(0..=MAX_TIME_NANOS).contains(&nanos) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Time out of range: {} nanoseconds since midnight", nanos),
+        ));
+    }
+
+    NaiveTime::from_num_seconds_from_midnight_opt(
+        (nanos / 1_000_000_000) as u32,
+        (nanos % 1_000_000_000) as u32,
+    )
+    .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))
+}
+
+// Encodes a time of day as Cassandra `time` data (bytes), i.e. nanoseconds since midnight. Leap
+// seconds are clamped to the last nanosecond of the day.
+pub fn encode_naive_time(time: NaiveTime) -> [u8; 8] {
+    let nanos = time.num_seconds_from_midnight() as i64 * 1_000_000_000 + time.nanosecond() as i64;
+    nanos.min(MAX_TIME_NANOS).to_be_bytes()
+}
+
 // Decodes Cassandra `timeuuid` data (bytes)
 #[inline]
 pub fn decode_timeuuid(bytes: &[u8]) -> Result<uuid::Uuid, uuid::Error> {
@@ -212,14 +284,14 @@ pub fn decode_varint(bytes: &[u8]) -> Result<BigInt, io::Error> {
 // Decodes Cassandra `duration` data (bytes)
 #[inline]
 pub fn decode_duration(bytes: &[u8]) -> Result<Duration, io::Error> {
-    let (months, month_bytes_read) =
-        i32::decode_var(bytes).ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
-
-    let (days, day_bytes_read) = i32::decode_var(&bytes[month_bytes_read..])
-        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+    let to_i32 = |value: i64| {
+        i32::try_from(value).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    };
 
-    let (nanoseconds, _) = i64::decode_var(&bytes[(month_bytes_read + day_bytes_read)..])
-        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+    let (months, month_bytes_read) = decode_vint(bytes)?;
+    let (days, day_bytes_read) = decode_vint(&bytes[month_bytes_read..])?;
+    let (nanoseconds, _) = decode_vint(&bytes[(month_bytes_read + day_bytes_read)..])?;
+    let (months, days) = (to_i32(months)?, to_i32(days)?);
 
     Duration::new(months, days, nanoseconds)
         .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
@@ -314,6 +386,31 @@ mod tests {
         assert_eq!(decode_date(&[0, 0, 0, 3]).unwrap(), 3);
     }
 
+    #[test]
+    fn naive_date_test() {
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+        let cases = [
+            (epoch, [0x80, 0, 0, 0], 0),
+            (epoch.pred_opt().unwrap(), [0x7F, 0xFF, 0xFF, 0xFF], -1),
+            (epoch.succ_opt().unwrap(), [0x80, 0, 0, 1], 1),
+            (
+                NaiveDate::from_ymd_opt(2022, 8, 1).unwrap(),
+                [0x80, 0, 0x4B, 0x05],
+                19205,
+            ),
+        ];
+
+        for (date, bytes, days) in cases {
+            assert_eq!(encode_naive_date(date), bytes);
+            assert_eq!(decode_date_days(&bytes).unwrap(), days);
+            assert_eq!(decode_naive_date(&bytes).unwrap(), date);
+        }
+
+        // the full range of `date` exceeds chrono's
+        assert!(decode_naive_date(&[0, 0, 0, 0]).is_err());
+        assert_eq!(decode_date_days(&[0, 0, 0, 0]).unwrap(), -DATE_EPOCH_OFFSET);
+    }
+
     #[test]
     fn decode_double_test() {
         let bytes = to_float_big(0.3);
@@ -347,6 +444,9 @@ mod tests {
             Ok(IpAddr::V6(ref ip)) => assert_eq!(ip.segments(), [0, 0, 0, 0, 0, 0, 0, 0]),
             _ => panic!("wrong ip v6 address"),
         };
+
+        assert!(decode_inet(&[0, 0, 0]).is_err());
+        assert!(decode_inet(&[0; 5]).is_err());
     }
 
     #[test]
@@ -359,12 +459,18 @@ mod tests {
         let results = decode_list(&[0, 0, 0, 1, 0, 0, 0, 2, 1, 2], Version::V4).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].as_slice().unwrap(), &[1, 2]);
+
+        assert!(decode_list(&[0x7f, 0xff, 0xff, 0xff, 0], Version::V4).is_err());
+        assert!(decode_map(&[0x7f, 0xff, 0xff, 0xff, 0], Version::V4).is_err());
     }
 
     #[test]
     fn decode_duration_test() {
-        let result = decode_duration(&[200, 1, 144, 3, 216, 4]).unwrap();
+        let result = decode_duration(&[0x80, 0xC8, 0x81, 0x90, 0x82, 0x58]).unwrap();
         assert_eq!(result, Duration::new(100, 200, 300).unwrap());
+
+        // months out of i32 range
+        assert!(decode_duration(&[0xF8, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]).is_err());
     }
 
     #[test]
@@ -394,10 +500,13 @@ mod tests {
     #[test]
     fn decode_tinyint_test() {
         assert_eq!(decode_tinyint(&[10]).unwrap(), 10);
+        assert!(decode_tinyint(&[]).is_err());
     }
 
     #[test]
     fn decode_decimal_test() {
+        assert!(decode_decimal(&[0, 0, 0]).is_err());
+
         assert_eq!(
             decode_decimal(&[0, 0, 0, 0, 10u8]).unwrap(),
             Decimal::new(10.into(), 0)
@@ -434,6 +543,27 @@ mod tests {
         assert_eq!(decode_time(&[0, 0, 0, 0, 0, 0, 0, 10]).unwrap(), 10);
     }
 
+    #[test]
+    fn naive_time_test() {
+        let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        let last = NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap();
+        let leap = NaiveTime::from_hms_nano_opt(23, 59, 59, 1_500_000_000).unwrap();
+
+        assert_eq!(encode_naive_time(midnight), [0; 8]);
+        assert_eq!(encode_naive_time(last), MAX_TIME_NANOS.to_be_bytes());
+        assert_eq!(encode_naive_time(leap), MAX_TIME_NANOS.to_be_bytes());
+
+        let time = NaiveTime::from_hms_nano_opt(12, 30, 15, 5).unwrap();
+        assert_eq!(decode_naive_time(&encode_naive_time(time)).unwrap(), time);
+        assert_eq!(
+            decode_naive_time(&MAX_TIME_NANOS.to_be_bytes()).unwrap(),
+            last
+        );
+
+        assert!(decode_naive_time(&(-1i64).to_be_bytes()).is_err());
+        assert!(decode_naive_time(&(MAX_TIME_NANOS + 1).to_be_bytes()).is_err());
+    }
+
     #[test]
     fn decode_timeuuid_test() {
         assert_eq!(
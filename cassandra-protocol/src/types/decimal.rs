@@ -1,11 +1,16 @@
 use derive_more::Constructor;
 use float_eq::*;
-use num::BigInt;
+use num::{BigInt, Signed};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
 use std::io::Cursor;
+use std::str::FromStr;
 
+use crate::error;
 use crate::frame::{Serialize, Version};
 
-/// Cassandra Decimal type
+/// Cassandra Decimal type: an arbitrary-precision `unscaled` value multiplied by `10^-scale`.
+/// Unlike floating point conversions, parsing from and displaying as a string is lossless.
 #[derive(Debug, Clone, PartialEq, Constructor, Ord, PartialOrd, Eq, Hash)]
 pub struct Decimal {
     pub unscaled: BigInt,
@@ -13,9 +18,86 @@ pub struct Decimal {
 }
 
 impl Decimal {
-    /// Method that returns plain `BigInt` value.
+    /// Method that returns plain `BigInt` value, truncating any fractional part.
     pub fn as_plain(&self) -> BigInt {
-        self.unscaled.clone() / 10i64.pow(self.scale as u32)
+        let factor = BigInt::from(10).pow(self.scale.unsigned_abs());
+        if self.scale >= 0 {
+            &self.unscaled / factor
+        } else {
+            &self.unscaled * factor
+        }
+    }
+}
+
+impl Display for Decimal {
+    /// Displays the exact value in plain notation, e.g. `-12.340` for unscaled `-12340` and
+    /// scale `3`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.scale <= 0 {
+            return write!(f, "{}", self.as_plain());
+        }
+
+        let scale = self.scale as usize;
+        let digits = self.unscaled.abs().to_string();
+        let digits = if digits.len() <= scale {
+            format!("{}{}", "0".repeat(scale - digits.len() + 1), digits)
+        } else {
+            digits
+        };
+
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        let sign = if self.unscaled.is_negative() { "-" } else { "" };
+        write!(f, "{}{}.{}", sign, integer, fraction)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = error::Error;
+
+    /// Parses a decimal number in plain or scientific notation, e.g. `-12.340` or `1.5e-3`,
+    /// preserving all given digits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || error::Error::General(format!("Invalid decimal: {}", s));
+
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(index) => (
+                &s[..index],
+                s[index + 1..].parse::<i32>().map_err(|_| invalid())?,
+            ),
+            None => (s, 0),
+        };
+
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let (sign, integer) = match integer.strip_prefix('-') {
+            Some(integer) => ("-", integer),
+            None => ("", integer.strip_prefix('+').unwrap_or(integer)),
+        };
+
+        if integer.is_empty() && fraction.is_empty()
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let unscaled = format!("{}{}{}", sign, integer, fraction)
+            .parse::<BigInt>()
+            .map_err(|_| invalid())?;
+        let scale = i32::try_from(fraction.len())
+            .ok()
+            .and_then(|scale| scale.checked_sub(exponent))
+            .ok_or_else(invalid)?;
+
+        Ok(Decimal::new(unscaled, scale))
+    }
+}
+
+impl From<BigInt> for Decimal {
+    #[inline]
+    fn from(unscaled: BigInt) -> Self {
+        Decimal::new(unscaled, 0)
     }
 }
 
@@ -47,6 +129,8 @@ impl_from_for_decimal!(i32);
 impl_from_for_decimal!(i64);
 impl_from_for_decimal!(u8);
 impl_from_for_decimal!(u16);
+impl_from_for_decimal!(u32);
+impl_from_for_decimal!(u64);
 
 impl From<f32> for Decimal {
     fn from(f: f32) -> Decimal {
@@ -115,6 +199,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn should_parse_and_display_losslessly() {
+        let cases = [
+            ("12.340", Decimal::new(12340.into(), 3), "12.340"),
+            ("-0.05", Decimal::new(BigInt::from(-5), 2), "-0.05"),
+            ("+7", Decimal::new(7.into(), 0), "7"),
+            (".5", Decimal::new(5.into(), 1), "0.5"),
+            ("1.5e-3", Decimal::new(15.into(), 4), "0.0015"),
+            ("25E2", Decimal::new(25.into(), -2), "2500"),
+            (
+                "123456789012345678901234567890.01",
+                Decimal::new("12345678901234567890123456789001".parse().unwrap(), 2),
+                "123456789012345678901234567890.01",
+            ),
+        ];
+
+        for (input, decimal, displayed) in cases {
+            assert_eq!(input.parse::<Decimal>().unwrap(), decimal);
+            assert_eq!(decimal.to_string(), displayed);
+        }
+
+        for input in ["", "-", ".", "1.2.3", "1e", "abc", "1 2"] {
+            assert!(input.parse::<Decimal>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn should_compute_plain_value() {
+        assert_eq!(Decimal::new(12345.into(), 2).as_plain(), BigInt::from(123));
+        assert_eq!(
+            Decimal::new(5.into(), -20).as_plain(),
+            BigInt::from(5) * BigInt::from(10).pow(20)
+        );
+    }
+
     #[test]
     fn from_f32() {
         assert_eq!(
@@ -1,8 +1,8 @@
-use integer_encoding::VarInt;
 use std::io::{Cursor, Write};
 use thiserror::Error;
 
 use crate::frame::{Serialize, Version};
+use crate::types::vint::{encode_vint, MAX_VINT_LEN};
 
 /// Possible `Duration` creation error.
 #[derive(Debug, Error, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
@@ -61,15 +61,11 @@ impl Duration {
 
 impl Serialize for Duration {
     fn serialize(&self, cursor: &mut Cursor<&mut Vec<u8>>, _version: Version) {
-        let month_space = self.months.required_space();
-        let day_space = self.days.required_space();
+        let mut buffer = Vec::with_capacity(3 * MAX_VINT_LEN);
 
-        let mut buffer = vec![0u8; month_space + day_space + self.nanoseconds.required_space()];
-
-        self.months.encode_var(&mut buffer);
-        self.days.encode_var(&mut buffer[month_space..]);
-        self.nanoseconds
-            .encode_var(&mut buffer[(month_space + day_space)..]);
+        encode_vint(self.months as i64, &mut buffer);
+        encode_vint(self.days as i64, &mut buffer);
+        encode_vint(self.nanoseconds, &mut buffer);
 
         let _ = cursor.write(&buffer);
     }
@@ -85,7 +81,13 @@ mod tests {
         let duration = Duration::new(100, 200, 300).unwrap();
         assert_eq!(
             duration.serialize_to_vec(Version::V5),
-            vec![200, 1, 144, 3, 216, 4]
+            vec![0x80, 0xC8, 0x81, 0x90, 0x82, 0x58]
+        );
+
+        let duration = Duration::new(-1, 0, -1_000_000_000).unwrap();
+        assert_eq!(
+            duration.serialize_to_vec(Version::V5),
+            vec![0x01, 0x00, 0xF0, 0x77, 0x35, 0x93, 0xFF]
         );
     }
 }
@@ -54,6 +54,8 @@ impl FromCdrs for NonZeroI16 {}
 impl FromCdrs for NonZeroI32 {}
 impl FromCdrs for NonZeroI64 {}
 impl FromCdrs for NaiveDateTime {}
+impl FromCdrs for NaiveDate {}
+impl FromCdrs for NaiveTime {}
 impl<Tz: TimeZone> FromCdrs for DateTime<Tz> {}
 
 pub trait FromCdrsByName {
@@ -96,4 +98,6 @@ impl FromCdrsByName for NonZeroI16 {}
 impl FromCdrsByName for NonZeroI32 {}
 impl FromCdrsByName for NonZeroI64 {}
 impl FromCdrsByName for NaiveDateTime {}
+impl FromCdrsByName for NaiveDate {}
+impl FromCdrsByName for NaiveTime {}
 impl<Tz: TimeZone> FromCdrsByName for DateTime<Tz> {}
@@ -111,6 +111,8 @@ into_rust_by_name!(Row, NonZeroI16);
 into_rust_by_name!(Row, NonZeroI32);
 into_rust_by_name!(Row, NonZeroI64);
 into_rust_by_name!(Row, NaiveDateTime);
+into_rust_by_name!(Row, NaiveDate);
+into_rust_by_name!(Row, NaiveTime);
 into_rust_by_name!(Row, DateTime<Utc>);
 into_rust_by_name!(Row, BigInt);
 
@@ -138,5 +140,92 @@ into_rust_by_index!(Row, NonZeroI16);
 into_rust_by_index!(Row, NonZeroI32);
 into_rust_by_index!(Row, NonZeroI64);
 into_rust_by_index!(Row, NaiveDateTime);
+into_rust_by_index!(Row, NaiveDate);
+into_rust_by_index!(Row, NaiveTime);
 into_rust_by_index!(Row, DateTime<Utc>);
 into_rust_by_index!(Row, BigInt);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::message_result::RowsMetadataFlags;
+
+    fn row(row_content: Vec<CBytes>) -> Row {
+        let col_spec = |name: &str, id| ColSpec {
+            table_spec: None,
+            name: name.into(),
+            col_type: ColTypeOption { id, value: None },
+        };
+
+        Row {
+            metadata: Arc::new(RowsMetadata {
+                flags: RowsMetadataFlags::empty(),
+                columns_count: 3,
+                paging_state: None,
+                new_metadata_id: None,
+                global_table_spec: None,
+                col_specs: vec![
+                    col_spec("int", ColType::Int),
+                    col_spec("text", ColType::Varchar),
+                    col_spec("blob", ColType::Blob),
+                ],
+            }),
+            row_content,
+            protocol_version: Version::V4,
+        }
+    }
+
+    #[test]
+    fn should_report_null_columns_as_empty() {
+        let row = row(vec![
+            CBytes::new_empty(),
+            CBytes::new_empty(),
+            CBytes::new_empty(),
+        ]);
+
+        assert_eq!(
+            IntoRustByName::<i32>::get_by_name(&row, "int").unwrap(),
+            None
+        );
+        assert_eq!(
+            IntoRustByName::<String>::get_by_name(&row, "text").unwrap(),
+            None
+        );
+        assert_eq!(
+            IntoRustByName::<Blob>::get_by_name(&row, "blob").unwrap(),
+            None
+        );
+
+        let expected = column_is_empty_err("int").to_string();
+        assert_eq!(
+            IntoRustByName::<i32>::get_r_by_name(&row, "int")
+                .unwrap_err()
+                .to_string(),
+            expected
+        );
+        assert!(IntoRustByName::<String>::get_r_by_name(&row, "text").is_err());
+        assert!(IntoRustByName::<Blob>::get_r_by_name(&row, "blob").is_err());
+        assert!(IntoRustByIndex::<i32>::get_r_by_index(&row, 0).is_err());
+    }
+
+    #[test]
+    fn should_distinguish_empty_from_null() {
+        let row = row(vec![
+            CBytes::new(vec![]),
+            CBytes::new(vec![]),
+            CBytes::new(vec![]),
+        ]);
+
+        assert!(IntoRustByName::<i32>::get_by_name(&row, "int").is_err());
+        assert_eq!(
+            IntoRustByName::<String>::get_r_by_name(&row, "text").unwrap(),
+            ""
+        );
+        assert_eq!(
+            IntoRustByName::<Blob>::get_r_by_name(&row, "blob")
+                .unwrap()
+                .into_vec(),
+            Vec::<u8>::new()
+        );
+    }
+}
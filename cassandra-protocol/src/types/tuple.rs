@@ -58,6 +58,13 @@ impl Tuple {
             protocol_version,
         }
     }
+
+    /// Decodes a serialized tuple value using given tuple type metadata.
+    pub fn from_bytes(bytes: &[u8], metadata: &CTuple, protocol_version: Version) -> Result<Tuple> {
+        decode_tuple(bytes, metadata.types.len(), protocol_version)
+            .map(|elements| Tuple::new(elements, metadata, protocol_version))
+            .map_err(Into::into)
+    }
 }
 
 impl ByIndex for Tuple {}
@@ -80,6 +87,8 @@ into_rust_by_index!(Tuple, Tuple);
 into_rust_by_index!(Tuple, PrimitiveDateTime);
 into_rust_by_index!(Tuple, Decimal);
 into_rust_by_index!(Tuple, NaiveDateTime);
+into_rust_by_index!(Tuple, NaiveDate);
+into_rust_by_index!(Tuple, NaiveTime);
 into_rust_by_index!(Tuple, DateTime<Utc>);
 into_rust_by_index!(Tuple, BigInt);
 
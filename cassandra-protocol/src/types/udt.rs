@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::io::Cursor;
 use std::net::IpAddr;
 use std::num::{NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8};
 
@@ -8,13 +9,14 @@ use uuid::Uuid;
 
 use crate::error::{column_is_empty_err, Error, Result};
 use crate::frame::message_result::{CUdt, ColType, ColTypeOption, ColTypeOptionValue};
-use crate::frame::Version;
+use crate::frame::{Serialize, Version};
 use crate::types::blob::Blob;
 use crate::types::data_serialization_types::*;
 use crate::types::decimal::Decimal;
 use crate::types::list::List;
 use crate::types::map::Map;
 use crate::types::tuple::Tuple;
+use crate::types::value::{Bytes, Value};
 use crate::types::{ByName, CBytes, IntoRustByName};
 use num::BigInt;
 
@@ -39,6 +41,15 @@ impl Udt {
     }
 }
 
+impl Udt {
+    /// Decodes a serialized UDT value using given UDT metadata.
+    pub fn from_bytes(bytes: &[u8], metadata: &CUdt, protocol_version: Version) -> Result<Udt> {
+        decode_udt(bytes, metadata.descriptions.len(), protocol_version)
+            .map(|fields| Udt::new(fields, metadata, protocol_version))
+            .map_err(Into::into)
+    }
+}
+
 impl ByName for Udt {}
 
 into_rust_by_name!(Udt, Blob);
@@ -63,7 +74,157 @@ into_rust_by_name!(Udt, NonZeroI16);
 into_rust_by_name!(Udt, NonZeroI32);
 into_rust_by_name!(Udt, NonZeroI64);
 into_rust_by_name!(Udt, NaiveDateTime);
+into_rust_by_name!(Udt, NaiveDate);
+into_rust_by_name!(Udt, NaiveTime);
 into_rust_by_name!(Udt, DateTime<Utc>);
 into_rust_by_name!(Udt, BigInt);
 
 udt_as_cassandra_type!();
+
+/// UDT value keyed by field name. Fields are serialized in the order defined by UDT metadata,
+/// regardless of the order they were set in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UdtValue {
+    fields: HashMap<String, Value>,
+}
+
+impl UdtValue {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets a field value.
+    #[must_use]
+    pub fn with_field(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.set_field(name, value);
+        self
+    }
+
+    /// Sets a field value.
+    pub fn set_field(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        self.fields.insert(name.into(), value.into());
+    }
+
+    /// Returns a field value, if set.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.get(name)
+    }
+
+    /// Consumes the value and returns fields keyed by name.
+    pub fn into_fields(self) -> HashMap<String, Value> {
+        self.fields
+    }
+
+    /// Serializes fields in the order defined by given metadata. Fields which were not set are
+    /// sent as null. Returns an error if a field is not defined in metadata.
+    pub fn serialize_with_metadata(&self, metadata: &CUdt) -> Result<Bytes> {
+        if let Some(name) = self.fields.keys().find(|name| {
+            !metadata
+                .descriptions
+                .iter()
+                .any(|(field, _)| field == *name)
+        }) {
+            return Err(Error::General(format!(
+                "Field {} is not defined in UDT {}.{}",
+                name, metadata.ks, metadata.udt_name
+            )));
+        }
+
+        let mut bytes = Vec::new();
+        let mut cursor = Cursor::new(&mut bytes);
+        for (name, _) in &metadata.descriptions {
+            self.fields
+                .get(name)
+                .unwrap_or(&Value::Null)
+                .serialize(&mut cursor, Version::V4);
+        }
+
+        Ok(Bytes::new(bytes))
+    }
+
+    /// Decodes a serialized UDT value into fields keyed by name, using given UDT metadata.
+    pub fn from_bytes(bytes: &[u8], metadata: &CUdt, protocol_version: Version) -> Result<Self> {
+        let values = decode_udt(bytes, metadata.descriptions.len(), protocol_version)?;
+        let fields = metadata
+            .descriptions
+            .iter()
+            .zip(values)
+            .map(|((name, _), value)| {
+                let value = value.into_bytes().map(Value::Some).unwrap_or(Value::Null);
+                (name.clone(), value)
+            })
+            .collect();
+
+        Ok(UdtValue { fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> CUdt {
+        CUdt {
+            ks: "ks".into(),
+            udt_name: "udt".into(),
+            descriptions: vec![
+                (
+                    "a".into(),
+                    ColTypeOption {
+                        id: ColType::Int,
+                        value: None,
+                    },
+                ),
+                (
+                    "b".into(),
+                    ColTypeOption {
+                        id: ColType::Varchar,
+                        value: None,
+                    },
+                ),
+                (
+                    "c".into(),
+                    ColTypeOption {
+                        id: ColType::Int,
+                        value: None,
+                    },
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn should_serialize_in_metadata_order() {
+        let value = UdtValue::new().with_field("b", "x").with_field("a", 1i32);
+        let bytes = value.serialize_with_metadata(&metadata()).unwrap();
+        assert_eq!(
+            bytes.into_inner(),
+            vec![0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 0, 1, b'x', 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_fields() {
+        let value = UdtValue::new().with_field("d", 1i32);
+        assert!(value.serialize_with_metadata(&metadata()).is_err());
+    }
+
+    #[test]
+    fn should_decode_by_name() {
+        let metadata = metadata();
+        let value = UdtValue::new().with_field("b", "x").with_field("a", 1i32);
+        let bytes = value
+            .serialize_with_metadata(&metadata)
+            .unwrap()
+            .into_inner();
+
+        let decoded = UdtValue::from_bytes(&bytes, &metadata, Version::V4).unwrap();
+        assert_eq!(decoded.field("a"), Some(&Value::Some(vec![0, 0, 0, 1])));
+        assert_eq!(decoded.field("b"), Some(&Value::Some(vec![b'x'])));
+        assert_eq!(decoded.field("c"), Some(&Value::Null));
+
+        let udt = Udt::from_bytes(&bytes, &metadata, Version::V4).unwrap();
+        let b: Option<String> = udt.get_by_name("b").unwrap();
+        assert_eq!(b, Some("x".into()));
+    }
+}
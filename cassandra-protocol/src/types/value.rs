@@ -1,17 +1,19 @@
 use std::cmp::Eq;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::Into;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::num::{NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8};
 
 use chrono::prelude::*;
-use num::BigInt;
+use itertools::Itertools;
+use num::{BigInt, BigUint};
 use time::PrimitiveDateTime;
 use uuid::Uuid;
 
 use super::blob::Blob;
+use super::data_serialization_types::{encode_naive_date, encode_naive_time};
 use super::decimal::Decimal;
 use super::duration::Duration;
 use super::*;
@@ -20,11 +22,17 @@ use crate::Error;
 const NULL_INT_VALUE: i32 = -1;
 const NOT_SET_INT_VALUE: i32 = -2;
 
+/// Maximum number of bytes displayed for a value, after which the output is truncated.
+const MAX_DISPLAYED_BYTES: usize = 64;
+
 /// Cassandra value which could be an array of bytes, null and non-set values.
 #[derive(Debug, Clone, PartialEq, Ord, PartialOrd, Eq, Hash)]
 pub enum Value {
     Some(Vec<u8>),
+    /// Null value, serialized with a length of `-1`. Binding it writes a null, i.e. deletes the
+    /// column value.
     Null,
+    /// Unset value, serialized with a length of `-2`. Binding it leaves the column unchanged.
     NotSet,
 }
 
@@ -35,6 +43,43 @@ impl Value {
     {
         Value::Some(v.into().0)
     }
+
+    /// Creates a null value, the same as converting from `None`. Note: CQL doesn't support
+    /// `IS NULL` conditions, and comparing with a bound null never matches any row.
+    #[inline]
+    pub fn null() -> Value {
+        Value::Null
+    }
+
+    /// Creates an unset value, which leaves the bound column unchanged. Requires protocol V4 or
+    /// above.
+    #[inline]
+    pub fn not_set() -> Value {
+        Value::NotSet
+    }
+}
+
+impl Display for Value {
+    /// Displays the value as hex bytes (e.g. `0x0a0b`), `NULL` or `UNSET`. Values longer than 64
+    /// bytes are truncated, with total length appended.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::NotSet => write!(f, "UNSET"),
+            Value::Some(value) => {
+                write!(f, "0x")?;
+                for byte in value.iter().take(MAX_DISPLAYED_BYTES) {
+                    write!(f, "{:02x}", byte)?;
+                }
+
+                if value.len() > MAX_DISPLAYED_BYTES {
+                    write!(f, "...({} bytes)", value.len())?;
+                }
+
+                Ok(())
+            }
+        }
+    }
 }
 
 impl Serialize for Value {
@@ -59,7 +104,7 @@ impl FromCursor for Value {
             CInt::from_be_bytes(buff)
         };
 
-        if value_size > 0 {
+        if value_size >= 0 {
             Ok(Value::Some(cursor_next_value(cursor, value_size as usize)?))
         } else if value_size == -1 {
             Ok(Value::Null)
@@ -219,12 +264,26 @@ impl From<IpAddr> for Bytes {
     #[inline]
     fn from(value: IpAddr) -> Self {
         match value {
-            IpAddr::V4(ip) => Bytes(ip.octets().to_vec()),
-            IpAddr::V6(ip) => Bytes(ip.octets().to_vec()),
+            IpAddr::V4(ip) => ip.into(),
+            IpAddr::V6(ip) => ip.into(),
         }
     }
 }
 
+impl From<Ipv4Addr> for Bytes {
+    #[inline]
+    fn from(value: Ipv4Addr) -> Self {
+        Bytes(value.octets().to_vec())
+    }
+}
+
+impl From<Ipv6Addr> for Bytes {
+    #[inline]
+    fn from(value: Ipv6Addr) -> Self {
+        Bytes(value.octets().to_vec())
+    }
+}
+
 impl From<f32> for Bytes {
     #[inline]
     fn from(value: f32) -> Self {
@@ -248,6 +307,15 @@ impl From<PrimitiveDateTime> for Bytes {
     }
 }
 
+impl From<bytes::Bytes> for Bytes {
+    /// Reuses the underlying buffer if `value` is its only reference and spans the whole
+    /// allocation; copies the data otherwise.
+    #[inline]
+    fn from(value: bytes::Bytes) -> Self {
+        Bytes(value.into())
+    }
+}
+
 impl From<Blob> for Bytes {
     #[inline]
     fn from(value: Blob) -> Self {
@@ -269,6 +337,22 @@ impl From<NaiveDateTime> for Bytes {
     }
 }
 
+impl From<NaiveDate> for Bytes {
+    /// Encodes the date as CQL `date`: days since Unix epoch, offset by 2^31.
+    #[inline]
+    fn from(value: NaiveDate) -> Self {
+        Bytes(encode_naive_date(value).to_vec())
+    }
+}
+
+impl From<NaiveTime> for Bytes {
+    /// Encodes the time as CQL `time`: nanoseconds since midnight.
+    #[inline]
+    fn from(value: NaiveTime) -> Self {
+        Bytes(encode_naive_time(value).to_vec())
+    }
+}
+
 impl From<DateTime<Utc>> for Bytes {
     #[inline]
     fn from(value: DateTime<Utc>) -> Self {
@@ -283,22 +367,86 @@ impl From<Duration> for Bytes {
     }
 }
 
-impl<T: Into<Bytes>> From<Vec<T>> for Bytes {
-    fn from(vec: Vec<T>) -> Bytes {
-        let mut bytes = Vec::with_capacity(INT_LEN);
-        let len = vec.len() as CInt;
+/// Serializes a list, set or map body: an `[int]` element count followed by length-prefixed
+/// elements. Since protocol V3, frozen and non-frozen collections share this encoding when sent as
+/// values; frozenness only affects how the server stores them, and isn't part of result metadata.
+fn serialize_collection<T: Into<Bytes>>(items: impl ExactSizeIterator<Item = T>) -> Bytes {
+    let mut bytes = Vec::with_capacity(INT_LEN);
+    let len = items.len() as CInt;
+
+    bytes.extend_from_slice(&len.to_be_bytes());
+
+    let mut cursor = Cursor::new(&mut bytes);
+    cursor.set_position(INT_LEN as u64);
 
-        bytes.extend_from_slice(&len.to_be_bytes());
+    for v in items {
+        let b: Bytes = v.into();
+        Value::new(b).serialize(&mut cursor, Version::V4);
+    }
+
+    Bytes(bytes)
+}
 
-        let mut cursor = Cursor::new(&mut bytes);
-        cursor.set_position(INT_LEN as u64);
+/// Serializes tuple elements, each as a length-prefixed `[bytes]` value. `None` elements are
+/// encoded as null.
+macro_rules! impl_from_tuple_for_bytes {
+    ($($name:ident),+) => {
+        impl<$($name: Into<Value>),+> From<($($name,)+)> for Bytes {
+            #[allow(non_snake_case)]
+            fn from(($($name,)+): ($($name,)+)) -> Bytes {
+                let mut bytes = Vec::new();
+                let mut cursor = Cursor::new(&mut bytes);
+                $(
+                    $name.into().serialize(&mut cursor, Version::V4);
+                )+
 
-        for v in vec {
-            let b: Bytes = v.into();
-            Value::new(b).serialize(&mut cursor, Version::V4);
+                Bytes(bytes)
+            }
         }
+    };
+}
+
+impl_from_tuple_for_bytes!(A);
+impl_from_tuple_for_bytes!(A, B);
+impl_from_tuple_for_bytes!(A, B, C);
+impl_from_tuple_for_bytes!(A, B, C, D);
+impl_from_tuple_for_bytes!(A, B, C, D, E);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H, I);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H, I, J);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H, I, J, K);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H, I, J, K, L);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H, I, J, K, L, M);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H, I, J, K, L, M, N);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
+impl_from_tuple_for_bytes!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
+
+impl<T: Into<Bytes>> From<Vec<T>> for Bytes {
+    #[inline]
+    fn from(vec: Vec<T>) -> Bytes {
+        serialize_collection(vec.into_iter())
+    }
+}
+
+impl<T: Into<Bytes>> From<HashSet<T>> for Bytes {
+    /// Serializes the set in the same way as a list, which is the wire format of CQL sets.
+    /// Contrary to a `Vec`, elements are unique, so the server doesn't have to deduplicate them,
+    /// and they're sorted by their serialized form, so the result doesn't depend on the iteration
+    /// order of the set.
+    fn from(set: HashSet<T>) -> Bytes {
+        let mut items = set.into_iter().map(Into::into).collect_vec();
+        items.sort_unstable_by(|a: &Bytes, b: &Bytes| a.0.cmp(&b.0));
+        serialize_collection(items.into_iter())
+    }
+}
 
-        Bytes(bytes)
+impl<T: Into<Bytes>> From<BTreeSet<T>> for Bytes {
+    /// Serializes the set in the same way as a list, with unique elements in their order.
+    #[inline]
+    fn from(set: BTreeSet<T>) -> Bytes {
+        serialize_collection(set.into_iter())
     }
 }
 
@@ -308,29 +456,30 @@ impl From<BigInt> for Bytes {
     }
 }
 
+impl From<BigUint> for Bytes {
+    /// Serializes the value as a `varint`, which is always signed, so values with the highest bit
+    /// set get an additional leading zero byte. Decoded values are returned as `BigInt`.
+    #[inline]
+    fn from(value: BigUint) -> Self {
+        BigInt::from(value).into()
+    }
+}
+
 impl<K, V> From<HashMap<K, V>> for Bytes
 where
     K: Into<Bytes> + Hash + Eq,
     V: Into<Bytes>,
 {
+    /// Serializes the map with entries sorted by serialized key, so the result doesn't depend on
+    /// the iteration order of the map.
     fn from(map: HashMap<K, V>) -> Bytes {
-        let mut bytes = Vec::with_capacity(INT_LEN);
-        let len = map.len() as CInt;
-
-        bytes.extend_from_slice(&len.to_be_bytes());
-
-        let mut cursor = Cursor::new(&mut bytes);
-        cursor.set_position(INT_LEN as u64);
+        let mut entries = map
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect_vec();
 
-        for (k, v) in map {
-            let key_bytes: Bytes = k.into();
-            let val_bytes: Bytes = v.into();
-
-            Value::new(key_bytes).serialize(&mut cursor, Version::V4);
-            Value::new(val_bytes).serialize(&mut cursor, Version::V4);
-        }
-
-        Bytes(bytes)
+        entries.sort_unstable_by(|(a, _), (b, _): &(Bytes, Bytes)| a.0.cmp(&b.0));
+        serialize_map(entries.into_iter())
     }
 }
 
@@ -339,25 +488,29 @@ where
     K: Into<Bytes> + Hash + Eq,
     V: Into<Bytes>,
 {
+    /// Serializes the map with entries in key order.
+    #[inline]
     fn from(map: BTreeMap<K, V>) -> Bytes {
-        let mut bytes = Vec::with_capacity(INT_LEN);
-        let len = map.len() as CInt;
-
-        bytes.extend_from_slice(&len.to_be_bytes());
+        serialize_map(map.into_iter().map(|(k, v)| (k.into(), v.into())))
+    }
+}
 
-        let mut cursor = Cursor::new(&mut bytes);
-        cursor.set_position(INT_LEN as u64);
+/// Serializes a map body: an `[int]` entry count followed by length-prefixed keys and values.
+fn serialize_map(entries: impl ExactSizeIterator<Item = (Bytes, Bytes)>) -> Bytes {
+    let mut bytes = Vec::with_capacity(INT_LEN);
+    let len = entries.len() as CInt;
 
-        for (k, v) in map {
-            let key_bytes: Bytes = k.into();
-            let val_bytes: Bytes = v.into();
+    bytes.extend_from_slice(&len.to_be_bytes());
 
-            Value::new(key_bytes).serialize(&mut cursor, Version::V4);
-            Value::new(val_bytes).serialize(&mut cursor, Version::V4);
-        }
+    let mut cursor = Cursor::new(&mut bytes);
+    cursor.set_position(INT_LEN as u64);
 
-        Bytes(bytes)
+    for (k, v) in entries {
+        Value::new(k).serialize(&mut cursor, Version::V4);
+        Value::new(v).serialize(&mut cursor, Version::V4);
     }
+
+    Bytes(bytes)
 }
 
 #[cfg(test)]
@@ -386,6 +539,59 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_new_value_null() {
+        assert_eq!(
+            Value::null().serialize_to_vec(Version::V4),
+            vec![255, 255, 255, 255]
+        );
+        assert_eq!(Value::from(None::<i32>), Value::null());
+        assert_eq!(Value::from(Some(1i32)), Value::new(1i32));
+        assert_eq!(Value::not_set(), Value::NotSet);
+    }
+
+    #[test]
+    fn test_new_value_set() {
+        let set: HashSet<i32> = vec![1].into_iter().collect();
+        assert_eq!(Value::new(set), Value::new(vec![1]));
+
+        let set: HashSet<i32> = vec![3, 1, 2, 1].into_iter().collect();
+        assert_eq!(Value::new(set), Value::new(vec![1, 2, 3]));
+
+        let set: BTreeSet<&str> = vec!["b", "a", "b"].into_iter().collect();
+        assert_eq!(Value::new(set), Value::new(vec!["a", "b"]));
+    }
+
+    #[test]
+    fn test_value_display() {
+        assert_eq!(Value::Some(vec![0x0a, 0xff]).to_string(), "0x0aff");
+        assert_eq!(Value::Some(vec![]).to_string(), "0x");
+        assert_eq!(Value::Null.to_string(), "NULL");
+        assert_eq!(Value::NotSet.to_string(), "UNSET");
+
+        let displayed = Value::Some(vec![1; 100]).to_string();
+        assert_eq!(
+            displayed,
+            format!("0x{}...(100 bytes)", "01".repeat(MAX_DISPLAYED_BYTES))
+        );
+    }
+
+    #[test]
+    fn test_value_deserialization() {
+        for value in [
+            Value::Some(vec![1, 2]),
+            Value::Some(vec![]),
+            Value::Null,
+            Value::NotSet,
+        ] {
+            let data = value.serialize_to_vec(Version::V4);
+            assert_eq!(
+                Value::from_cursor(&mut Cursor::new(data.as_slice()), Version::V4).unwrap(),
+                value
+            );
+        }
+    }
+
     #[test]
     fn test_new_value_all_types() {
         assert_eq!(
@@ -407,7 +613,156 @@ mod tests {
         assert_eq!(Value::new(true), Value::Some(vec!(1)));
         assert_eq!(
             Value::new(Duration::new(100, 200, 300).unwrap()),
-            Value::Some(vec!(200, 1, 144, 3, 216, 4))
+            Value::Some(vec!(0x80, 0xC8, 0x81, 0x90, 0x82, 0x58))
         );
     }
+
+    #[test]
+    fn test_new_value_inet() {
+        use crate::types::data_serialization_types::decode_inet;
+
+        let ipv4 = Ipv4Addr::new(127, 0, 0, 1);
+        let ipv6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+        assert_eq!(Value::new(ipv4), Value::Some(vec![127, 0, 0, 1]));
+        assert_eq!(Value::new(IpAddr::V4(ipv4)), Value::new(ipv4));
+        assert_eq!(Value::new(IpAddr::V6(ipv6)), Value::new(ipv6));
+
+        for ip in [IpAddr::V4(ipv4), IpAddr::V6(ipv6)] {
+            match Value::new(ip) {
+                Value::Some(bytes) => assert_eq!(decode_inet(&bytes).unwrap(), ip),
+                value => panic!("unexpected value: {:?}", value),
+            }
+        }
+
+        // IPv4-mapped IPv6 addresses keep their 16-byte form
+        let mapped = ipv4.to_ipv6_mapped();
+        match Value::new(mapped) {
+            Value::Some(bytes) => {
+                assert_eq!(bytes.len(), 16);
+                assert_eq!(decode_inet(&bytes).unwrap(), IpAddr::V6(mapped));
+            }
+            value => panic!("unexpected value: {:?}", value),
+        }
+    }
+
+    #[test]
+    fn test_new_value_list_frozen_and_non_frozen() {
+        use crate::frame::message_result::{ColType, ColTypeOption, ColTypeOptionValue};
+        use crate::types::data_serialization_types::decode_list;
+        use crate::types::list::List;
+        use crate::types::AsRustType;
+
+        // list<int> and frozen<list<int>> columns share both encoding and result metadata
+        let expected = vec![0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 2];
+        assert_eq!(Value::new(vec![1i32, 2]), Value::Some(expected.clone()));
+
+        let metadata = ColTypeOption {
+            id: ColType::List,
+            value: Some(ColTypeOptionValue::CList(Box::new(ColTypeOption {
+                id: ColType::Int,
+                value: None,
+            }))),
+        };
+
+        for version in [Version::V4, Version::V5] {
+            let list = List::new(
+                metadata.clone(),
+                decode_list(&expected, version).unwrap(),
+                version,
+            );
+            let decoded: Option<Vec<i32>> = list.as_rust_type().unwrap();
+            assert_eq!(decoded, Some(vec![1, 2]));
+        }
+    }
+
+    #[test]
+    fn test_new_value_shared_bytes() {
+        let data = vec![1u8; 4 * 1024 * 1024];
+        let ptr = data.as_ptr();
+
+        let bytes: Bytes = bytes::Bytes::from(data).into();
+        let data = bytes.into_inner();
+        assert_eq!(data.as_ptr(), ptr);
+        assert_eq!(data.len(), 4 * 1024 * 1024);
+
+        let shared = bytes::Bytes::from_static(&[1, 2, 3]);
+        assert_eq!(Value::new(shared.clone()), Value::Some(vec![1, 2, 3]));
+        assert_eq!(shared.as_ref(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_new_value_tuple() {
+        use crate::frame::message_result::{CTuple, ColType, ColTypeOption};
+        use crate::types::tuple::Tuple;
+        use crate::types::IntoRustByIndex;
+
+        let value: Value = (1i32, "ab", None::<i32>).into();
+        let expected = vec![
+            0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 0, 2, b'a', b'b', 0xFF, 0xFF, 0xFF, 0xFF,
+        ];
+        assert_eq!(value, Value::Some(expected.clone()));
+
+        let metadata = CTuple {
+            types: vec![
+                ColTypeOption {
+                    id: ColType::Int,
+                    value: None,
+                },
+                ColTypeOption {
+                    id: ColType::Varchar,
+                    value: None,
+                },
+                ColTypeOption {
+                    id: ColType::Int,
+                    value: None,
+                },
+            ],
+        };
+
+        let tuple = Tuple::from_bytes(&expected, &metadata, Version::V4).unwrap();
+        let first: Option<i32> = tuple.get_by_index(0).unwrap();
+        let second: Option<String> = tuple.get_by_index(1).unwrap();
+        let third: Option<i32> = tuple.get_by_index(2).unwrap();
+        assert_eq!(first, Some(1));
+        assert_eq!(second, Some("ab".into()));
+        assert_eq!(third, None);
+    }
+
+    #[test]
+    fn test_new_value_varint() {
+        use crate::types::data_serialization_types::decode_varint;
+
+        let large: BigUint = BigUint::from(1u8) << 71u32;
+        let expected = vec![0, 0x80, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(Value::new(large.clone()), Value::Some(expected.clone()));
+        assert_eq!(
+            decode_varint(&expected).unwrap(),
+            BigInt::from(large.clone())
+        );
+
+        let negative = -BigInt::from(large);
+        let expected = vec![0x80, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(Value::new(negative.clone()), Value::Some(expected.clone()));
+        assert_eq!(decode_varint(&expected).unwrap(), negative);
+    }
+
+    #[test]
+    fn test_new_value_map() {
+        let map: HashMap<String, String> = vec![
+            ("b".to_string(), "2".to_string()),
+            ("a".to_string(), "1".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let expected = vec![
+            0, 0, 0, 2, 0, 0, 0, 1, b'a', 0, 0, 0, 1, b'1', 0, 0, 0, 1, b'b', 0, 0, 0, 1, b'2',
+        ];
+        assert_eq!(Value::new(map.clone()), Value::Some(expected.clone()));
+        assert_eq!(Value::from(map.clone()), Value::Some(expected));
+
+        let ordered: BTreeMap<String, String> = map.clone().into_iter().collect();
+        assert_eq!(Value::new(ordered), Value::new(map));
+    }
 }
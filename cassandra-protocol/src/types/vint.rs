@@ -0,0 +1,117 @@
+//! Variable length integers (`[vint]` and `[unsigned vint]`), used e.g. by the `duration` type.
+//! Unlike LEB128, the number of extra bytes is stored as leading 1 bits of the first byte, with
+//! remaining bits holding the value in big-endian order. Signed values are zigzag encoded first,
+//! so small negative numbers stay short.
+use std::io;
+
+/// Maximum length of an encoded vint.
+pub const MAX_VINT_LEN: usize = 9;
+
+/// Appends given value encoded as `[unsigned vint]` to the buffer.
+pub fn encode_unsigned_vint(value: u64, buffer: &mut Vec<u8>) {
+    let magnitude = (value | 1).leading_zeros() as usize;
+    let size = (639 - magnitude * 9) >> 6;
+    let extra_bytes = size - 1;
+
+    let mut encoded = [0u8; MAX_VINT_LEN];
+    encoded[1..].copy_from_slice(&value.to_be_bytes());
+
+    let encoded = &mut encoded[MAX_VINT_LEN - size..];
+    encoded[0] |= (0xFF00u16 >> extra_bytes) as u8;
+
+    buffer.extend_from_slice(encoded);
+}
+
+/// Appends given value encoded as `[vint]` to the buffer.
+#[inline]
+pub fn encode_vint(value: i64, buffer: &mut Vec<u8>) {
+    encode_unsigned_vint(((value << 1) ^ (value >> 63)) as u64, buffer);
+}
+
+/// Decodes an `[unsigned vint]` from the beginning of given bytes. Returns the value and the
+/// number of bytes read.
+pub fn decode_unsigned_vint(bytes: &[u8]) -> io::Result<(u64, usize)> {
+    let first_byte = *bytes
+        .first()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+    let extra_bytes = first_byte.leading_ones() as usize;
+    let size = extra_bytes + 1;
+    if bytes.len() < size {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("Expected {} vint bytes, got {}", size, bytes.len()),
+        ));
+    }
+
+    let value = bytes[1..size].iter().fold(
+        (first_byte & (0xFFu16 >> extra_bytes) as u8) as u64,
+        |value, byte| (value << 8) | *byte as u64,
+    );
+
+    Ok((value, size))
+}
+
+/// Decodes a `[vint]` from the beginning of given bytes. Returns the value and the number of bytes
+/// read.
+#[inline]
+pub fn decode_vint(bytes: &[u8]) -> io::Result<(i64, usize)> {
+    decode_unsigned_vint(bytes)
+        .map(|(value, size)| (((value >> 1) as i64) ^ -((value & 1) as i64), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(value: i64) -> Vec<u8> {
+        let mut buffer = vec![];
+        encode_vint(value, &mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn should_encode_vint() {
+        assert_eq!(encoded(0), vec![0]);
+        assert_eq!(encoded(1), vec![2]);
+        assert_eq!(encoded(-1), vec![1]);
+        assert_eq!(encoded(63), vec![0x7E]);
+        assert_eq!(encoded(-64), vec![0x7F]);
+        assert_eq!(encoded(64), vec![0x80, 0x80]);
+        assert_eq!(encoded(100), vec![0x80, 0xC8]);
+        assert_eq!(encoded(8191), vec![0xBF, 0xFE]);
+        assert_eq!(encoded(8192), vec![0xC0, 0x40, 0x00]);
+        assert_eq!(
+            encoded(i64::MAX),
+            vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE]
+        );
+        assert_eq!(encoded(i64::MIN), vec![0xFF; MAX_VINT_LEN]);
+    }
+
+    #[test]
+    fn should_round_trip_vint() {
+        for value in [
+            0,
+            1,
+            -1,
+            64,
+            -65,
+            300,
+            i32::MAX as i64,
+            i32::MIN as i64,
+            1 << 55,
+            -(1 << 56),
+            i64::MAX,
+            i64::MIN,
+        ] {
+            let buffer = encoded(value);
+            assert_eq!(decode_vint(&buffer).unwrap(), (value, buffer.len()));
+        }
+    }
+
+    #[test]
+    fn should_reject_truncated_vint() {
+        assert!(decode_vint(&[]).is_err());
+        assert!(decode_vint(&[0xC0, 0x40]).is_err());
+    }
+}
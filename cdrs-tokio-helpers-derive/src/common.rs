@@ -79,6 +79,12 @@ fn convert_field_into_rust(field: Field) -> TokenStream {
     into_rust_with_args(&field.ty, arguments)
 }
 
+/// Converts a column with given name into a value of given type. Expects a `cdrs` row reference
+/// to be in scope.
+pub fn convert_column_into_rust(field_type: &Type, column_name: &str) -> TokenStream {
+    into_rust_with_args(field_type, quote! { cdrs, #column_name })
+}
+
 fn get_arguments(name: TokenStream) -> TokenStream {
     quote! {
       &cdrs, #name
@@ -172,8 +178,8 @@ fn get_cdrs_type(ty: &Type) -> Type {
         "Uuid" => parse_str("Uuid").unwrap(),
         "Timespec" => parse_str("Timespec").unwrap(),
         "PrimitiveDateTime" => parse_str("PrimitiveDateTime").unwrap(),
-        "Vec" => parse_str("cdrs_tokio::types::list::List").unwrap(),
-        "HashMap" => parse_str("cdrs_tokio::types::map::Map").unwrap(),
+        "Vec" | "HashSet" | "BTreeSet" => parse_str("cdrs_tokio::types::list::List").unwrap(),
+        "HashMap" | "BTreeMap" => parse_str("cdrs_tokio::types::map::Map").unwrap(),
         "Option" => parse_str("Option").unwrap(),
         "NonZeroI8" => parse_str("NonZeroI8").unwrap(),
         "NonZeroI16" => parse_str("NonZeroI16").unwrap(),
@@ -208,6 +214,16 @@ fn as_rust(ty: &Type, val: TokenStream) -> TokenStream {
             let vec_type = get_ident_params_string(ty);
             let inter_rust_type = get_cdrs_type(&vec_type);
             let decoded_item = as_rust(&vec_type, quote! {item});
+            // sets are transferred as lists
+            let result = match get_ident_string(ty).as_str() {
+                "HashSet" => {
+                    quote! { decoded.into_iter().collect::<std::collections::HashSet<#vec_type>>() }
+                }
+                "BTreeSet" => {
+                    quote! { decoded.into_iter().collect::<std::collections::BTreeSet<#vec_type>>() }
+                }
+                _ => quote! { decoded },
+            };
             quote! {
               {
                 let inner: Vec<#inter_rust_type> = #val.as_r_type()?;
@@ -215,7 +231,7 @@ fn as_rust(ty: &Type, val: TokenStream) -> TokenStream {
                 for item in inner {
                   decoded.push(#decoded_item);
                 }
-                decoded
+                #result
               }
             }
         }
@@ -223,6 +239,11 @@ fn as_rust(ty: &Type, val: TokenStream) -> TokenStream {
             let (map_key_type, map_value_type) = get_map_params_string(ty);
             let inter_rust_type = get_cdrs_type(&map_value_type);
             let decoded_item = as_rust(&map_value_type, quote! {val});
+            let result = if get_ident_string(ty) == "BTreeMap" {
+                quote! { decoded.into_iter().collect::<std::collections::BTreeMap<#map_key_type, #map_value_type>>() }
+            } else {
+                quote! { decoded }
+            };
             quote! {
               {
                 let inner: std::collections::HashMap<#map_key_type, #inter_rust_type> = #val.as_r_type()?;
@@ -230,7 +251,7 @@ fn as_rust(ty: &Type, val: TokenStream) -> TokenStream {
                 for (key, val) in inner {
                   decoded.insert(key, #decoded_item);
                 }
-                decoded
+                #result
               }
             }
         }
@@ -1,48 +1,559 @@
 use proc_macro2::TokenStream;
 use quote::*;
-use syn::DeriveInput;
+use syn::{DeriveInput, Field, Ident, Lit, Meta, MetaNameValue, Type};
 
-use crate::common::struct_fields;
+use crate::common::{
+    convert_column_into_rust, get_ident_params_string, get_ident_string, struct_fields,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ColumnKind {
+    PartitionKey,
+    ClusteringKey,
+    Regular,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum CollectionKind {
+    List,
+    Set,
+    Map,
+}
+
+impl CollectionKind {
+    fn new(ty: &Type) -> Option<Self> {
+        match get_ident_string(ty).as_str() {
+            "Vec" => Some(CollectionKind::List),
+            "HashSet" | "BTreeSet" => Some(CollectionKind::Set),
+            "HashMap" | "BTreeMap" => Some(CollectionKind::Map),
+            _ => None,
+        }
+    }
+}
+
+struct Column {
+    ident: Ident,
+    ty: Type,
+    name: String,
+    kind: ColumnKind,
+    optional: bool,
+    null_if_none: bool,
+    collection: Option<CollectionKind>,
+    counter: bool,
+}
+
+impl Column {
+    fn new(field: &Field) -> Self {
+        let ident = field.ident.clone().unwrap();
+        let has_attr = |name: &str| field.attrs.iter().any(|attr| attr.path.is_ident(name));
+
+        let kind = match (has_attr("partition_key"), has_attr("clustering_key")) {
+            (true, true) => panic!(
+                "Field {} cannot be both a partition and a clustering key",
+                ident
+            ),
+            (true, false) => ColumnKind::PartitionKey,
+            (false, true) => ColumnKind::ClusteringKey,
+            (false, false) => ColumnKind::Regular,
+        };
+
+        let name = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("column_name"))
+            .map(|attr| match attr.parse_meta() {
+                Ok(Meta::NameValue(MetaNameValue {
+                    lit: Lit::Str(name),
+                    ..
+                })) => name.value(),
+                _ => panic!(
+                    "Expected #[column_name = \"...\"] attribute on field {}",
+                    ident
+                ),
+            })
+            .unwrap_or_else(|| ident.to_string());
+
+        let counter = has_attr("counter");
+        if counter {
+            if kind != ColumnKind::Regular {
+                panic!(
+                    "Counter field {} cannot be a part of the primary key",
+                    ident
+                );
+            }
+
+            if get_ident_string(&field.ty) != "i64" {
+                panic!("Counter field {} must be of type i64", ident);
+            }
+        }
+
+        let optional = get_ident_string(&field.ty) == "Option";
+        let collection = if optional {
+            CollectionKind::new(&get_ident_params_string(&field.ty))
+        } else {
+            CollectionKind::new(&field.ty)
+        };
+
+        Column {
+            ident,
+            ty: field.ty.clone(),
+            name,
+            kind,
+            optional,
+            null_if_none: has_attr("null_if_none"),
+            collection,
+            counter,
+        }
+    }
+
+    /// Returns an expression converting the field into a `Value`. `None` in optional fields is
+    /// converted into an unset value, unless marked with `#[null_if_none]`.
+    fn value(&self) -> TokenStream {
+        let ident = &self.ident;
+        if self.optional && !self.null_if_none {
+            quote! {
+                match self.#ident {
+                    Some(value) => value.into(),
+                    None => cdrs_tokio::types::value::Value::NotSet,
+                }
+            }
+        } else {
+            quote! {
+                self.#ident.into()
+            }
+        }
+    }
+
+    /// Returns an expression converting a map field into a `Value` containing the set of its keys,
+    /// as expected when removing entries from the map. Sorted maps produce sorted sets.
+    fn key_set_value(&self) -> TokenStream {
+        let ident = &self.ident;
+        let map_ty = if self.optional {
+            get_ident_params_string(&self.ty)
+        } else {
+            self.ty.clone()
+        };
+
+        let set = if get_ident_string(&map_ty) == "BTreeMap" {
+            quote! { std::collections::BTreeSet }
+        } else {
+            quote! { std::collections::HashSet }
+        };
+
+        if !self.optional {
+            quote! {
+                self.#ident.into_keys().collect::<#set<_>>().into()
+            }
+        } else if self.null_if_none {
+            quote! {
+                self.#ident.map(|map| map.into_keys().collect::<#set<_>>()).into()
+            }
+        } else {
+            quote! {
+                match self.#ident {
+                    Some(map) => map.into_keys().collect::<#set<_>>().into(),
+                    None => cdrs_tokio::types::value::Value::NotSet,
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn is_key(&self) -> bool {
+        self.kind != ColumnKind::Regular
+    }
+}
+
+fn assignments<'a>(columns: impl Iterator<Item = &'a Column>, separator: &str) -> String {
+    columns
+        .map(|column| format!("{} = ?", column.name))
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// Returns the table name, qualified with keyspace given by `#[keyspace = "..."]`, if present.
+fn table_name(ast: &DeriveInput) -> String {
+    let name = &ast.ident;
+    ast.attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("keyspace"))
+        .map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(keyspace),
+                ..
+            })) => format!("{}.{}", keyspace.value(), name),
+            _ => panic!(
+                "Expected #[keyspace = \"...\"] attribute on struct {}",
+                name
+            ),
+        })
+        .unwrap_or_else(|| name.to_string())
+}
 
 pub fn impl_db_mirror(ast: &DeriveInput) -> TokenStream {
     let name = &ast.ident;
-    let idents = struct_fields(ast)
+    let table = table_name(ast);
+    let columns = struct_fields(ast)
         .iter()
-        .map(|f| f.ident.clone().unwrap())
+        .map(Column::new)
         .collect::<Vec<_>>();
-    let idents_copy = idents.clone();
 
-    let fields = idents
-        .iter()
-        .map(|i| i.to_string())
-        .collect::<Vec<String>>();
-    let names = fields.join(", ");
-    let question_marks = fields
+    let values = columns.iter().map(Column::value).collect::<Vec<_>>();
+    let column_names = columns.iter().map(|column| &column.name);
+
+    let decoded_fields = columns.iter().map(|column| {
+        let ident = &column.ident;
+        let value = convert_column_into_rust(&column.ty, &column.name);
+        quote! {
+            #ident: #value
+        }
+    });
+
+    let names = columns
         .iter()
-        .map(|_| "?".to_string())
-        .collect::<Vec<String>>()
+        .map(|column| column.name.as_str())
+        .collect::<Vec<_>>()
         .join(", ");
+    let question_marks = columns.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+    let insert_query = format!(
+        "insert into {}({}) values ({})",
+        table, names, question_marks
+    );
+    let insert_if_not_exists_query = format!("{} if not exists", insert_query);
+    let insert_query_with_ttl = format!("{} using ttl ? and timestamp ?", insert_query);
 
-    quote! {
-        impl #name {
+    if !columns
+        .iter()
+        .any(|column| column.kind == ColumnKind::PartitionKey)
+    {
+        panic!(
+            "DbMirror requires at least one field marked with #[partition_key] in {}",
+            name
+        );
+    }
+
+    let key_columns = columns
+        .iter()
+        .filter(|column| column.is_key())
+        .collect::<Vec<_>>();
+    let key_values = key_columns.iter().map(|column| column.value());
+    let primary_key_condition = assignments(key_columns.iter().copied(), " and ");
+    let delete_query = format!("delete from {} where {}", table, primary_key_condition);
+    let select_by_primary_key_query =
+        format!("select * from {} where {}", table, primary_key_condition);
+    let select_all_query = format!("select * from {}", table);
+
+    let value_columns = columns
+        .iter()
+        .filter(|column| !column.is_key())
+        .collect::<Vec<_>>();
+
+    let is_counter_table = value_columns.iter().any(|column| column.counter);
+    if is_counter_table && value_columns.iter().any(|column| !column.counter) {
+        panic!(
+            "DbMirror cannot mix counter and non-counter columns in {} - all non-key fields of a \
+             counter table must be marked with #[counter]",
+            name
+        );
+    }
+
+    let update_methods = if value_columns.is_empty() {
+        quote! {}
+    } else if is_counter_table {
+        let counter_query = |operator: &str| {
+            format!(
+                "update {} set {} where {}",
+                table,
+                value_columns
+                    .iter()
+                    .map(|column| format!("{0} = {0} {1} ?", column.name, operator))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                primary_key_condition
+            )
+        };
+        let increment_query = counter_query("+");
+        let decrement_query = counter_query("-");
+        let update_values = value_columns
+            .iter()
+            .chain(key_columns.iter())
+            .map(|column| column.value());
+
+        quote! {
+            /// Returns an update query incrementing all counters by given values, matched by
+            /// primary key.
+            pub fn increment_query() -> &'static str {
+                #increment_query
+            }
+
+            /// Returns an update query decrementing all counters by given values, matched by
+            /// primary key.
+            pub fn decrement_query() -> &'static str {
+                #decrement_query
+            }
+
+            /// Returns positional values for `increment_query()` and `decrement_query()`: counter
+            /// deltas followed by key columns.
+            pub fn into_update_query_values(self) -> cdrs_tokio::query::QueryValues {
+                cdrs_tokio::query::QueryValues::SimpleValues(vec![
+                    #(
+                        #update_values,
+                    )*
+                ])
+            }
+        }
+    } else {
+        let update_query = format!(
+            "update {} set {} where {}",
+            table,
+            assignments(value_columns.iter().copied(), ", "),
+            primary_key_condition
+        );
+        let update_values = value_columns
+            .iter()
+            .chain(key_columns.iter())
+            .map(|column| column.value());
+
+        quote! {
+            /// Returns an update query for all non-key columns, matched by primary key.
+            pub fn update_query() -> &'static str {
+                #update_query
+            }
+
+            /// Returns positional values for `update_query()`: non-key columns followed by key
+            /// columns.
+            pub fn into_update_query_values(self) -> cdrs_tokio::query::QueryValues {
+                cdrs_tokio::query::QueryValues::SimpleValues(vec![
+                    #(
+                        #update_values,
+                    )*
+                ])
+            }
+        }
+    };
+
+    // counters cannot be inserted, only updated
+    let insert_methods = if is_counter_table {
+        quote! {}
+    } else {
+        quote! {
             pub fn insert_query() -> &'static str {
-                concat!("insert into ", stringify!(#name), "(",
-                  #names,
-                 ") values (",
-                 #question_marks,
-                 ")")
+                #insert_query
+            }
+
+            /// Returns a lightweight transaction insert query, which only inserts if the row doesn't
+            /// exist. Accepts the same values as `insert_query()`. The result contains a row with
+            /// an `[applied]` column, which callers must inspect to find out if the row was
+            /// actually inserted.
+            pub fn insert_if_not_exists_query() -> &'static str {
+                #insert_if_not_exists_query
+            }
+
+            /// Returns an insert query with TTL and timestamp bind markers. Use
+            /// `into_query_values_with_ttl()` to create matching values.
+            pub fn insert_query_with_ttl() -> &'static str {
+                #insert_query_with_ttl
+            }
+
+            /// Returns positional values for `insert_query_with_ttl()`: field values in declaration
+            /// order, followed by given TTL (in seconds) and timestamp (in microseconds), since the
+            /// `using` clause follows the inserted values.
+            pub fn into_query_values_with_ttl(
+                self,
+                ttl: i32,
+                timestamp: i64,
+            ) -> cdrs_tokio::query::QueryValues {
+                cdrs_tokio::query::QueryValues::SimpleValues(vec![
+                    #(
+                        #values,
+                    )*
+                    ttl.into(),
+                    timestamp.into(),
+                ])
             }
+        }
+    };
+
+    let collection_methods = value_columns.iter().filter_map(|column| {
+        let kind = column.collection?;
+        let ident = &column.ident;
+        let update_query = |assignment: String| {
+            format!(
+                "update {} set {} where {}",
+                table, assignment, primary_key_condition
+            )
+        };
+
+        let append_fn = format_ident!("append_to_{}_query", ident);
+        let append_query = update_query(format!("{0} = {0} + ?", column.name));
+        let append_doc = format!(
+            "Returns an update query adding elements to `{}`, matched by primary key.",
+            column.name
+        );
+
+        let remove_fn = format_ident!("remove_from_{}_query", ident);
+        let remove_query = update_query(format!("{0} = {0} - ?", column.name));
+        let remove_doc = if kind == CollectionKind::Map {
+            format!(
+                "Returns an update query removing entries from `{}`, matched by primary key. \
+                 Removed keys must be bound as a set, followed by primary key values, as returned \
+                 by `into_{}_remove_values()`.",
+                column.name, ident
+            )
+        } else {
+            format!(
+                "Returns an update query removing elements from `{}`, matched by primary key.",
+                column.name
+            )
+        };
+
+        let prepend_method = if kind == CollectionKind::List {
+            let prepend_fn = format_ident!("prepend_to_{}_query", ident);
+            let prepend_query = update_query(format!("{0} = ? + {0}", column.name));
+            let prepend_doc = format!(
+                "Returns an update query prepending elements to `{}`, matched by primary key.",
+                column.name
+            );
+
+            quote! {
+                #[doc = #prepend_doc]
+                pub fn #prepend_fn() -> &'static str {
+                    #prepend_query
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        let values_fn = format_ident!("into_{}_update_values", ident);
+        let values_doc = format!(
+            "Returns positional values for collection update queries of `{}`: the field value \
+             followed by key columns.",
+            column.name
+        );
+        let value = column.value();
+        let key_values = key_columns.iter().map(|column| column.value());
+
+        let remove_values_method = if kind == CollectionKind::Map {
+            let remove_values_fn = format_ident!("into_{}_remove_values", ident);
+            let remove_values_doc = format!(
+                "Returns positional values for `remove_from_{}_query()`: the set of keys of the \
+                 field value followed by key columns.",
+                ident
+            );
+            let key_set_value = column.key_set_value();
+            let key_values = key_columns.iter().map(|column| column.value());
+
+            quote! {
+                #[doc = #remove_values_doc]
+                pub fn #remove_values_fn(self) -> cdrs_tokio::query::QueryValues {
+                    cdrs_tokio::query::QueryValues::SimpleValues(vec![
+                        #key_set_value,
+                        #(
+                            #key_values,
+                        )*
+                    ])
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        Some(quote! {
+            #[doc = #append_doc]
+            pub fn #append_fn() -> &'static str {
+                #append_query
+            }
+
+            #prepend_method
+
+            #[doc = #remove_doc]
+            pub fn #remove_fn() -> &'static str {
+                #remove_query
+            }
+
+            #[doc = #values_doc]
+            pub fn #values_fn(self) -> cdrs_tokio::query::QueryValues {
+                cdrs_tokio::query::QueryValues::SimpleValues(vec![
+                    #value,
+                    #(
+                        #key_values,
+                    )*
+                ])
+            }
+
+            #remove_values_method
+        })
+    });
+
+    quote! {
+        impl #name {
+            #insert_methods
 
             pub fn into_query_values(self) -> cdrs_tokio::query::QueryValues {
                 use std::collections::HashMap;
                 let mut values: HashMap<String, cdrs_tokio::types::value::Value> = HashMap::new();
 
                 #(
-                    values.insert(stringify!(#idents).to_string(), self.#idents_copy.into());
+                    values.insert(#column_names.to_string(), #values);
                 )*
 
                 cdrs_tokio::query::QueryValues::NamedValues(values)
             }
+
+            /// Returns positional values in field declaration order, matching `insert_query()`.
+            pub fn into_positional_query_values(self) -> cdrs_tokio::query::QueryValues {
+                cdrs_tokio::query::QueryValues::SimpleValues(vec![
+                    #(
+                        #values,
+                    )*
+                ])
+            }
+
+            /// Returns a delete query matching the whole primary key.
+            pub fn delete_query() -> &'static str {
+                #delete_query
+            }
+
+            /// Returns a select query matching the whole primary key.
+            pub fn select_by_primary_key_query() -> &'static str {
+                #select_by_primary_key_query
+            }
+
+            /// Returns a select query for all rows in the table.
+            pub fn select_all_query() -> &'static str {
+                #select_all_query
+            }
+
+            /// Returns positional primary key values for `delete_query()` and
+            /// `select_by_primary_key_query()`.
+            pub fn into_primary_key_values(self) -> cdrs_tokio::query::QueryValues {
+                cdrs_tokio::query::QueryValues::SimpleValues(vec![
+                    #(
+                        #key_values,
+                    )*
+                ])
+            }
+
+            #update_methods
+
+            #(#collection_methods)*
+
+            /// Creates a new instance from given row, extracting fields by their column names.
+            pub fn try_from_row(cdrs: &cdrs_tokio::types::rows::Row) -> cdrs_tokio::Result<Self> {
+                #[allow(unused_imports)]
+                use cdrs_tokio::frame::TryFromUdt;
+                #[allow(unused_imports)]
+                use cdrs_tokio::types::from_cdrs::FromCdrsByName;
+                #[allow(unused_imports)]
+                use cdrs_tokio::types::IntoRustByName;
+                #[allow(unused_imports)]
+                use cdrs_tokio::types::AsRustType;
+
+                Ok(#name {
+                    #(#decoded_fields),*
+                })
+            }
         }
     }
 }
@@ -14,7 +14,31 @@ use crate::into_cdrs_value::impl_into_cdrs_value;
 use crate::try_from_row::impl_try_from_row;
 use crate::try_from_udt::impl_try_from_udt;
 
-#[proc_macro_derive(DbMirror)]
+/// Generates queries and query values for a struct mirroring a table with the same name.
+///
+/// Supported struct attributes:
+/// * `#[keyspace = "..."]` - qualifies the table name with given keyspace in generated queries.
+///
+/// Supported field attributes:
+/// * `#[partition_key]` - the field is a part of the partition key (at least one is required).
+/// * `#[clustering_key]` - the field is a part of the clustering key.
+/// * `#[column_name = "..."]` - overrides the column name, which defaults to the field name.
+/// * `#[null_if_none]` - writes `None` as `NULL`. By default, `None` values of `Option` fields are
+///   written as unset, which leaves existing data intact and doesn't create tombstones.
+/// * `#[counter]` - the field is an `i64` counter column. Counter tables get increment and
+///   decrement queries instead of insert and update ones. All non-key fields of a counter table
+///   must be counters.
+#[proc_macro_derive(
+    DbMirror,
+    attributes(
+        keyspace,
+        partition_key,
+        clustering_key,
+        column_name,
+        null_if_none,
+        counter
+    )
+)]
 pub fn db_mirror(input: TokenStream) -> TokenStream {
     // Parse the string representation
     let ast = parse_macro_input!(input as DeriveInput);
@@ -7,9 +7,11 @@ use std::sync::Arc;
 use cdrs_tokio::authenticators::StaticPasswordAuthenticatorProvider;
 use cdrs_tokio::cluster::session::{Session, SessionBuilder, TcpSessionBuilder};
 use cdrs_tokio::cluster::{NodeTcpConfigBuilder, TcpConnectionManager};
+use cdrs_tokio::consistency::Consistency;
 use cdrs_tokio::load_balancing::RoundRobinLoadBalancingStrategy;
 use cdrs_tokio::query::*;
 use cdrs_tokio::query_values;
+use cdrs_tokio::statement::StatementParamsBuilder;
 
 use cdrs_tokio::frame::TryFromRow;
 use cdrs_tokio::transport::TransportTcp;
@@ -42,6 +44,7 @@ async fn main() {
     create_table(&mut session).await;
     insert_struct(&mut session).await;
     select_struct(&mut session).await;
+    select_struct_serial(&mut session).await;
     update_struct(&mut session).await;
     delete_struct(&mut session).await;
 }
@@ -129,6 +132,31 @@ async fn select_struct(session: &mut CurrentSession) {
     }
 }
 
+async fn select_struct_serial(session: &mut CurrentSession) {
+    // serial reads use SERIAL or LOCAL_SERIAL as the main consistency to see in-flight
+    // lightweight transactions
+    let select_struct_cql = "SELECT * FROM test_ks.my_test_table WHERE key = ?";
+    let user_key = 1i32;
+    let params = StatementParamsBuilder::new()
+        .with_consistency(Consistency::Serial)
+        .with_values(query_values!(user_key))
+        .build();
+
+    let rows = session
+        .query_with_params(select_struct_cql, params)
+        .await
+        .expect("query")
+        .response_body()
+        .expect("get body")
+        .into_rows()
+        .expect("into rows");
+
+    for row in rows {
+        let my_row: RowStruct = RowStruct::try_from_row(row).expect("into RowStruct");
+        println!("struct got with serial read: {:?}", my_row);
+    }
+}
+
 async fn update_struct(session: &mut CurrentSession) {
     let update_struct_cql = "UPDATE test_ks.my_test_table SET user = ? WHERE key = ?";
     let upd_user = User {
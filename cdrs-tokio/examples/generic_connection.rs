@@ -14,17 +14,17 @@ use cdrs_tokio::{
     query::*,
     query_values,
     retry::DefaultRetryPolicy,
-    transport::TransportTcp,
+    transport::{TransportTcp, MAX_IN_FLIGHT_REQUESTS},
     types::prelude::*,
     TryFromRow, TryFromUdt,
 };
 
 use cdrs_tokio::cluster::connection_pool::ConnectionPoolConfig;
 use cdrs_tokio::cluster::session::{
-    NodeDistanceEvaluatorWrapper, ReconnectionPolicyWrapper, RetryPolicyWrapper,
-    DEFAULT_TRANSPORT_BUFFER_SIZE,
+    NodeDistanceEvaluatorWrapper, ReconnectionPolicyWrapper, RetryPolicyWrapper, SessionOptions,
+    DEFAULT_COMPRESSION_THRESHOLD, DEFAULT_TRANSPORT_BUFFER_SIZE,
 };
-use cdrs_tokio::cluster::{ConnectionManager, KeyspaceHolder};
+use cdrs_tokio::cluster::{ConnectionManager, KeyspaceHolder, VersionHolder};
 use cdrs_tokio::compression::Compression;
 use cdrs_tokio::frame::{Envelope, Version};
 use cdrs_tokio::frame_encoding::ProtocolFrameEncodingFactory;
@@ -108,6 +108,7 @@ impl VirtualConnectionManager {
     async fn new(
         config: &VirtualClusterConfig,
         keyspace_holder: Arc<KeyspaceHolder>,
+        version_holder: Arc<VersionHolder>,
     ) -> Result<Self> {
         Ok(VirtualConnectionManager {
             inner: TcpConnectionManager::new(
@@ -116,9 +117,11 @@ impl VirtualConnectionManager {
                 config.reconnection_policy.clone(),
                 Box::new(ProtocolFrameEncodingFactory::default()),
                 Compression::None,
+                DEFAULT_COMPRESSION_THRESHOLD,
                 DEFAULT_TRANSPORT_BUFFER_SIZE,
                 true,
-                config.version,
+                MAX_IN_FLIGHT_REQUESTS,
+                version_holder,
             ),
             mask: config.mask,
             actual: config.actual,
@@ -130,10 +133,11 @@ impl GenericClusterConfig<TransportTcp, VirtualConnectionManager> for VirtualClu
     fn create_manager(
         &self,
         keyspace_holder: Arc<KeyspaceHolder>,
+        version_holder: Arc<VersionHolder>,
     ) -> BoxFuture<Result<VirtualConnectionManager>> {
         // create a connection manager that points at the rewritten address so that's where it connects, but
         // then return a manager with the 'virtual' address for internal purposes.
-        VirtualConnectionManager::new(self, keyspace_holder).boxed()
+        VirtualConnectionManager::new(self, keyspace_holder, version_holder).boxed()
     }
 
     fn event_channel_capacity(&self) -> usize {
@@ -180,6 +184,7 @@ async fn main() {
         ReconnectionPolicyWrapper(reconnection_policy),
         NodeDistanceEvaluatorWrapper(Box::new(AllLocalNodeDistanceEvaluator::default())),
         None,
+        SessionOptions::default(),
     )
     .await
     .expect("session should be created");
@@ -158,7 +158,8 @@ async fn paged_with_value(session: &CurrentSession) {
         q,
         QueryParamsBuilder::new()
             .with_values(query_values!(1, 2))
-            .build(),
+            .build()
+            .expect("query params"),
     );
 
     // Oddly enough, this returns false the first time...
@@ -183,7 +184,8 @@ async fn paged_with_values_list(session: &CurrentSession) {
         q,
         QueryParamsBuilder::new()
             .with_values(query_values!(vec![100, 101, 102, 103, 104]))
-            .build(),
+            .build()
+            .expect("query params"),
     );
 
     // Macro instead of a function or closure, since problem with lifetimes
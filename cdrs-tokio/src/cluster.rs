@@ -2,18 +2,19 @@ pub(crate) use self::cluster_metadata_manager::ClusterMetadataManager;
 #[cfg(feature = "rust-tls")]
 pub use self::config_rustls::{NodeRustlsConfig, NodeRustlsConfigBuilder};
 pub use self::config_tcp::{NodeTcpConfig, NodeTcpConfigBuilder};
-pub use self::connection_manager::{startup, ConnectionManager};
+pub use self::connection_manager::{connect_with_version_fallback, startup, ConnectionManager};
 pub use self::keyspace_holder::KeyspaceHolder;
 pub use self::node_address::NodeAddress;
 pub use self::node_info::NodeInfo;
-pub use self::pager::{ExecPager, PagerState, QueryPager, SessionPager};
+pub use self::pager::{ExecPager, PagerState, QueryPager, RowsPage, SessionPager};
 #[cfg(feature = "rust-tls")]
 pub use self::rustls_connection_manager::RustlsConnectionManager;
-pub use self::session::connect_generic;
+pub use self::session::{connect_generic, SessionOptions};
 pub(crate) use self::session_context::SessionContext;
 pub use self::tcp_connection_manager::TcpConnectionManager;
 pub use self::token_map::TokenMap;
 pub use self::topology::cluster_metadata::ClusterMetadata;
+pub use self::version_holder::VersionHolder;
 use crate::cluster::connection_pool::ConnectionPoolConfig;
 use crate::future::BoxFuture;
 use crate::transport::CdrsTransport;
@@ -33,6 +34,8 @@ mod metadata_builder;
 mod node_address;
 mod node_info;
 mod pager;
+mod paging_state_validator;
+mod prepared_statement_cache;
 #[cfg(feature = "rust-tls")]
 mod rustls_connection_manager;
 pub mod send_envelope;
@@ -41,11 +44,19 @@ mod session_context;
 mod tcp_connection_manager;
 mod token_map;
 pub mod topology;
+mod version_holder;
 
 /// Generic connection configuration trait that can be used to create user-supplied
 /// connection objects that can be used with the `session::connect()` function.
 pub trait GenericClusterConfig<T: CdrsTransport, CM: ConnectionManager<T>>: Send + Sync {
-    fn create_manager(&self, keyspace_holder: Arc<KeyspaceHolder>) -> BoxFuture<error::Result<CM>>;
+    /// Creates a connection manager sharing given keyspace and protocol version holders with the
+    /// session. The manager can lower the version if servers don't support it, see
+    /// [`connect_with_version_fallback`].
+    fn create_manager(
+        &self,
+        keyspace_holder: Arc<KeyspaceHolder>,
+        version_holder: Arc<VersionHolder>,
+    ) -> BoxFuture<error::Result<CM>>;
 
     /// Returns desired event channel capacity. Take a look at
     /// [`Session`](self::session::Session) builders for more info.
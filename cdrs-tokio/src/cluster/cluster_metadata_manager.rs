@@ -1,4 +1,5 @@
 use arc_swap::ArcSwap;
+use atomic::Atomic;
 use cassandra_protocol::error::{Error, Result};
 use cassandra_protocol::events::{SchemaChange, ServerEvent};
 use cassandra_protocol::frame::events::{
@@ -8,15 +9,13 @@ use cassandra_protocol::frame::events::{
 use cassandra_protocol::frame::message_error::{AdditionalErrorInfo, ErrorBody};
 use cassandra_protocol::frame::{Envelope, Flags, Version};
 use cassandra_protocol::query::{Query, QueryParams, QueryParamsBuilder, QueryValues};
-use cassandra_protocol::token::Murmur3Token;
+use cassandra_protocol::token::Partitioner;
 use cassandra_protocol::types::list::List;
 use cassandra_protocol::types::rows::Row;
 use cassandra_protocol::types::{AsRustType, ByName, IntoRustByName};
 use fxhash::FxHashMap;
 use itertools::Itertools;
-use rand::{thread_rng, Rng};
 use serde_json::{Map, Value as JsonValue};
-use std::convert::TryInto;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -29,7 +28,7 @@ use crate::cluster::connection_pool::ConnectionPoolFactory;
 use crate::cluster::metadata_builder::{add_new_node, build_initial_metadata, refresh_metadata};
 use crate::cluster::topology::{KeyspaceMetadata, Node, NodeState, ReplicationStrategy};
 use crate::cluster::{ClusterMetadata, ConnectionManager};
-use crate::cluster::{NodeInfo, SessionContext};
+use crate::cluster::{NodeInfo, SessionContext, VersionHolder};
 use crate::load_balancing::node_distance_evaluator::NodeDistanceEvaluator;
 use crate::transport::CdrsTransport;
 
@@ -37,6 +36,7 @@ fn find_in_peers(
     peers: &[Row],
     broadcast_rpc_address: SocketAddr,
     control_addr: SocketAddr,
+    partitioner: Partitioner,
 ) -> Result<Option<NodeInfo>> {
     peers
         .iter()
@@ -45,7 +45,7 @@ fn find_in_peers(
                 .filter(|peer_address| {
                     *peer_address == broadcast_rpc_address && is_peer_row_valid(peer)
                 })
-                .map(|peer_address| build_node_info(peer, peer_address))
+                .map(|peer_address| build_node_info(peer, peer_address, partitioner))
         })
         .transpose()
 }
@@ -56,7 +56,7 @@ async fn send_query<T: CdrsTransport>(
     version: Version,
     beta_protocol: bool,
 ) -> Result<Option<Vec<Row>>> {
-    let query_params = QueryParamsBuilder::new().build();
+    let query_params = QueryParams::default();
     send_query_with_params(query, query_params, transport, version, beta_protocol).await
 }
 
@@ -67,7 +67,9 @@ async fn send_query_with_values<T: CdrsTransport, V: Into<QueryValues>>(
     version: Version,
     beta_protocol: bool,
 ) -> Result<Option<Vec<Row>>> {
-    let query_params = QueryParamsBuilder::new().with_values(values.into()).build();
+    let query_params = QueryParamsBuilder::new()
+        .with_values(values.into())
+        .build()?;
     send_query_with_params(query, query_params, transport, version, beta_protocol).await
 }
 
@@ -98,7 +100,11 @@ async fn send_query_with_params<T: CdrsTransport>(
         .map(|body| body.into_rows())
 }
 
-fn build_node_info(row: &Row, broadcast_rpc_address: SocketAddr) -> Result<NodeInfo> {
+fn build_node_info(
+    row: &Row,
+    broadcast_rpc_address: SocketAddr,
+    partitioner: Partitioner,
+) -> Result<NodeInfo> {
     row.get_r_by_name("host_id").and_then(move |host_id| {
         let broadcast_address: Option<IpAddr> = row
             .get_by_name("broadcast_address")
@@ -132,10 +138,12 @@ fn build_node_info(row: &Row, broadcast_rpc_address: SocketAddr) -> Result<NodeI
             datacenter,
             tokens
                 .into_iter()
-                .map(|token| {
-                    token.try_into().unwrap_or_else(|_| {
-                    warn!(%broadcast_rpc_address, "Unsupported token type - using a dummy value.");
-                    Murmur3Token::new(thread_rng().gen()) })
+                .filter_map(|token| match partitioner.parse_token(&token) {
+                    Ok(token) => Some(token),
+                    Err(error) => {
+                        warn!(%broadcast_rpc_address, %token, %error, "Unsupported token - skipping.");
+                        None
+                    }
                 })
                 .collect(),
             rack,
@@ -143,6 +151,22 @@ fn build_node_info(row: &Row, broadcast_rpc_address: SocketAddr) -> Result<NodeI
     })
 }
 
+fn partitioner_from_row(row: &Row) -> Option<Partitioner> {
+    if !row.contains_column("partitioner") {
+        return None;
+    }
+
+    let partitioner: Option<String> = row.get_by_name("partitioner").ok().flatten();
+    partitioner.and_then(|partitioner| {
+        let result = Partitioner::from_class_name(&partitioner);
+        if result.is_none() {
+            warn!(%partitioner, "Unsupported partitioner - token-aware routing will not work.");
+        }
+
+        result
+    })
+}
+
 fn build_node_broadcast_rpc_address(
     row: &Row,
     broadcast_rpc_address: Option<SocketAddr>,
@@ -320,9 +344,10 @@ pub struct ClusterMetadataManager<T: CdrsTransport + 'static, CM: ConnectionMana
     connection_pool_factory: Arc<ConnectionPoolFactory<T, CM>>,
     did_initial_refresh: AtomicBool,
     is_schema_v2: AtomicBool,
+    partitioner: Atomic<Partitioner>,
     session_context: Arc<SessionContext<T>>,
     node_distance_evaluator: Box<dyn NodeDistanceEvaluator + Send + Sync>,
-    version: Version,
+    version: Arc<VersionHolder>,
     beta_protocol: bool,
 }
 
@@ -332,7 +357,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
         connection_pool_factory: Arc<ConnectionPoolFactory<T, CM>>,
         session_context: Arc<SessionContext<T>>,
         node_distance_evaluator: Box<dyn NodeDistanceEvaluator + Send + Sync>,
-        version: Version,
+        version: Arc<VersionHolder>,
         beta_protocol: bool,
     ) -> Self {
         ClusterMetadataManager {
@@ -341,6 +366,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
             connection_pool_factory,
             did_initial_refresh: AtomicBool::new(false),
             is_schema_v2: AtomicBool::new(true),
+            partitioner: Atomic::new(Partitioner::default()),
             session_context,
             node_distance_evaluator,
             version,
@@ -476,7 +502,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
             "SELECT keyspace_name, toJson(replication) AS replication FROM system_schema.keyspaces WHERE keyspace_name = ?",
             QueryValues::SimpleValues(vec![keyspace.into()]),
             control_transport.as_ref(),
-            self.version,
+            self.version.current(),
             self.beta_protocol,
         )
         .await
@@ -541,29 +567,46 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
             let local_info = fetch_control_connection_info(
                 control_transport.as_ref(),
                 &control_addr,
-                self.version,
+                self.version.current(),
                 self.beta_protocol,
             )
             .await?;
 
-            return build_node_info(&local_info, broadcast_rpc_address).map(Some);
+            let partitioner = self.update_partitioner(&local_info);
+            return build_node_info(&local_info, broadcast_rpc_address, partitioner).map(Some);
         }
 
         send_query(
             &format!("SELECT * FROM {}", self.peer_table_name()),
             control_transport.as_ref(),
-            self.version,
+            self.version.current(),
             self.beta_protocol,
         )
         .await
         .map(|peers| {
             peers.and_then(|peers| {
-                find_in_peers(&peers, broadcast_rpc_address, control_addr).transpose()
+                find_in_peers(
+                    &peers,
+                    broadcast_rpc_address,
+                    control_addr,
+                    self.partitioner.load(Ordering::Relaxed),
+                )
+                .transpose()
             })
         })?
         .transpose()
     }
 
+    /// Updates known partitioner from given `system.local` row and returns the current one.
+    fn update_partitioner(&self, local: &Row) -> Partitioner {
+        if let Some(partitioner) = partitioner_from_row(local) {
+            self.partitioner.store(partitioner, Ordering::Relaxed);
+            partitioner
+        } else {
+            self.partitioner.load(Ordering::Relaxed)
+        }
+    }
+
     #[inline]
     fn control_transport(&self) -> Result<Arc<T>> {
         self.session_context
@@ -587,6 +630,12 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
         self.metadata.load().clone()
     }
 
+    /// Forgets all known nodes, releasing their connection pools.
+    #[inline]
+    pub fn clear_metadata(&self) {
+        self.metadata.store(Default::default());
+    }
+
     #[inline]
     pub fn find_node_by_rpc_address(
         &self,
@@ -601,6 +650,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
     pub async fn refresh_metadata(&self) -> Result<()> {
         let (node_infos, keyspaces) =
             tokio::try_join!(self.refresh_node_infos(), self.refresh_keyspaces())?;
+        let partitioner = self.partitioner.load(Ordering::Relaxed);
 
         if self
             .did_initial_refresh
@@ -613,6 +663,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
                 &self.contact_points,
                 &self.connection_pool_factory,
                 self.node_distance_evaluator.as_ref(),
+                partitioner,
             )));
         } else {
             self.metadata.rcu(move |old_metadata| {
@@ -621,6 +672,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
                     old_metadata.as_ref(),
                     &self.connection_pool_factory,
                     self.node_distance_evaluator.as_ref(),
+                    partitioner,
                 )
             });
         };
@@ -633,7 +685,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
         send_query(
             "SELECT keyspace_name, toJson(replication) AS replication FROM system_schema.keyspaces",
             control_transport.as_ref(),
-            self.version,
+            self.version.current(),
             self.beta_protocol,
         )
         .await
@@ -651,7 +703,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
         let local = fetch_control_connection_info(
             control_transport.as_ref(),
             &control_addr,
-            self.version,
+            self.version.current(),
             self.beta_protocol,
         )
         .await?;
@@ -664,7 +716,13 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
         let local_broadcast_rpc_address =
             build_node_broadcast_rpc_address(&local, local_broadcast_rpc_address, control_addr);
 
-        let mut node_infos = vec![build_node_info(&local, local_broadcast_rpc_address)?];
+        let partitioner = self.update_partitioner(&local);
+
+        let mut node_infos = vec![build_node_info(
+            &local,
+            local_broadcast_rpc_address,
+            partitioner,
+        )?];
 
         let peers = self.query_peers(control_transport.as_ref()).await?;
         if let Some(peers) = peers {
@@ -676,8 +734,9 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
                         return None;
                     }
 
-                    broadcast_rpc_address_from_row(row, control_addr)
-                        .map(|broadcast_rpc_address| build_node_info(row, broadcast_rpc_address))
+                    broadcast_rpc_address_from_row(row, control_addr).map(|broadcast_rpc_address| {
+                        build_node_info(row, broadcast_rpc_address, partitioner)
+                    })
                 })
                 .fold_ok(node_infos, |mut node_infos, node_info| {
                     node_infos.push(node_info);
@@ -697,7 +756,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
         let peers_v2_result = send_query(
             "SELECT * FROM system.peers_v2",
             transport,
-            self.version,
+            self.version.current(),
             self.beta_protocol,
         )
         .await;
@@ -725,7 +784,7 @@ impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ClusterMeta
         send_query(
             "SELECT * FROM system.peers",
             transport,
-            self.version,
+            self.version.current(),
             self.beta_protocol,
         )
         .await
@@ -1,16 +1,19 @@
+use std::future::Future;
 use std::io;
 use std::net::SocketAddr;
 use tokio::sync::mpsc::Sender;
+use tracing::*;
 
 #[cfg(test)]
 use mockall::*;
 
-use crate::cluster::KeyspaceHolder;
+use crate::cluster::{KeyspaceHolder, VersionHolder};
 use crate::future::BoxFuture;
 use crate::transport::CdrsTransport;
 use cassandra_protocol::authenticators::SaslAuthenticatorProvider;
 use cassandra_protocol::compression::Compression;
 use cassandra_protocol::error::{Error, Result};
+use cassandra_protocol::frame::message_error::AdditionalErrorInfo;
 use cassandra_protocol::frame::message_response::ResponseBody;
 use cassandra_protocol::frame::{Envelope, Opcode, Version};
 use cassandra_protocol::query::utils::quote;
@@ -42,6 +45,43 @@ mock! {
     }
 }
 
+/// Checks if the server supports given compression by sending an `OPTIONS` request, falling back
+/// to no compression otherwise.
+pub async fn negotiate_compression<T: CdrsTransport>(
+    transport: &T,
+    compression: Compression,
+    version: Version,
+) -> Result<Compression> {
+    if !compression.is_compressed() {
+        return Ok(compression);
+    }
+
+    let options_envelope = Envelope::new_req_options(version);
+    match transport
+        .write_envelope(&options_envelope, true)
+        .await?
+        .response_body()?
+    {
+        ResponseBody::Supported(supported) if supported.supports_compression(compression) => {
+            Ok(compression)
+        }
+        ResponseBody::Supported(_) => {
+            warn!(
+                %compression,
+                "Server does not support requested compression - falling back to no compression."
+            );
+            Ok(Compression::None)
+        }
+        ResponseBody::Error(body) => Err(Error::Server {
+            body,
+            addr: transport.address(),
+        }),
+        _ => Err(Error::General(
+            "Unexpected response to OPTIONS request!".into(),
+        )),
+    }
+}
+
 /// Establishes Cassandra connection with given authentication, last used keyspace and compression.
 pub async fn startup<
     T: CdrsTransport + 'static,
@@ -57,6 +97,15 @@ pub async fn startup<
         Envelope::new_req_startup(compression.as_str().map(String::from), version);
     let start_response = transport.write_envelope(&startup_envelope, true).await?;
 
+    if start_response.opcode == Opcode::Error {
+        if let ResponseBody::Error(body) = start_response.response_body()? {
+            return Err(Error::Server {
+                body,
+                addr: transport.address(),
+            });
+        }
+    }
+
     if start_response.opcode == Opcode::Ready {
         return set_keyspace(transport, keyspace_holder, version).await;
     }
@@ -66,34 +115,7 @@ pub async fn startup<
         let authenticator = body.authenticator()
             .ok_or_else(|| Error::General("Cassandra server did communicate that it needed authentication but the auth schema was missing in the body response".into()))?;
 
-        // This creates a new scope; avoiding a clone
-        // and we check whether
-        // 1. any authenticators has been passed in by client and if not send error back
-        // 2. authenticator is provided by the client and `auth_scheme` presented by
-        //      the server and client are same if not send error back
-        // 3. if it falls through it means the preliminary conditions are true
-
-        let auth_check = authenticator_provider
-            .name()
-            .ok_or_else(|| Error::General("No authenticator was provided".to_string()))
-            .map(|auth| {
-                if authenticator != auth {
-                    let io_err = io::Error::new(
-                        io::ErrorKind::NotFound,
-                        format!(
-                            "Unsupported type of authenticator. {:?} got,
-                             but {} is supported.",
-                            authenticator, auth
-                        ),
-                    );
-                    return Err(Error::Io(io_err));
-                }
-                Ok(())
-            });
-
-        if let Err(err) = auth_check {
-            return Err(err);
-        }
+        verify_authenticator(authenticator, authenticator_provider.name())?;
 
         let authenticator = authenticator_provider.create_authenticator();
         let response = authenticator.initial_response();
@@ -124,6 +146,67 @@ pub async fn startup<
     Err(Error::UnexpectedStartupResponse(start_response.opcode))
 }
 
+/// Establishes a connection with the protocol version from given holder. If the server rejects
+/// protocol V5, the version is lowered to V4 for the whole session and the connection is
+/// established again.
+pub async fn connect_with_version_fallback<T, F, Fut>(
+    version_holder: &VersionHolder,
+    addr: SocketAddr,
+    connect: F,
+) -> Result<T>
+where
+    F: Fn(Version) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let version = version_holder.current();
+    match connect(version).await {
+        Err(error) if version >= Version::V5 && is_unsupported_version(&error) => {
+            warn!(
+                %addr,
+                %version,
+                %error,
+                "Server does not support requested protocol version - falling back to V4."
+            );
+
+            version_holder.downgrade(version, Version::V4);
+            connect(Version::V4).await
+        }
+        result => result,
+    }
+}
+
+/// Checks if given error is the server rejecting the protocol version of a new connection.
+fn is_unsupported_version(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Server { body, .. }
+            if matches!(body.additional_info, AdditionalErrorInfo::Protocol)
+                && body.message.contains("version")
+    )
+}
+
+/// Checks if the authenticator requested by the server matches the one provided by the client.
+fn verify_authenticator(
+    server_authenticator: &str,
+    client_authenticator: Option<&str>,
+) -> Result<()> {
+    let client_authenticator = client_authenticator
+        .ok_or_else(|| Error::General("No authenticator was provided".to_string()))?;
+
+    if server_authenticator != client_authenticator {
+        let io_err = io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "Unsupported type of authenticator. {:?} got, but {} is supported.",
+                server_authenticator, client_authenticator
+            ),
+        );
+        return Err(Error::Io(io_err));
+    }
+
+    Ok(())
+}
+
 async fn set_keyspace<T: CdrsTransport>(
     transport: &T,
     keyspace_holder: &KeyspaceHolder,
@@ -153,3 +236,155 @@ async fn set_keyspace<T: CdrsTransport>(
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cassandra_protocol::compression::Compression;
+    use cassandra_protocol::error::{Error, Result};
+    use cassandra_protocol::frame::message_error::{AdditionalErrorInfo, ErrorBody};
+    use cassandra_protocol::frame::message_supported::{BodyResSupported, COMPRESSION};
+    use cassandra_protocol::frame::{Direction, Envelope, Flags, Opcode, Serialize, Version};
+    use futures::FutureExt;
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+    use std::sync::Mutex;
+
+    use super::{connect_with_version_fallback, negotiate_compression, verify_authenticator};
+    use crate::cluster::VersionHolder;
+    use crate::transport::MockCdrsTransport;
+
+    fn protocol_error(message: &str) -> ErrorBody {
+        ErrorBody {
+            error_code: 0x000A,
+            message: message.into(),
+            additional_info: AdditionalErrorInfo::Protocol,
+        }
+    }
+
+    #[test]
+    fn should_verify_authenticator() {
+        let password_authenticator = "org.apache.cassandra.auth.PasswordAuthenticator";
+
+        assert!(verify_authenticator(password_authenticator, Some(password_authenticator)).is_ok());
+        assert!(verify_authenticator(password_authenticator, None).is_err());
+        assert!(verify_authenticator(password_authenticator, Some("other")).is_err());
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_supported_compression() {
+        let mut data = HashMap::new();
+        data.insert(COMPRESSION.to_string(), vec!["snappy".to_string()]);
+        let body = BodyResSupported { data }.serialize_to_vec(Version::V4);
+
+        let mut transport = MockCdrsTransport::new();
+        transport.expect_write_envelope().returning(move |_, _| {
+            let envelope = Envelope::new(
+                Version::V4,
+                Direction::Response,
+                Flags::empty(),
+                Opcode::Supported,
+                0,
+                body.clone(),
+                None,
+                vec![],
+            );
+
+            async move { Ok(envelope) }.boxed()
+        });
+
+        assert_eq!(
+            negotiate_compression(&transport, Compression::Snappy, Version::V4)
+                .await
+                .unwrap(),
+            Compression::Snappy
+        );
+        assert_eq!(
+            negotiate_compression(&transport, Compression::Lz4, Version::V4)
+                .await
+                .unwrap(),
+            Compression::None
+        );
+    }
+
+    #[tokio::test]
+    async fn should_report_server_error_during_negotiation() {
+        let addr: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let body = protocol_error("Invalid or unsupported protocol version (5)")
+            .serialize_to_vec(Version::V4);
+
+        let mut transport = MockCdrsTransport::new();
+        transport.expect_address().return_const(addr);
+        transport.expect_write_envelope().returning(move |_, _| {
+            let envelope = Envelope::new(
+                Version::V4,
+                Direction::Response,
+                Flags::empty(),
+                Opcode::Error,
+                0,
+                body.clone(),
+                None,
+                vec![],
+            );
+
+            async move { Ok(envelope) }.boxed()
+        });
+
+        assert!(matches!(
+            negotiate_compression(&transport, Compression::Lz4, Version::V5).await,
+            Err(Error::Server { addr: error_addr, .. }) if error_addr == addr
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_v4() {
+        let addr: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let attempts = Mutex::new(vec![]);
+        let connect = |version| {
+            attempts.lock().unwrap().push(version);
+            async move {
+                if version == Version::V5 {
+                    Err(Error::Server {
+                        body: protocol_error("Invalid or unsupported protocol version (5)"),
+                        addr,
+                    })
+                } else {
+                    Ok(version)
+                }
+            }
+        };
+
+        let version_holder = VersionHolder::new(Version::V5);
+        assert_eq!(
+            connect_with_version_fallback(&version_holder, addr, connect)
+                .await
+                .unwrap(),
+            Version::V4
+        );
+        assert_eq!(version_holder.current(), Version::V4);
+
+        // subsequent connections use the lowered version directly
+        assert!(
+            connect_with_version_fallback(&version_holder, addr, connect)
+                .await
+                .is_ok()
+        );
+        assert_eq!(
+            *attempts.lock().unwrap(),
+            vec![Version::V5, Version::V4, Version::V4]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_not_fall_back_on_other_errors() {
+        let addr: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let version_holder = VersionHolder::new(Version::V5);
+
+        let result: Result<()> = connect_with_version_fallback(&version_holder, addr, |_| async {
+            Err(Error::General("connection refused".into()))
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(version_holder.current(), Version::V5);
+    }
+}
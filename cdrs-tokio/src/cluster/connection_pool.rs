@@ -1,18 +1,20 @@
 use arc_swap::{ArcSwap, AsRaw};
-use cassandra_protocol::frame::{Envelope, Version};
+use cassandra_protocol::frame::Envelope;
 use cassandra_protocol::query::utils::quote;
 use futures::future::{join_all, try_join_all};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 use tokio::sync::watch::Receiver;
 use tracing::*;
 
 use crate::cluster::topology::NodeDistance;
-use crate::cluster::ConnectionManager;
-use crate::error::{Error, Result as CdrsResult};
+use crate::cluster::{ConnectionManager, VersionHolder};
+use crate::error::{Error, Result as CdrsResult, TimeoutOperation};
+use crate::metrics::Metrics;
 use crate::transport::CdrsTransport;
 
 async fn new_connection<T: CdrsTransport, CM: ConnectionManager<T>>(
@@ -26,11 +28,9 @@ async fn new_connection<T: CdrsTransport, CM: ConnectionManager<T>>(
             connection_manager.connection(None, None, broadcast_rpc_address),
         )
         .await
-        .map_err(|_| {
-            Error::Timeout(format!(
-                "Timeout waiting for connection to: {}",
-                broadcast_rpc_address
-            ))
+        .map_err(|_| Error::Timeout {
+            operation: TimeoutOperation::Connect(broadcast_rpc_address),
+            elapsed: timeout,
         })
         .and_then(|result| result)
     } else {
@@ -40,14 +40,27 @@ async fn new_connection<T: CdrsTransport, CM: ConnectionManager<T>>(
     }
 }
 
+/// Default number of in-flight requests on a connection, above which a pool opens an additional
+/// connection, if allowed by its maximum size.
+pub const DEFAULT_NEW_CONNECTION_THRESHOLD: usize = 800;
+
 /// Configuration for node connection pools. By default, the pool size depends on the number of
-/// cpu for local nodes and a fixed value for remote, and there is no timeout. If the distance to a
-/// given node is unknown, it is treated as remote.
+/// cpu for local nodes and a fixed value for remote, and there is no timeout nor heartbeat. If the
+/// distance to a given node is unknown, it is treated as remote.
+///
+/// Pools start with their minimum size and grow up to their maximum size, which defaults to the
+/// minimum, when connections get busy. Connections above the minimum are closed after being idle
+/// for the configured idle timeout.
 #[derive(Clone, Copy)]
 pub struct ConnectionPoolConfig {
     local_size: usize,
     remote_size: usize,
+    max_local_size: usize,
+    max_remote_size: usize,
+    new_connection_threshold: usize,
+    idle_timeout: Option<Duration>,
     connect_timeout: Option<Duration>,
+    heartbeat_interval: Option<Duration>,
 }
 
 impl Default for ConnectionPoolConfig {
@@ -55,7 +68,12 @@ impl Default for ConnectionPoolConfig {
         ConnectionPoolConfig {
             local_size: 1,
             remote_size: 1,
+            max_local_size: 1,
+            max_remote_size: 1,
+            new_connection_threshold: DEFAULT_NEW_CONNECTION_THRESHOLD,
+            idle_timeout: None,
             connect_timeout: None,
+            heartbeat_interval: None,
         }
     }
 }
@@ -67,7 +85,55 @@ impl ConnectionPoolConfig {
         ConnectionPoolConfig {
             local_size,
             remote_size,
+            max_local_size: local_size,
+            max_remote_size: remote_size,
             connect_timeout,
+            ..Default::default()
+        }
+    }
+
+    /// Enables sending an `OPTIONS` request on every connection at given interval, which keeps
+    /// idle connections alive and detects dead ones. A connection which doesn't respond within the
+    /// interval is replaced with a new one.
+    #[must_use]
+    pub fn with_heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        self.heartbeat_interval = Some(heartbeat_interval);
+        self
+    }
+
+    /// Sets the maximum number of connections to local and remote nodes. Must not be lower than
+    /// the minimum sizes given to [`ConnectionPoolConfig::new`].
+    #[must_use]
+    pub fn with_max_size(mut self, max_local_size: usize, max_remote_size: usize) -> Self {
+        assert!(max_local_size >= self.local_size && max_remote_size >= self.remote_size);
+        self.max_local_size = max_local_size;
+        self.max_remote_size = max_remote_size;
+        self
+    }
+
+    /// Sets the number of in-flight requests on a connection, above which a new connection is
+    /// opened, unless the pool already has its maximum size. Defaults to
+    /// `DEFAULT_NEW_CONNECTION_THRESHOLD`.
+    #[must_use]
+    pub fn with_new_connection_threshold(mut self, new_connection_threshold: usize) -> Self {
+        assert!(new_connection_threshold > 0);
+        self.new_connection_threshold = new_connection_threshold;
+        self
+    }
+
+    /// Closes connections above the minimum pool size, which haven't been used for given time.
+    #[must_use]
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    #[inline]
+    fn size_bounds(&self, node_distance: NodeDistance) -> (usize, usize) {
+        if node_distance == NodeDistance::Local {
+            (self.local_size, self.max_local_size)
+        } else {
+            (self.remote_size, self.max_remote_size)
         }
     }
 }
@@ -75,24 +141,27 @@ impl ConnectionPoolConfig {
 /// Factory for node connection pools.
 pub struct ConnectionPoolFactory<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> {
     config: ConnectionPoolConfig,
-    version: Version,
+    version: Arc<VersionHolder>,
     connection_manager: Arc<CM>,
     keyspace_receiver: Receiver<Option<String>>,
+    metrics: Arc<dyn Metrics>,
     _transport: PhantomData<T>,
 }
 
 impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPoolFactory<T, CM> {
     pub fn new(
         config: ConnectionPoolConfig,
-        version: Version,
+        version: Arc<VersionHolder>,
         connection_manager: CM,
         keyspace_receiver: Receiver<Option<String>>,
+        metrics: Arc<dyn Metrics>,
     ) -> Self {
         ConnectionPoolFactory {
             config,
             version,
             connection_manager: Arc::new(connection_manager),
             keyspace_receiver,
+            metrics,
             _transport: Default::default(),
         }
     }
@@ -102,6 +171,11 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPoolFactory<T, CM> {
         self.connection_manager.as_ref()
     }
 
+    #[inline]
+    pub(crate) fn metrics(&self) -> &dyn Metrics {
+        self.metrics.as_ref()
+    }
+
     pub async fn create(
         &self,
         node_distance: NodeDistance,
@@ -113,17 +187,40 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPoolFactory<T, CM> {
                 broadcast_rpc_address,
                 node_distance,
                 self.config,
+                self.metrics.clone(),
             )
             .await?,
         );
 
+        if let Some(heartbeat_interval) = self.config.heartbeat_interval {
+            tokio::spawn(Self::send_heartbeats(
+                Arc::downgrade(&pool),
+                heartbeat_interval,
+                self.version.clone(),
+            ));
+        }
+
+        if let Some(idle_timeout) = self.config.idle_timeout {
+            if pool.max_size > pool.min_size {
+                tokio::spawn(Self::reap_idle_connections(
+                    Arc::downgrade(&pool),
+                    idle_timeout,
+                ));
+            }
+        }
+
         // watch for keyspace changes
         let mut keyspace_receiver = self.keyspace_receiver.clone();
-        let pool_clone = pool.clone();
-        let version = self.version;
+        let weak_pool = Arc::downgrade(&pool);
+        let version = self.version.clone();
 
         tokio::spawn(async move {
             while let Ok(()) = keyspace_receiver.changed().await {
+                let pool_clone = match weak_pool.upgrade() {
+                    Some(pool) => pool,
+                    None => break,
+                };
+
                 let keyspace = keyspace_receiver.borrow().clone();
                 if let Some(keyspace) = keyspace {
                     let use_envelope = Arc::new(Envelope::new_req_query(
@@ -138,11 +235,11 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPoolFactory<T, CM> {
                         None,
                         None,
                         Default::default(),
-                        version,
+                        version.current(),
                     ));
 
-                    join_all(pool_clone.pool.iter()
-                        .map(|connection| connection.load().clone())
+                    join_all(pool_clone.slots.load().iter()
+                        .map(|slot| slot.connection.load_full())
                         .filter(|connection| !connection.is_broken())
                         .map(|connection| {
                             let use_envelope = use_envelope.clone();
@@ -158,6 +255,113 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPoolFactory<T, CM> {
 
         Ok(pool)
     }
+
+    async fn send_heartbeats(
+        pool: Weak<ConnectionPool<T, CM>>,
+        heartbeat_interval: Duration,
+        version: Arc<VersionHolder>,
+    ) {
+        loop {
+            tokio::time::sleep(heartbeat_interval).await;
+
+            let options_envelope = Envelope::new_req_options(version.current());
+
+            match pool.upgrade() {
+                Some(pool) => pool.heartbeat(&options_envelope, heartbeat_interval).await,
+                None => break,
+            }
+        }
+    }
+
+    async fn reap_idle_connections(pool: Weak<ConnectionPool<T, CM>>, idle_timeout: Duration) {
+        // checking twice per timeout keeps connections at most 1.5 times the timeout
+        let check_interval = idle_timeout / 2;
+
+        loop {
+            tokio::time::sleep(check_interval).await;
+
+            match pool.upgrade() {
+                Some(pool) => pool.reap_idle(idle_timeout),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Pooled connection along with its usage statistics.
+struct PoolSlot<T> {
+    connection: ArcSwap<T>,
+    in_flight_requests: AtomicUsize,
+    last_used: Mutex<Instant>,
+}
+
+impl<T> PoolSlot<T> {
+    fn new(connection: T) -> Self {
+        PoolSlot {
+            connection: ArcSwap::from_pointee(connection),
+            in_flight_requests: AtomicUsize::new(0),
+            last_used: Mutex::new(Instant::now()),
+        }
+    }
+
+    #[inline]
+    fn in_flight_requests(&self) -> usize {
+        self.in_flight_requests.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn is_idle(&self, idle_timeout: Duration) -> bool {
+        self.in_flight_requests() == 0 && self.last_used.lock().unwrap().elapsed() >= idle_timeout
+    }
+}
+
+/// Connection taken from a pool, which counts as used by an in-flight request until dropped.
+pub struct PooledConnection<T> {
+    connection: Arc<T>,
+    slot: Arc<PoolSlot<T>>,
+}
+
+impl<T> PooledConnection<T> {
+    fn new(connection: Arc<T>, slot: Arc<PoolSlot<T>>) -> Self {
+        slot.in_flight_requests.fetch_add(1, Ordering::Relaxed);
+        *slot.last_used.lock().unwrap() = Instant::now();
+
+        PooledConnection { connection, slot }
+    }
+}
+
+impl<T> Deref for PooledConnection<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.connection.as_ref()
+    }
+}
+
+impl<T> Drop for PooledConnection<T> {
+    fn drop(&mut self) {
+        *self.slot.last_used.lock().unwrap() = Instant::now();
+        self.slot.in_flight_requests.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Allows only one pool growth at a time.
+struct GrowthGuard<'a>(&'a AtomicBool);
+
+impl<'a> GrowthGuard<'a> {
+    fn try_start(is_growing: &'a AtomicBool) -> Option<Self> {
+        is_growing
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| GrowthGuard(is_growing))
+    }
+}
+
+impl Drop for GrowthGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
 }
 
 /// Node connection pool.
@@ -165,25 +369,28 @@ pub struct ConnectionPool<T: CdrsTransport, CM: ConnectionManager<T>> {
     connection_manager: Arc<CM>,
     broadcast_rpc_address: SocketAddr,
     config: ConnectionPoolConfig,
-    pool: Vec<ArcSwap<T>>,
+    metrics: Arc<dyn Metrics>,
+    min_size: usize,
+    max_size: usize,
+    slots: ArcSwap<Vec<Arc<PoolSlot<T>>>>,
+    // serializes pool resizing, so concurrent changes can't get lost
+    resize_lock: Mutex<()>,
+    is_growing: AtomicBool,
     current_index: AtomicUsize,
 }
 
-impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPool<T, CM> {
+impl<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> ConnectionPool<T, CM> {
     async fn new(
         connection_manager: Arc<CM>,
         broadcast_rpc_address: SocketAddr,
         node_distance: NodeDistance,
         config: ConnectionPoolConfig,
+        metrics: Arc<dyn Metrics>,
     ) -> CdrsResult<Self> {
-        let size = if node_distance == NodeDistance::Local {
-            config.local_size
-        } else {
-            config.remote_size
-        };
+        let (min_size, max_size) = config.size_bounds(node_distance);
 
         // initialize the pool
-        let pool = try_join_all((0..size).into_iter().map(|_| {
+        let slots = try_join_all((0..min_size).map(|_| {
             new_connection(
                 connection_manager.as_ref(),
                 broadcast_rpc_address,
@@ -192,28 +399,158 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPool<T, CM> {
         }))
         .await?
         .into_iter()
-        .map(ArcSwap::from_pointee)
+        .map(|connection| {
+            metrics.on_connection_open(broadcast_rpc_address);
+            Arc::new(PoolSlot::new(connection))
+        })
         .collect();
 
         Ok(ConnectionPool {
             connection_manager,
             broadcast_rpc_address,
             config,
-            pool,
+            metrics,
+            min_size,
+            max_size,
+            slots: ArcSwap::from_pointee(slots),
+            resize_lock: Mutex::new(()),
+            is_growing: AtomicBool::new(false),
             current_index: AtomicUsize::new(0),
         })
     }
 
+    /// Returns current number of pooled connections.
     #[inline]
-    pub async fn connection(&self) -> CdrsResult<Arc<T>> {
-        let index = self.current_index.fetch_add(1, Ordering::Relaxed) % self.pool.len();
-        let slot = &self.pool[index];
+    pub fn size(&self) -> usize {
+        self.slots.load().len()
+    }
 
-        let connection = slot.load().clone();
-        if !connection.is_broken() {
-            return Ok(connection);
+    pub async fn connection(self: &Arc<Self>) -> CdrsResult<PooledConnection<T>> {
+        let slot = {
+            let slots = self.slots.load();
+            let index = self.current_index.fetch_add(1, Ordering::Relaxed) % slots.len();
+            slots[index].clone()
+        };
+
+        // new connections are added in the background, so the request doesn't wait for them
+        if slot.in_flight_requests() >= self.config.new_connection_threshold
+            && self.size() < self.max_size
+            && !self.is_growing.load(Ordering::Relaxed)
+        {
+            tokio::spawn(Self::grow(Arc::downgrade(self)));
+        }
+
+        let connection = slot.connection.load_full();
+        let connection = if connection.is_broken() {
+            self.reconnect(&slot.connection, connection).await?
+        } else {
+            connection
+        };
+
+        Ok(PooledConnection::new(connection, slot))
+    }
+
+    /// Adds a new connection to the pool, unless it's already at its maximum size, another
+    /// request is growing it or it has been dropped.
+    async fn grow(pool: Weak<Self>) {
+        let pool = match pool.upgrade() {
+            Some(pool) => pool,
+            None => return,
+        };
+
+        if pool.size() >= pool.max_size {
+            return;
         }
 
+        let _growth_guard = match GrowthGuard::try_start(&pool.is_growing) {
+            Some(guard) => guard,
+            None => return,
+        };
+
+        debug!(broadcast_rpc_address = %pool.broadcast_rpc_address, "Growing connection pool.");
+
+        let connection = match new_connection(
+            pool.connection_manager.as_ref(),
+            pool.broadcast_rpc_address,
+            pool.config.connect_timeout,
+        )
+        .await
+        {
+            Ok(connection) => connection,
+            Err(error) => {
+                warn!(%error, broadcast_rpc_address = %pool.broadcast_rpc_address, "Error growing connection pool!");
+                return;
+            }
+        };
+
+        pool.metrics.on_connection_open(pool.broadcast_rpc_address);
+
+        let slot = Arc::new(PoolSlot::new(connection));
+
+        let _resize_lock = pool.resize_lock.lock().unwrap();
+        let mut slots = pool.slots.load().as_ref().clone();
+        slots.push(slot);
+        pool.slots.store(Arc::new(slots));
+    }
+
+    /// Closes connections above the minimum size, which are idle for at least given time.
+    fn reap_idle(&self, idle_timeout: Duration) {
+        let _resize_lock = self.resize_lock.lock().unwrap();
+
+        let slots = self.slots.load();
+        if slots.len() <= self.min_size {
+            return;
+        }
+
+        let (core, extra) = slots.split_at(self.min_size);
+        let mut retained = core.to_vec();
+        retained.extend(
+            extra
+                .iter()
+                .filter(|slot| !slot.is_idle(idle_timeout))
+                .cloned(),
+        );
+
+        let reaped = slots.len() - retained.len();
+        if reaped == 0 {
+            return;
+        }
+
+        debug!(broadcast_rpc_address = %self.broadcast_rpc_address, reaped, "Closing idle connections.");
+
+        self.slots.store(Arc::new(retained));
+        for _ in 0..reaped {
+            self.metrics.on_connection_close(self.broadcast_rpc_address);
+        }
+    }
+
+    /// Sends given envelope on every working connection, and replaces the ones which don't
+    /// respond in time. Broken connections are left for lazy replacement.
+    async fn heartbeat(&self, envelope: &Envelope, timeout: Duration) {
+        let slots = self.slots.load_full();
+        join_all(slots.iter().map(|slot| async move {
+            let connection = slot.connection.load_full();
+            if connection.is_broken() {
+                return;
+            }
+
+            let response =
+                tokio::time::timeout(timeout, connection.write_envelope(envelope, false)).await;
+
+            if let Ok(Ok(_)) = response {
+                return;
+            }
+
+            warn!(broadcast_rpc_address = %self.broadcast_rpc_address, "Connection heartbeat failed - reconnecting.");
+
+            if let Err(error) = self.reconnect(&slot.connection, connection).await {
+                error!(%error, broadcast_rpc_address = %self.broadcast_rpc_address, "Error replacing connection!");
+            }
+        }))
+        .await;
+    }
+
+    async fn reconnect(&self, slot: &ArcSwap<T>, connection: Arc<T>) -> CdrsResult<Arc<T>> {
         debug!("Establishing new connection...");
 
         let new_connection = Arc::new(
@@ -228,6 +565,10 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPool<T, CM> {
         // another thread might have already updated this slot, so try once and use current one
         let previous = slot.compare_and_swap(&connection, new_connection.clone());
 
+        // either the replaced or the new connection gets discarded
+        self.metrics.on_connection_open(self.broadcast_rpc_address);
+        self.metrics.on_connection_close(self.broadcast_rpc_address);
+
         Ok(if previous.as_raw() == (&connection).as_raw() {
             new_connection
         } else {
@@ -235,3 +576,231 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> ConnectionPool<T, CM> {
         })
     }
 }
+
+impl<T: CdrsTransport, CM: ConnectionManager<T>> Drop for ConnectionPool<T, CM> {
+    fn drop(&mut self) {
+        for _ in self.slots.load().iter() {
+            self.metrics.on_connection_close(self.broadcast_rpc_address);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cassandra_protocol::error::Error;
+    use cassandra_protocol::frame::{Envelope, Version};
+    use futures::FutureExt;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::watch;
+
+    use crate::cluster::connection_manager::MockConnectionManager;
+    use crate::cluster::connection_pool::{
+        ConnectionPool, ConnectionPoolConfig, ConnectionPoolFactory,
+    };
+    use crate::cluster::topology::NodeDistance;
+    use crate::cluster::VersionHolder;
+    use crate::metrics::{Metrics, NoopMetrics};
+    use crate::transport::MockCdrsTransport;
+
+    #[derive(Default)]
+    struct ConnectionCountingMetrics {
+        open: AtomicUsize,
+        closed: AtomicUsize,
+    }
+
+    impl Metrics for ConnectionCountingMetrics {
+        fn on_connection_open(&self, _node: SocketAddr) {
+            self.open.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_connection_close(&self, _node: SocketAddr) {
+            self.closed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_replace_connections_failing_heartbeat() {
+        let connection_count = Arc::new(AtomicUsize::new(0));
+
+        let mut connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
+        let connection_count_clone = connection_count.clone();
+        connection_manager
+            .expect_connection()
+            .returning(move |_, _, _| {
+                let index = connection_count_clone.fetch_add(1, Ordering::SeqCst);
+
+                let mut transport = MockCdrsTransport::new();
+                transport.expect_is_broken().return_const(false);
+                transport.expect_write_envelope().returning(move |_, _| {
+                    // only the first connection is unresponsive
+                    let result = if index == 0 {
+                        Err(Error::General("test".into()))
+                    } else {
+                        Ok(Envelope::new_req_options(Version::V4))
+                    };
+
+                    async move { result }.boxed()
+                });
+
+                async move { Ok(transport) }.boxed()
+            });
+
+        let pool = ConnectionPool::new(
+            Arc::new(connection_manager),
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9042),
+            NodeDistance::Local,
+            ConnectionPoolConfig::new(2, 1, None).with_heartbeat_interval(Duration::from_secs(1)),
+            Arc::new(NoopMetrics),
+        )
+        .await
+        .unwrap();
+
+        let envelope = Envelope::new_req_options(Version::V4);
+
+        pool.heartbeat(&envelope, Duration::from_secs(1)).await;
+        assert_eq!(connection_count.load(Ordering::SeqCst), 3);
+
+        pool.heartbeat(&envelope, Duration::from_secs(1)).await;
+        assert_eq!(connection_count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn should_report_connection_metrics() {
+        let mut connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
+        connection_manager.expect_connection().returning(|_, _, _| {
+            let mut transport = MockCdrsTransport::new();
+            transport.expect_is_broken().return_const(false);
+            transport
+                .expect_write_envelope()
+                .returning(|_, _| async { Err(Error::General("test".into())) }.boxed());
+
+            async move { Ok(transport) }.boxed()
+        });
+
+        let metrics = Arc::new(ConnectionCountingMetrics::default());
+        let pool = ConnectionPool::new(
+            Arc::new(connection_manager),
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9042),
+            NodeDistance::Local,
+            ConnectionPoolConfig::new(2, 1, None),
+            metrics.clone(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(metrics.open.load(Ordering::SeqCst), 2);
+        assert_eq!(metrics.closed.load(Ordering::SeqCst), 0);
+
+        let envelope = Envelope::new_req_options(Version::V4);
+        pool.heartbeat(&envelope, Duration::from_secs(1)).await;
+
+        assert_eq!(metrics.open.load(Ordering::SeqCst), 4);
+        assert_eq!(metrics.closed.load(Ordering::SeqCst), 2);
+
+        drop(pool);
+        assert_eq!(metrics.closed.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn should_drop_pool_when_no_longer_used() {
+        let mut connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
+        connection_manager.expect_connection().returning(|_, _, _| {
+            let mut transport = MockCdrsTransport::new();
+            transport.expect_is_broken().return_const(false);
+
+            async move { Ok(transport) }.boxed()
+        });
+
+        // keep the sender alive, so the keyspace watcher keeps running
+        let (_keyspace_sender, keyspace_receiver) = watch::channel(None);
+        let metrics = Arc::new(ConnectionCountingMetrics::default());
+        let factory = ConnectionPoolFactory::new(
+            ConnectionPoolConfig::new(2, 1, None).with_heartbeat_interval(Duration::from_secs(1)),
+            Arc::new(VersionHolder::new(Version::V4)),
+            connection_manager,
+            keyspace_receiver,
+            metrics.clone(),
+        );
+
+        let pool = factory
+            .create(
+                NodeDistance::Local,
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9042),
+            )
+            .await
+            .unwrap();
+
+        let weak_pool = Arc::downgrade(&pool);
+        drop(pool);
+
+        assert!(weak_pool.upgrade().is_none());
+        assert_eq!(metrics.closed.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn should_grow_and_reap_idle_connections() {
+        let mut connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
+        connection_manager.expect_connection().returning(|_, _, _| {
+            let mut transport = MockCdrsTransport::new();
+            transport.expect_is_broken().return_const(false);
+
+            async move { Ok(transport) }.boxed()
+        });
+
+        let metrics = Arc::new(ConnectionCountingMetrics::default());
+        let pool = Arc::new(
+            ConnectionPool::new(
+                Arc::new(connection_manager),
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9042),
+                NodeDistance::Local,
+                ConnectionPoolConfig::new(1, 1, None)
+                    .with_max_size(2, 1)
+                    .with_new_connection_threshold(1),
+                metrics.clone(),
+            )
+            .await
+            .unwrap(),
+        );
+
+        let first = pool.connection().await.unwrap();
+        assert_eq!(pool.size(), 1);
+
+        // the only connection is busy, so a new one gets added in the background
+        let second = pool.connection().await.unwrap();
+        assert_eq!(pool.size(), 1);
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while pool.size() < 2 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // no more connections get added above the maximum
+        let third = pool.connection().await.unwrap();
+        let fourth = pool.connection().await.unwrap();
+        tokio::task::yield_now().await;
+        assert_eq!(pool.size(), 2);
+        assert_eq!(metrics.open.load(Ordering::SeqCst), 2);
+
+        // connections in use are not idle
+        drop(first);
+        drop(second);
+        drop(third);
+        pool.reap_idle(Duration::ZERO);
+        assert_eq!(pool.size(), 2);
+
+        drop(fourth);
+        pool.reap_idle(Duration::from_secs(60));
+        assert_eq!(pool.size(), 2);
+
+        pool.reap_idle(Duration::ZERO);
+        assert_eq!(pool.size(), 1);
+        assert_eq!(metrics.closed.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,3 +1,4 @@
+use cassandra_protocol::token::Partitioner;
 use fxhash::{FxHashMap, FxHashSet};
 use std::collections::hash_map::Entry;
 use std::sync::Arc;
@@ -15,6 +16,7 @@ pub fn build_initial_metadata<T: CdrsTransport, CM: ConnectionManager<T>>(
     contact_points: &[Arc<Node<T, CM>>],
     connection_pool_factory: &Arc<ConnectionPoolFactory<T, CM>>,
     node_distance_evaluator: &(dyn NodeDistanceEvaluator + Send + Sync),
+    partitioner: Partitioner,
 ) -> ClusterMetadata<T, CM> {
     let mut nodes = FxHashMap::with_capacity_and_hasher(node_infos.len(), Default::default());
     for node_info in node_infos {
@@ -50,7 +52,7 @@ pub fn build_initial_metadata<T: CdrsTransport, CM: ConnectionManager<T>>(
         }
     }
 
-    ClusterMetadata::new(nodes, keyspaces)
+    ClusterMetadata::new(nodes, keyspaces, partitioner)
 }
 
 pub fn refresh_metadata<T: CdrsTransport, CM: ConnectionManager<T>>(
@@ -58,6 +60,7 @@ pub fn refresh_metadata<T: CdrsTransport, CM: ConnectionManager<T>>(
     old_metadata: &ClusterMetadata<T, CM>,
     connection_pool_factory: &Arc<ConnectionPoolFactory<T, CM>>,
     node_distance_evaluator: &dyn NodeDistanceEvaluator,
+    partitioner: Partitioner,
 ) -> ClusterMetadata<T, CM> {
     let old_nodes = old_metadata.nodes();
 
@@ -99,7 +102,11 @@ pub fn refresh_metadata<T: CdrsTransport, CM: ConnectionManager<T>>(
         }
     }
 
-    ClusterMetadata::new(added_or_updated, old_metadata.keyspaces().clone())
+    ClusterMetadata::new(
+        added_or_updated,
+        old_metadata.keyspaces().clone(),
+        partitioner,
+    )
 }
 
 pub fn add_new_node<T: CdrsTransport, CM: ConnectionManager<T>>(
@@ -150,8 +157,9 @@ mod tests {
     };
     use crate::cluster::topology::NodeMap;
     use crate::cluster::topology::{Node, NodeDistance, NodeState};
-    use crate::cluster::{ClusterMetadata, NodeInfo};
+    use crate::cluster::{ClusterMetadata, NodeInfo, VersionHolder};
     use crate::load_balancing::node_distance_evaluator::MockNodeDistanceEvaluator;
+    use crate::metrics::NoopMetrics;
     use crate::transport::MockCdrsTransport;
 
     fn create_connection_pool_factory(
@@ -161,9 +169,10 @@ mod tests {
         let connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
         let connection_pool_factory = ConnectionPoolFactory::new(
             Default::default(),
-            Version::V4,
+            Arc::new(VersionHolder::new(Version::V4)),
             connection_manager,
             keyspace_receiver,
+            Arc::new(NoopMetrics),
         );
 
         Arc::new(connection_pool_factory)
@@ -193,6 +202,7 @@ mod tests {
             &[],
             &connection_pool_factory,
             &node_distance_evaluator,
+            Default::default(),
         );
 
         let nodes = metadata.nodes();
@@ -241,6 +251,7 @@ mod tests {
             &contact_points,
             &connection_pool_factory,
             &node_distance_evaluator,
+            Default::default(),
         );
 
         let nodes = metadata.nodes();
@@ -286,13 +297,14 @@ mod tests {
             )),
         );
 
-        let old_metadata = ClusterMetadata::new(old_nodes, Default::default());
+        let old_metadata = ClusterMetadata::new(old_nodes, Default::default(), Default::default());
 
         let metadata = refresh_metadata(
             &node_infos,
             &old_metadata,
             &connection_pool_factory,
             &node_distance_evaluator,
+            Default::default(),
         );
 
         let nodes = metadata.nodes();
@@ -336,13 +348,14 @@ mod tests {
             )),
         );
 
-        let old_metadata = ClusterMetadata::new(old_nodes, Default::default());
+        let old_metadata = ClusterMetadata::new(old_nodes, Default::default(), Default::default());
 
         let metadata = refresh_metadata(
             &node_infos,
             &old_metadata,
             &connection_pool_factory,
             &node_distance_evaluator,
+            Default::default(),
         );
 
         let nodes = metadata.nodes();
@@ -382,7 +395,7 @@ mod tests {
         let mut old_nodes = NodeMap::default();
         old_nodes.insert(node_info.host_id, Arc::new(old_node));
 
-        let old_metadata = ClusterMetadata::new(old_nodes, Default::default());
+        let old_metadata = ClusterMetadata::new(old_nodes, Default::default(), Default::default());
 
         let metadata = add_new_node(
             node_info.clone(),
@@ -436,7 +449,7 @@ mod tests {
         let mut old_nodes = NodeMap::default();
         old_nodes.insert(node_info.host_id, Arc::new(old_node));
 
-        let old_metadata = ClusterMetadata::new(old_nodes, Default::default());
+        let old_metadata = ClusterMetadata::new(old_nodes, Default::default(), Default::default());
 
         let metadata = add_new_node(
             node_info.clone(),
@@ -474,7 +487,8 @@ mod tests {
             "".into(),
         );
 
-        let old_metadata = ClusterMetadata::new(Default::default(), Default::default());
+        let old_metadata =
+            ClusterMetadata::new(Default::default(), Default::default(), Default::default());
 
         let metadata = add_new_node(
             node_info.clone(),
@@ -1,4 +1,4 @@
-use cassandra_protocol::token::Murmur3Token;
+use cassandra_protocol::token::Token;
 use derivative::Derivative;
 use derive_more::Constructor;
 use std::net::SocketAddr;
@@ -13,6 +13,6 @@ pub struct NodeInfo {
     pub broadcast_address: Option<SocketAddr>,
     pub datacenter: String,
     #[derivative(Debug = "ignore")]
-    pub tokens: Vec<Murmur3Token>,
+    pub tokens: Vec<Token>,
     pub rack: String,
 }
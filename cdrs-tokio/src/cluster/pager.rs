@@ -1,9 +1,10 @@
 use cassandra_protocol::consistency::Consistency;
 use cassandra_protocol::error;
 use cassandra_protocol::frame::message_result::RowsMetadataFlags;
-use cassandra_protocol::query::{PreparedQuery, QueryParams, QueryParamsBuilder, QueryValues};
+use cassandra_protocol::query::{PreparedQuery, QueryParams, QueryValues};
 use cassandra_protocol::types::rows::Row;
 use cassandra_protocol::types::CBytes;
+use std::sync::Arc;
 
 use crate::cluster::session::Session;
 use crate::cluster::ConnectionManager;
@@ -11,6 +12,23 @@ use crate::load_balancing::LoadBalancingStrategy;
 use crate::statement::StatementParamsBuilder;
 use crate::transport::CdrsTransport;
 
+/// A single page of query results, along with the paging state for fetching the next one.
+#[derive(Debug, Clone)]
+pub struct RowsPage<R = Row> {
+    pub rows: Vec<R>,
+    /// Opaque state for fetching the next page, e.g. with
+    /// `StatementParamsBuilder::with_paging_state`. `None` if there are no more pages.
+    pub paging_state: Option<CBytes>,
+}
+
+impl<R> RowsPage<R> {
+    /// Checks if there are more pages to be fetched.
+    #[inline]
+    pub fn has_more_pages(&self) -> bool {
+        self.paging_state.is_some()
+    }
+}
+
 pub struct SessionPager<
     'a,
     T: CdrsTransport + 'static,
@@ -67,9 +85,10 @@ impl<
     {
         self.query_with_param(
             query,
-            QueryParamsBuilder::new()
-                .with_consistency(Consistency::One)
-                .build(),
+            QueryParams {
+                consistency: Consistency::One,
+                ..Default::default()
+            },
         )
     }
 
@@ -108,7 +127,7 @@ pub struct QueryPager<'a, Q: ToString, P: 'a> {
     pager: &'a mut P,
     pager_state: PagerState,
     query: Q,
-    qv: Option<QueryValues>,
+    qv: Option<Arc<QueryValues>>,
     consistency: Consistency,
 }
 
@@ -130,7 +149,7 @@ impl<
             .with_page_size(self.pager.page_size);
 
         if let Some(qv) = &self.qv {
-            params = params.with_values(qv.clone());
+            params = params.with_shared_values(qv.clone());
         }
         if let Some(cursor) = &self.pager_state.cursor {
             params = params.with_paging_state(cursor.clone());
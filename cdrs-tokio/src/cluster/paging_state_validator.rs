@@ -0,0 +1,114 @@
+use cassandra_protocol::error::{Error, Result};
+use cassandra_protocol::frame::Envelope;
+use cassandra_protocol::types::CBytes;
+use fxhash::FxHashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Maximum number of tracked paging states. Oldest states are forgotten first.
+const MAX_TRACKED_PAGING_STATES: usize = 1024;
+
+#[derive(Default)]
+struct TrackedPagingStates {
+    query_hashes: FxHashMap<u64, u64>,
+    order: VecDeque<u64>,
+}
+
+/// Remembers which query returned recent paging states, in order to detect reusing them with a
+/// different query, which the server would reject with a confusing error. Paging states unknown
+/// to the validator, e.g. returned by another session, are always accepted.
+#[derive(Default)]
+pub(crate) struct PagingStateValidator {
+    paging_states: Mutex<TrackedPagingStates>,
+}
+
+impl PagingStateValidator {
+    #[inline]
+    pub(crate) fn query_hash(query: &str) -> u64 {
+        fxhash::hash64(query)
+    }
+
+    /// Verifies given paging state, if known, was returned for the query with given hash.
+    pub(crate) fn verify(&self, query_hash: u64, paging_state: Option<&CBytes>) -> Result<()> {
+        let paging_state = match paging_state {
+            Some(paging_state) => paging_state,
+            None => return Ok(()),
+        };
+
+        let paging_states = self.paging_states.lock().unwrap();
+        match paging_states
+            .query_hashes
+            .get(&fxhash::hash64(paging_state))
+        {
+            Some(expected) if *expected != query_hash => Err(Error::General(
+                "Paging state was returned for a different query!".into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remembers the paging state contained in given response, if any, for the query with given
+    /// hash.
+    pub(crate) fn record(&self, query_hash: u64, response: &Envelope) {
+        let paging_state = response.response_body().ok().and_then(|body| {
+            body.as_rows_metadata()
+                .and_then(|metadata| metadata.paging_state.as_ref())
+                .map(fxhash::hash64)
+        });
+
+        if let Some(paging_state) = paging_state {
+            self.track(paging_state, query_hash);
+        }
+    }
+
+    fn track(&self, paging_state_hash: u64, query_hash: u64) {
+        let mut paging_states = self.paging_states.lock().unwrap();
+        if paging_states
+            .query_hashes
+            .insert(paging_state_hash, query_hash)
+            .is_none()
+        {
+            paging_states.order.push_back(paging_state_hash);
+        }
+
+        if paging_states.order.len() > MAX_TRACKED_PAGING_STATES {
+            if let Some(oldest) = paging_states.order.pop_front() {
+                paging_states.query_hashes.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cassandra_protocol::types::CBytes;
+
+    use crate::cluster::paging_state_validator::{PagingStateValidator, MAX_TRACKED_PAGING_STATES};
+
+    #[test]
+    fn should_reject_paging_state_of_different_query() {
+        let validator = PagingStateValidator::default();
+        let paging_state = CBytes::new(vec![1, 2, 3]);
+        let query = PagingStateValidator::query_hash("SELECT * FROM t1");
+        let other_query = PagingStateValidator::query_hash("SELECT * FROM t2");
+
+        assert!(validator.verify(other_query, Some(&paging_state)).is_ok());
+
+        validator.track(fxhash::hash64(&paging_state), query);
+        assert!(validator.verify(query, Some(&paging_state)).is_ok());
+        assert!(validator.verify(other_query, Some(&paging_state)).is_err());
+        assert!(validator.verify(other_query, None).is_ok());
+    }
+
+    #[test]
+    fn should_forget_oldest_paging_states() {
+        let validator = PagingStateValidator::default();
+        for paging_state in 0..=MAX_TRACKED_PAGING_STATES as u64 {
+            validator.track(paging_state, 1);
+        }
+
+        let paging_states = validator.paging_states.lock().unwrap();
+        assert_eq!(paging_states.query_hashes.len(), MAX_TRACKED_PAGING_STATES);
+        assert!(!paging_states.query_hashes.contains_key(&0));
+    }
+}
@@ -0,0 +1,206 @@
+use cassandra_protocol::events::ServerEvent;
+use cassandra_protocol::query::PreparedQuery;
+use fxhash::FxHashMap;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tracing::*;
+
+struct CacheEntry {
+    prepared: PreparedQuery,
+    last_use: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    // statements are grouped by keyspace, so lookups can use borrowed keys
+    no_keyspace: FxHashMap<String, CacheEntry>,
+    by_keyspace: FxHashMap<String, FxHashMap<String, CacheEntry>>,
+    // keyspace and query of every entry, ordered by last use
+    use_order: BTreeMap<u64, (Option<String>, String)>,
+    last_use: u64,
+}
+
+impl CacheState {
+    fn entries_mut(
+        &mut self,
+        keyspace: Option<&str>,
+    ) -> Option<&mut FxHashMap<String, CacheEntry>> {
+        match keyspace {
+            Some(keyspace) => self.by_keyspace.get_mut(keyspace),
+            None => Some(&mut self.no_keyspace),
+        }
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let least_recently_used = match self.use_order.keys().next() {
+            Some(last_use) => *last_use,
+            None => return,
+        };
+
+        if let Some((keyspace, query)) = self.use_order.remove(&least_recently_used) {
+            match keyspace {
+                Some(keyspace) => {
+                    if let Some(entries) = self.by_keyspace.get_mut(&keyspace) {
+                        entries.remove(&query);
+                        if entries.is_empty() {
+                            self.by_keyspace.remove(&keyspace);
+                        }
+                    }
+                }
+                None => {
+                    self.no_keyspace.remove(&query);
+                }
+            }
+        }
+    }
+}
+
+/// Least recently used cache of prepared statements, keyed by keyspace and query string.
+/// A capacity of `0` disables caching.
+pub(crate) struct PreparedStatementCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl PreparedStatementCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        PreparedStatementCache {
+            capacity,
+            state: Default::default(),
+        }
+    }
+
+    pub(crate) fn get(&self, keyspace: Option<&str>, query: &str) -> Option<PreparedQuery> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.last_use += 1;
+
+        let last_use = state.last_use;
+        let entry = state.entries_mut(keyspace)?.get_mut(query)?;
+        let previous_use = std::mem::replace(&mut entry.last_use, last_use);
+        let prepared = entry.prepared.clone();
+
+        if let Some(key) = state.use_order.remove(&previous_use) {
+            state.use_order.insert(last_use, key);
+        }
+
+        Some(prepared)
+    }
+
+    pub(crate) fn insert(&self, keyspace: Option<String>, prepared: PreparedQuery) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.last_use += 1;
+
+        let last_use = state.last_use;
+        let query = prepared.query.clone();
+        let entries = match &keyspace {
+            Some(keyspace) => state.by_keyspace.entry(keyspace.clone()).or_default(),
+            None => &mut state.no_keyspace,
+        };
+
+        if let Some(previous) = entries.insert(query.clone(), CacheEntry { prepared, last_use }) {
+            state.use_order.remove(&previous.last_use);
+        }
+
+        state.use_order.insert(last_use, (keyspace, query));
+        while state.use_order.len() > self.capacity {
+            state.evict_least_recently_used();
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.no_keyspace.clear();
+        state.by_keyspace.clear();
+        state.use_order.clear();
+    }
+
+    /// Clears the cache on every schema change, since it might invalidate prepared metadata.
+    pub(crate) fn clear_on_schema_change(
+        self: Arc<Self>,
+        mut event_receiver: Receiver<ServerEvent>,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        tokio::spawn(async move {
+            loop {
+                match event_receiver.recv().await {
+                    Ok(ServerEvent::SchemaChange(_)) => self.clear(),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(n)) => {
+                        // a schema change might have been missed
+                        warn!("Skipped {} events.", n);
+                        self.clear();
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arc_swap::ArcSwapOption;
+    use cassandra_protocol::query::PreparedQuery;
+    use cassandra_protocol::types::CBytesShort;
+
+    use crate::cluster::prepared_statement_cache::PreparedStatementCache;
+
+    fn prepared(query: &str) -> PreparedQuery {
+        PreparedQuery {
+            id: CBytesShort::new(query.as_bytes().to_vec()),
+            query: query.into(),
+            keyspace: None,
+            pk_indexes: vec![],
+            result_metadata_id: ArcSwapOption::empty(),
+        }
+    }
+
+    #[test]
+    fn should_evict_least_recently_used() {
+        let cache = PreparedStatementCache::new(2);
+        cache.insert(None, prepared("q1"));
+        cache.insert(Some("ks".into()), prepared("q2"));
+
+        assert!(cache.get(None, "q2").is_none());
+        assert_eq!(cache.get(None, "q1"), Some(prepared("q1")));
+
+        cache.insert(None, prepared("q3"));
+        assert!(cache.get(None, "q1").is_some());
+        assert!(cache.get(Some("ks"), "q2").is_none());
+        assert!(cache.get(None, "q3").is_some());
+
+        cache.clear();
+        assert!(cache.get(None, "q1").is_none());
+    }
+
+    #[test]
+    fn should_replace_existing_entries() {
+        let cache = PreparedStatementCache::new(2);
+        cache.insert(Some("ks".into()), prepared("q1"));
+        cache.insert(Some("ks".into()), prepared("q1"));
+        cache.insert(Some("ks".into()), prepared("q2"));
+
+        assert!(cache.get(Some("ks"), "q1").is_some());
+        assert!(cache.get(Some("ks"), "q2").is_some());
+    }
+
+    #[test]
+    fn should_not_cache_with_zero_capacity() {
+        let cache = PreparedStatementCache::new(0);
+        cache.insert(None, prepared("q1"));
+        assert!(cache.get(None, "q1").is_none());
+    }
+}
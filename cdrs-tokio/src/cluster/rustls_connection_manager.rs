@@ -4,9 +4,12 @@ use std::ops::Deref;
 use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
 use tokio::time::sleep;
+use tracing::*;
 
-use crate::cluster::connection_manager::{startup, ConnectionManager};
-use crate::cluster::KeyspaceHolder;
+use crate::cluster::connection_manager::{
+    connect_with_version_fallback, negotiate_compression, startup, ConnectionManager,
+};
+use crate::cluster::{KeyspaceHolder, VersionHolder};
 use crate::frame_encoding::FrameEncodingFactory;
 use crate::future::BoxFuture;
 use crate::retry::ReconnectionPolicy;
@@ -24,9 +27,11 @@ pub struct RustlsConnectionManager {
     reconnection_policy: Arc<dyn ReconnectionPolicy + Send + Sync>,
     frame_encoder_factory: Box<dyn FrameEncodingFactory + Send + Sync>,
     compression: Compression,
+    compression_threshold: usize,
     buffer_size: usize,
     tcp_nodelay: bool,
-    version: Version,
+    max_in_flight_requests: usize,
+    version_holder: Arc<VersionHolder>,
 }
 
 impl ConnectionManager<TransportRustls> for RustlsConnectionManager {
@@ -67,9 +72,11 @@ impl RustlsConnectionManager {
         reconnection_policy: Arc<dyn ReconnectionPolicy + Send + Sync>,
         frame_encoder_factory: Box<dyn FrameEncodingFactory + Send + Sync>,
         compression: Compression,
+        compression_threshold: usize,
         buffer_size: usize,
         tcp_nodelay: bool,
-        version: Version,
+        max_in_flight_requests: usize,
+        version_holder: Arc<VersionHolder>,
     ) -> Self {
         RustlsConnectionManager {
             dns_name,
@@ -79,44 +86,94 @@ impl RustlsConnectionManager {
             reconnection_policy,
             frame_encoder_factory,
             compression,
+            compression_threshold,
             buffer_size,
             tcp_nodelay,
-            version,
+            max_in_flight_requests,
+            version_holder,
         }
     }
 
-    async fn establish_connection(
+    async fn create_transport(
         &self,
         event_handler: Option<Sender<Envelope>>,
         error_handler: Option<Sender<Error>>,
         addr: SocketAddr,
+        compression: Compression,
+        version: Version,
     ) -> Result<TransportRustls> {
-        let transport = TransportRustls::new(
+        Ok(TransportRustls::new(
             addr,
             self.dns_name.clone(),
             self.config.clone(),
             self.keyspace_holder.clone(),
             event_handler,
             error_handler,
-            self.compression,
+            compression,
+            self.compression_threshold,
             self.frame_encoder_factory
-                .create_encoder(self.version, self.compression),
+                .create_encoder(version, compression),
             self.frame_encoder_factory
-                .create_decoder(self.version, self.compression),
+                .create_decoder(version, compression),
             self.buffer_size,
             self.tcp_nodelay,
+            self.max_in_flight_requests,
         )
-        .await?;
+        .await?)
+    }
+
+    async fn establish_connection(
+        &self,
+        event_handler: Option<Sender<Envelope>>,
+        error_handler: Option<Sender<Error>>,
+        addr: SocketAddr,
+    ) -> Result<TransportRustls> {
+        connect_with_version_fallback(&self.version_holder, addr, |version| {
+            self.establish_connection_with_version(
+                event_handler.clone(),
+                error_handler.clone(),
+                addr,
+                version,
+            )
+        })
+        .await
+    }
+
+    async fn establish_connection_with_version(
+        &self,
+        event_handler: Option<Sender<Envelope>>,
+        error_handler: Option<Sender<Error>>,
+        addr: SocketAddr,
+        version: Version,
+    ) -> Result<TransportRustls> {
+        let mut transport = self
+            .create_transport(
+                event_handler.clone(),
+                error_handler.clone(),
+                addr,
+                self.compression,
+                version,
+            )
+            .await?;
+
+        let compression = negotiate_compression(&transport, self.compression, version).await?;
+        if compression != self.compression {
+            transport = self
+                .create_transport(event_handler, error_handler, addr, compression, version)
+                .await?;
+        }
 
         startup(
             &transport,
             self.authenticator_provider.deref(),
             self.keyspace_holder.deref(),
-            self.compression,
-            self.version,
+            compression,
+            version,
         )
         .await?;
 
+        debug!(%addr, %compression, %version, "Connection established.");
+
         Ok(transport)
     }
 }
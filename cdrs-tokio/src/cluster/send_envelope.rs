@@ -1,12 +1,61 @@
 use cassandra_protocol::error;
 use cassandra_protocol::frame::Envelope;
+use std::borrow::Cow;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::*;
 
 use crate::cluster::topology::Node;
 use crate::cluster::ConnectionManager;
+use crate::metrics::Metrics;
 use crate::retry::{QueryInfo, RetryDecision, RetrySession};
 use crate::transport::CdrsTransport;
 
+/// Reports the outcome of a single request to metrics. A request dropped before its outcome is
+/// reported, e.g. when its future gets cancelled, is reported as cancelled.
+struct RequestMetrics<'a> {
+    metrics: &'a dyn Metrics,
+    node: SocketAddr,
+    start: Instant,
+    is_finished: bool,
+}
+
+impl<'a> RequestMetrics<'a> {
+    fn start(metrics: &'a dyn Metrics, node: SocketAddr) -> Self {
+        metrics.on_request_start(node);
+        RequestMetrics {
+            metrics,
+            node,
+            start: Instant::now(),
+            is_finished: false,
+        }
+    }
+
+    fn success(mut self) -> Duration {
+        self.is_finished = true;
+
+        let latency = self.start.elapsed();
+        self.metrics.on_request_success(self.node, latency);
+        latency
+    }
+
+    fn error(mut self, error: &error::Error) {
+        self.is_finished = true;
+        self.metrics
+            .on_request_error(self.node, error, self.start.elapsed());
+    }
+}
+
+impl Drop for RequestMetrics<'_> {
+    fn drop(&mut self) {
+        if !self.is_finished {
+            self.metrics
+                .on_request_cancel(self.node, self.start.elapsed());
+        }
+    }
+}
+
 /// Mid-level interface for sending envelopes to the cluster. Uses a query plan to route envelope to
 /// appropriate node, and retry policy for error handling. Returns `None` if no nodes were present
 /// in the query plan.
@@ -16,29 +65,137 @@ pub async fn send_envelope<T: CdrsTransport + 'static, CM: ConnectionManager<T>
     is_idempotent: bool,
     mut retry_session: Box<dyn RetrySession + Send + Sync>,
 ) -> Option<error::Result<Envelope>> {
+    let mut envelope = Cow::Borrowed(envelope);
+
     'next_node: for node in query_plan {
         loop {
-            let transport = node.persistent_connection().await;
-            match transport {
-                Ok(transport) => match transport.write_envelope(envelope, false).await {
-                    Ok(envelope) => return Some(Ok(envelope)),
-                    Err(error) => {
-                        let query_info = QueryInfo {
-                            error: &error,
-                            is_idempotent,
-                        };
-
-                        match retry_session.decide(query_info) {
-                            RetryDecision::RetrySameNode => continue,
-                            RetryDecision::RetryNextNode => continue 'next_node,
-                            RetryDecision::DontRetry => return Some(Err(error)),
+            debug!(node = %node.broadcast_rpc_address(), "Sending envelope.");
+            let transport = match node.persistent_connection().await {
+                Ok(transport) => transport,
+                Err(error) => return Some(Err(error)),
+            };
+
+            // connection establishment is not a part of request latency
+            let request_metrics =
+                RequestMetrics::start(node.metrics(), node.broadcast_rpc_address());
+
+            match transport.write_envelope(&envelope, false).await {
+                Ok(envelope) => {
+                    node.record_latency(request_metrics.success());
+                    return Some(Ok(envelope));
+                }
+                Err(error) => {
+                    request_metrics.error(&error);
+
+                    let query_info = QueryInfo {
+                        error: &error,
+                        is_idempotent,
+                    };
+
+                    match retry_session.decide(query_info) {
+                        RetryDecision::RetrySameNode => continue,
+                        RetryDecision::RetrySameNodeWithConsistency(consistency) => {
+                            match envelope.with_consistency(consistency) {
+                                Ok(new_envelope) => {
+                                    envelope = Cow::Owned(new_envelope);
+                                    continue;
+                                }
+                                Err(_) => return Some(Err(error)),
+                            }
                         }
+                        RetryDecision::RetryNextNode => continue 'next_node,
+                        RetryDecision::DontRetry => return Some(Err(error)),
                     }
-                },
-                Err(error) => return Some(Err(error)),
+                }
             }
         }
     }
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use cassandra_protocol::frame::{Envelope, Version};
+    use futures::FutureExt;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    use crate::cluster::connection_manager::MockConnectionManager;
+    use crate::cluster::connection_pool::ConnectionPoolFactory;
+    use crate::cluster::send_envelope::send_envelope;
+    use crate::cluster::topology::{Node, NodeDistance, NodeState};
+    use crate::cluster::VersionHolder;
+    use crate::metrics::Metrics;
+    use crate::retry::{DefaultRetryPolicy, RetryPolicy};
+    use crate::transport::MockCdrsTransport;
+
+    #[derive(Default)]
+    struct RequestCountingMetrics {
+        started: AtomicUsize,
+        cancelled: AtomicUsize,
+    }
+
+    impl Metrics for RequestCountingMetrics {
+        fn on_request_start(&self, _node: SocketAddr) {
+            self.started.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_request_cancel(&self, _node: SocketAddr, _latency: Duration) {
+            self.cancelled.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_report_cancelled_requests() {
+        let mut connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
+        connection_manager.expect_connection().returning(|_, _, _| {
+            let mut transport = MockCdrsTransport::new();
+            transport.expect_is_broken().return_const(false);
+            transport
+                .expect_write_envelope()
+                .returning(|_, _| futures::future::pending().boxed());
+
+            async move { Ok(transport) }.boxed()
+        });
+
+        let (_, keyspace_receiver) = watch::channel(None);
+        let metrics = Arc::new(RequestCountingMetrics::default());
+        let node = Arc::new(Node::new_with_state(
+            Arc::new(ConnectionPoolFactory::new(
+                Default::default(),
+                Arc::new(VersionHolder::new(Version::V4)),
+                connection_manager,
+                keyspace_receiver,
+                metrics.clone(),
+            )),
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9042),
+            None,
+            None,
+            Some(NodeDistance::Local),
+            NodeState::Up,
+            vec![],
+            "".into(),
+            "".into(),
+        ));
+
+        let envelope = Envelope::new_req_options(Version::V4);
+        let result = tokio::time::timeout(
+            Duration::from_millis(10),
+            send_envelope(
+                std::iter::once(node),
+                &envelope,
+                true,
+                DefaultRetryPolicy.new_session(),
+            ),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(metrics.started.load(Ordering::SeqCst), 1);
+        assert_eq!(metrics.cancelled.load(Ordering::SeqCst), 1);
+    }
+}
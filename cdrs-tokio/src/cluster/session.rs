@@ -1,34 +1,47 @@
 use arc_swap::ArcSwapOption;
 use cassandra_protocol::compression::Compression;
-use cassandra_protocol::consistency::Consistency;
+use cassandra_protocol::consistency::{Consistency, OperationKind};
 use cassandra_protocol::error;
+use cassandra_protocol::error::PendingRequest;
 use cassandra_protocol::events::ServerEvent;
+use cassandra_protocol::frame::message_batch::BatchType;
 use cassandra_protocol::frame::message_response::ResponseBody;
-use cassandra_protocol::frame::message_result::{BodyResResultPrepared, TableSpec};
-use cassandra_protocol::frame::{Envelope, Flags, Serialize, Version};
-use cassandra_protocol::query::{PreparedQuery, Query, QueryBatch, QueryValues};
-use cassandra_protocol::token::Murmur3Token;
-use cassandra_protocol::types::value::Value;
-use cassandra_protocol::types::{CIntShort, SHORT_LEN};
+use cassandra_protocol::frame::message_result::{
+    BodyResResultPrepared, RowsMetadataFlags, TableSpec,
+};
+use cassandra_protocol::frame::message_supported::BodyResSupported;
+use cassandra_protocol::frame::{Envelope, Flags, Version};
+use cassandra_protocol::query::utils::serialize_routing_key;
+use cassandra_protocol::query::{PreparedQuery, Query, QueryBatch, QueryParams, QueryValues};
+use cassandra_protocol::token::Token;
+use cassandra_protocol::types::rows::Row;
+use cassandra_protocol::types::CInt;
 use futures::stream::FuturesUnordered;
-use futures::{FutureExt, StreamExt};
+use futures::stream::{self, Stream};
+use futures::{FutureExt, StreamExt, TryStreamExt};
+use fxhash::FxHashMap;
 use itertools::Itertools;
 use lazy_static::lazy_static;
-use std::io::{Cursor, Write};
+use std::borrow::Cow;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::broadcast::{channel, Receiver, Sender};
-use tokio::sync::watch;
+use tokio::sync::{watch, Notify};
 use tokio::task::JoinHandle;
-use tokio::time::sleep;
+use tokio::time::{sleep, timeout};
 use tokio::{pin, select};
 use tracing::*;
 
 use crate::cluster::connection_manager::ConnectionManager;
 use crate::cluster::connection_pool::{ConnectionPoolConfig, ConnectionPoolFactory};
 use crate::cluster::control_connection::ControlConnection;
+use crate::cluster::paging_state_validator::PagingStateValidator;
+use crate::cluster::prepared_statement_cache::PreparedStatementCache;
 #[cfg(feature = "rust-tls")]
 use crate::cluster::rustls_connection_manager::RustlsConnectionManager;
 use crate::cluster::send_envelope::send_envelope;
@@ -37,14 +50,15 @@ use crate::cluster::topology::{Node, NodeDistance, NodeState};
 #[cfg(feature = "rust-tls")]
 use crate::cluster::NodeRustlsConfig;
 use crate::cluster::{ClusterMetadata, ClusterMetadataManager, SessionContext};
-use crate::cluster::{GenericClusterConfig, KeyspaceHolder};
-use crate::cluster::{NodeTcpConfig, SessionPager};
+use crate::cluster::{GenericClusterConfig, KeyspaceHolder, VersionHolder};
+use crate::cluster::{NodeTcpConfig, RowsPage, SessionPager};
 use crate::frame_encoding::{FrameEncodingFactory, ProtocolFrameEncodingFactory};
 use crate::load_balancing::node_distance_evaluator::AllLocalNodeDistanceEvaluator;
 use crate::load_balancing::node_distance_evaluator::NodeDistanceEvaluator;
 use crate::load_balancing::{
     InitializingWrapperLoadBalancingStrategy, LoadBalancingStrategy, QueryPlan, Request,
 };
+use crate::metrics::{Metrics, NoopMetrics};
 use crate::retry::{
     DefaultRetryPolicy, ExponentialReconnectionPolicy, ReconnectionPolicy, RetryPolicy,
 };
@@ -52,10 +66,14 @@ use crate::speculative_execution::{Context, SpeculativeExecutionPolicy};
 use crate::statement::{StatementParams, StatementParamsBuilder};
 #[cfg(feature = "rust-tls")]
 use crate::transport::TransportRustls;
-use crate::transport::{CdrsTransport, TransportTcp};
+use crate::transport::{CdrsTransport, TransportTcp, MAX_IN_FLIGHT_REQUESTS};
 
 pub const DEFAULT_TRANSPORT_BUFFER_SIZE: usize = 1024;
+/// Default minimum envelope body size, in bytes, which is compressed when compression is enabled.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 512;
 const DEFAULT_EVENT_CHANNEL_CAPACITY: usize = 128;
+/// Default maximum number of cached prepared statements.
+pub const DEFAULT_PREPARED_STATEMENT_CACHE_SIZE: usize = 1024;
 
 lazy_static! {
     static ref DEFAULT_STATEMET_PARAMETERS: StatementParams = Default::default();
@@ -86,89 +104,148 @@ fn prepare_flags(with_tracing: bool, with_warnings: bool, beta_protocol: bool) -
     flags
 }
 
-fn create_keyspace_holder() -> (Arc<KeyspaceHolder>, watch::Receiver<Option<String>>) {
-    let (keyspace_sender, keyspace_receiver) = watch::channel(None);
-    (
-        Arc::new(KeyspaceHolder::new(keyspace_sender)),
-        keyspace_receiver,
-    )
+fn event_stream(receiver: Receiver<ServerEvent>) -> impl Stream<Item = ServerEvent> {
+    stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(RecvError::Lagged(count)) => warn!(count, "Skipped lagging server events."),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
 }
 
-fn verify_compression_configuration(
-    version: Version,
-    compression: Compression,
-) -> Result<(), SessionBuildError> {
-    if version < Version::V5 || compression != Compression::Snappy {
-        Ok(())
-    } else {
-        Err(SessionBuildError::CompressionTypeNotSupported)
+/// Requests being executed, which can be awaited to complete.
+#[derive(Default)]
+struct InFlightRequests {
+    next_id: AtomicU64,
+    requests: Mutex<FxHashMap<u64, Arc<Envelope>>>,
+    drained: Notify,
+}
+
+impl InFlightRequests {
+    fn start(&self, envelope: Arc<Envelope>) -> InFlightRequestGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.requests.lock().unwrap().insert(id, envelope);
+        InFlightRequestGuard {
+            in_flight_requests: self,
+            id,
+        }
+    }
+
+    #[inline]
+    fn count(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Describes requests which are still being executed.
+    fn pending(&self) -> Vec<PendingRequest> {
+        self.requests
+            .lock()
+            .unwrap()
+            .values()
+            .map(|envelope| PendingRequest::from_envelope(envelope))
+            .collect()
+    }
+
+    async fn drained(&self) {
+        loop {
+            // create the future before checking, so the notification can't be missed
+            let drained = self.drained.notified();
+            if self.count() == 0 {
+                return;
+            }
+
+            drained.await;
+        }
     }
 }
 
-// https://github.com/apache/cassandra/blob/3a950b45c321e051a9744721408760c568c05617/src/java/org/apache/cassandra/db/marshal/CompositeType.java#L39
-fn serialize_routing_value(cursor: &mut Cursor<&mut Vec<u8>>, value: &Value, version: Version) {
-    let temp_size: CIntShort = 0;
-    temp_size.serialize(cursor, version);
+struct InFlightRequestGuard<'a> {
+    in_flight_requests: &'a InFlightRequests,
+    id: u64,
+}
+
+impl Drop for InFlightRequestGuard<'_> {
+    fn drop(&mut self) {
+        let mut requests = self.in_flight_requests.requests.lock().unwrap();
+        requests.remove(&self.id);
 
-    let before_value_pos = cursor.position();
-    value.serialize(cursor, version);
+        if requests.is_empty() {
+            self.in_flight_requests.drained.notify_waiters();
+        }
+    }
+}
 
-    let after_value_pos = cursor.position();
-    cursor.set_position(before_value_pos - SHORT_LEN as u64);
+/// Extracts a page of rows from a query response. Responses without rows result in an empty page.
+fn envelope_page(envelope: Envelope) -> error::Result<RowsPage> {
+    let body = envelope.response_body()?;
 
-    let value_size: CIntShort = (after_value_pos - before_value_pos) as CIntShort;
-    value_size.serialize(cursor, version);
+    let paging_state = body
+        .as_rows_metadata()
+        .filter(|metadata| metadata.flags.contains(RowsMetadataFlags::HAS_MORE_PAGES))
+        .and_then(|metadata| metadata.paging_state.clone());
 
-    cursor.set_position(after_value_pos);
-    let _ = cursor.write(&[0]);
+    Ok(RowsPage {
+        rows: body.into_rows().unwrap_or_default(),
+        paging_state,
+    })
 }
 
-fn serialize_routing_key_with_indexes(
-    values: &[Value],
-    pk_indexes: &[i16],
-    version: Version,
-) -> Option<Vec<u8>> {
-    match pk_indexes.len() {
-        0 => None,
-        1 => values
-            .get(pk_indexes[0] as usize)
-            .map(|value| value.serialize_to_vec(version)),
-        _ => {
-            let mut buf = vec![];
-            if pk_indexes
-                .iter()
-                .map(|index| values.get(*index as usize))
-                .fold_options(Cursor::new(&mut buf), |mut cursor, value| {
-                    serialize_routing_value(&mut cursor, value, version);
-                    cursor
-                })
-                .is_some()
-            {
-                Some(buf)
-            } else {
-                None
-            }
+/// Applies the session default page size, unless given parameters set one explicitly.
+fn with_default_page_size(
+    query_params: Cow<'_, QueryParams>,
+    default_page_size: Option<CInt>,
+) -> Cow<'_, QueryParams> {
+    match (query_params.page_size, default_page_size) {
+        (None, Some(page_size)) => {
+            let mut query_params = query_params.into_owned();
+            query_params.page_size = Some(page_size);
+            Cow::Owned(query_params)
         }
+        _ => query_params,
     }
 }
 
-fn serialize_routing_key(values: &[Value], version: Version) -> Vec<u8> {
-    match values.len() {
-        0 => vec![],
-        1 => values[0].serialize_to_vec(version),
-        _ => {
-            let mut buf = vec![];
-            let mut cursor = Cursor::new(&mut buf);
+/// Verifies the consistency can be used for given statement, if its operation kind is known.
+fn verify_consistency(query: &str, consistency: Consistency) -> error::Result<()> {
+    OperationKind::from_cql(query)
+        .map(|operation| consistency.verify_for_operation(operation))
+        .unwrap_or(Ok(()))
+}
 
-            for value in values {
-                serialize_routing_value(&mut cursor, value, version);
-            }
+fn log_unsupported_params(query_params: &QueryParams, version: Version) {
+    if version < Version::V5 {
+        if query_params.keyspace.is_some() {
+            debug!(%version, "Per-request keyspace is not supported - ignoring it.");
+        }
 
-            buf
+        if query_params.now_in_seconds.is_some() {
+            debug!(%version, "Per-request \"now\" is not supported - ignoring it.");
         }
     }
 }
 
+fn create_keyspace_holder() -> (Arc<KeyspaceHolder>, watch::Receiver<Option<String>>) {
+    let (keyspace_sender, keyspace_receiver) = watch::channel(None);
+    (
+        Arc::new(KeyspaceHolder::new(keyspace_sender)),
+        keyspace_receiver,
+    )
+}
+
+fn verify_compression_configuration(
+    version: Version,
+    compression: Compression,
+) -> Result<(), SessionBuildError> {
+    if version < Version::V5 || compression != Compression::Snappy {
+        Ok(())
+    } else {
+        Err(SessionBuildError::CompressionTypeNotSupported)
+    }
+}
+
 /// CDRS session that holds a pool of connections to nodes and provides an interface for
 /// interacting with the cluster.
 pub struct Session<
@@ -183,9 +260,15 @@ pub struct Session<
     control_connection_handle: JoinHandle<()>,
     event_sender: Sender<ServerEvent>,
     cluster_metadata_manager: Arc<ClusterMetadataManager<T, CM>>,
+    reprepare_on_unprepared: bool,
+    prepared_statement_cache: Arc<PreparedStatementCache>,
+    default_page_size: Option<CInt>,
+    paging_state_validator: Option<PagingStateValidator>,
+    in_flight_requests: InFlightRequests,
+    is_shut_down: AtomicBool,
     _transport: PhantomData<T>,
     _connection_manager: PhantomData<CM>,
-    version: Version,
+    version: Arc<VersionHolder>,
 }
 
 impl<
@@ -216,7 +299,18 @@ impl<
         prepared: &PreparedQuery,
         parameters: &StatementParams,
     ) -> error::Result<Envelope> {
-        let consistency = parameters.query_params.consistency;
+        let query_params = with_default_page_size(
+            Cow::Borrowed(&parameters.query_params),
+            self.default_page_size,
+        );
+
+        query_params.verify()?;
+        verify_consistency(&prepared.query, query_params.consistency)?;
+        log_unsupported_params(&query_params, self.protocol_version());
+
+        let query_hash = self.verify_paging_state(&prepared.query, &query_params)?;
+
+        let consistency = query_params.consistency;
         let flags = prepare_flags(
             parameters.tracing,
             parameters.warnings,
@@ -232,49 +326,49 @@ impl<
         let envelope = Envelope::new_req_execute(
             &prepared.id,
             result_metadata_id.as_ref(),
-            &parameters.query_params,
+            &query_params,
             flags,
-            self.version,
-        );
+            self.protocol_version(),
+        )
+        .with_custom_payload(parameters.custom_payload.clone());
 
         let keyspace = prepared
             .keyspace
             .as_deref()
             .or(parameters.keyspace.as_deref());
 
-        let routing_key = parameters
-            .query_params
+        let routing_key = match query_params
             .values
             .as_ref()
-            .and_then(|values| match values {
-                QueryValues::SimpleValues(values) => {
-                    serialize_routing_key_with_indexes(values, &prepared.pk_indexes, self.version)
-                        .or_else(|| {
-                            parameters
-                                .routing_key
-                                .as_ref()
-                                .map(|values| serialize_routing_key(values, self.version))
-                        })
-                }
-                QueryValues::NamedValues(_) => None,
-            });
+            .map(|values| prepared.routing_key(values))
+            .transpose()?
+            .flatten()
+        {
+            Some(routing_key) => Some(routing_key),
+            None => parameters
+                .routing_key
+                .as_ref()
+                .map(|values| serialize_routing_key(values))
+                .transpose()?,
+        };
 
         let mut result = self
             .send_envelope(
                 envelope,
                 parameters.is_idempotent,
                 keyspace,
-                parameters.token,
+                parameters.token.clone(),
                 routing_key.as_deref(),
                 Some(consistency),
                 parameters.speculative_execution_policy.as_ref(),
                 parameters.retry_policy.as_ref(),
+                parameters.request_timeout,
             )
             .await;
 
         if let Err(error::Error::Server { body: error, addr }) = &result {
             // if query is unprepared
-            if error.error_code == 0x2500 {
+            if error.error_code == 0x2500 && self.reprepare_on_unprepared {
                 debug!("Re-preparing statement.");
 
                 // We need to send the prepare statement to the failing node.
@@ -292,7 +386,7 @@ impl<
                     prepared.query.clone(),
                     keyspace.map(|keyspace| keyspace.to_string()),
                     flags,
-                    self.version,
+                    self.protocol_version(),
                 );
 
                 let retry_policy = self.effective_retry_policy(parameters.retry_policy.as_ref());
@@ -326,21 +420,23 @@ impl<
                     let envelope = Envelope::new_req_execute(
                         &new.id,
                         new.result_metadata_id.as_ref(),
-                        &parameters.query_params,
+                        &query_params,
                         flags,
-                        self.version,
-                    );
+                        self.protocol_version(),
+                    )
+                    .with_custom_payload(parameters.custom_payload.clone());
 
                     result = self
                         .send_envelope(
                             envelope,
                             parameters.is_idempotent,
                             keyspace,
-                            parameters.token,
+                            parameters.token.clone(),
                             routing_key.as_deref(),
                             Some(consistency),
                             parameters.speculative_execution_policy.as_ref(),
                             parameters.retry_policy.as_ref(),
+                            parameters.request_timeout,
                         )
                         .await;
                 }
@@ -364,6 +460,7 @@ impl<
                 .swap(Some(Arc::new(new_metadata_id.clone())));
         }
 
+        self.record_paging_state(query_hash, &result);
         result
     }
 
@@ -403,9 +500,10 @@ impl<
     ) -> error::Result<BodyResResultPrepared> {
         let flags = prepare_flags(with_tracing, with_warnings, beta_protocol);
 
-        let envelope = Envelope::new_req_prepare(query.to_string(), keyspace, flags, self.version);
+        let envelope =
+            Envelope::new_req_prepare(query.to_string(), keyspace, flags, self.protocol_version());
 
-        self.send_envelope(envelope, true, None, None, None, None, None, None)
+        self.send_envelope(envelope, true, None, None, None, None, None, None, None)
             .await
             .and_then(|response| response.response_body())
             .and_then(convert_to_prepared)
@@ -421,7 +519,8 @@ impl<
     /// Prepares a query for execution. Along with query itself,
     /// the method takes `with_tracing` and `with_warnings` flags
     /// to get tracing information and warnings. Returns the prepared
-    /// query.
+    /// query. Queries already prepared in the same keyspace are taken from the prepared
+    /// statement cache, without contacting the cluster, unless tracing or warnings are requested.
     pub async fn prepare_tw<Q: ToString>(
         &self,
         query: Q,
@@ -431,7 +530,29 @@ impl<
         beta_protocol: bool,
     ) -> error::Result<PreparedQuery> {
         let s = query.to_string();
-        self.prepare_raw_tw(query, keyspace, with_tracing, with_warnings, beta_protocol)
+        let cache_keyspace = keyspace.clone().or_else(|| {
+            self.current_keyspace()
+                .map(|keyspace| keyspace.as_ref().clone())
+        });
+
+        // tracing information and warnings are only returned by the cluster
+        if !with_tracing && !with_warnings {
+            if let Some(prepared) = self
+                .prepared_statement_cache
+                .get(cache_keyspace.as_deref(), &s)
+            {
+                return Ok(prepared);
+            }
+        }
+
+        let prepared = self
+            .prepare_raw_tw(
+                s.as_str(),
+                keyspace,
+                with_tracing,
+                with_warnings,
+                beta_protocol,
+            )
             .await
             .map(|result| PreparedQuery {
                 id: result.id,
@@ -442,7 +563,19 @@ impl<
                     .map(|TableSpec { ks_name, .. }| ks_name),
                 pk_indexes: result.metadata.pk_indexes,
                 result_metadata_id: ArcSwapOption::new(result.result_metadata_id.map(Arc::new)),
-            })
+            })?;
+
+        self.prepared_statement_cache
+            .insert(cache_keyspace, prepared.clone());
+        Ok(prepared)
+    }
+
+    /// Removes all statements from the prepared statement cache. The cache is cleared
+    /// automatically on schema change events, but this can be used when they might have been
+    /// missed.
+    #[inline]
+    pub fn clear_prepared_statement_cache(&self) {
+        self.prepared_statement_cache.clear();
     }
 
     /// It prepares query without additional tracing information and warnings.
@@ -459,7 +592,10 @@ impl<
             .await
     }
 
-    /// Executes batch query with parameters.
+    /// Executes batch query with parameters. Consistency, serial consistency, timestamp, keyspace
+    /// and "now" are taken from the batch parameters, so corresponding query parameters of given
+    /// statement parameters are ignored. The batch is considered idempotent if either the batch
+    /// parameters or given statement parameters say so, unless it's a counter batch.
     pub async fn batch_with_params(
         &self,
         batch: QueryBatch,
@@ -471,19 +607,23 @@ impl<
             parameters.beta_protocol,
         );
 
-        let consistency = batch.consistency;
+        let consistency = batch.batch_params.consistency;
+        let is_idempotent = batch.batch_type != BatchType::Counter
+            && (batch.batch_params.is_idempotent || parameters.is_idempotent);
 
-        let envelope = Envelope::new_req_batch(batch, flags, self.version);
+        let envelope = Envelope::new_req_batch(batch, flags, self.protocol_version())
+            .with_custom_payload(parameters.custom_payload.clone());
 
         self.send_envelope(
             envelope,
-            parameters.is_idempotent,
+            is_idempotent,
             parameters.keyspace.as_deref(),
             None,
             None,
             Some(consistency),
             parameters.speculative_execution_policy.as_ref(),
             parameters.retry_policy.as_ref(),
+            parameters.request_timeout,
         )
         .await
     }
@@ -511,24 +651,104 @@ impl<
         .await
     }
 
+    /// Executes a query with query parameters and returns a page of resulting rows, which is empty
+    /// if the query doesn't return any, e.g. for `INSERT` statements. Only a single page is
+    /// fetched, as set by page size and paging state in given parameters; the next one can be
+    /// fetched using returned paging state. Use `query_stream` to transparently fetch all pages.
+    pub async fn query_rows<Q: ToString>(
+        &self,
+        query: Q,
+        parameters: StatementParams,
+    ) -> error::Result<RowsPage> {
+        self.query_with_params(query, parameters)
+            .await
+            .and_then(envelope_page)
+    }
+
+    /// Executes a query with query parameters, like `query_rows`, and decodes resulting rows with
+    /// given function, e.g. `TryFromRow::try_from_row`.
+    pub async fn query_map<Q: ToString, R>(
+        &self,
+        query: Q,
+        parameters: StatementParams,
+        decode: impl FnMut(Row) -> error::Result<R>,
+    ) -> error::Result<RowsPage<R>> {
+        let page = self.query_rows(query, parameters).await?;
+
+        Ok(RowsPage {
+            rows: page
+                .rows
+                .into_iter()
+                .map(decode)
+                .collect::<error::Result<_>>()?,
+            paging_state: page.paging_state,
+        })
+    }
+
+    /// Executes a query and returns a stream of resulting rows. Subsequent pages are fetched
+    /// transparently using returned paging state, until all rows are consumed. Page size can be
+    /// set with `StatementParamsBuilder::with_page_size`.
+    pub fn query_stream<Q: ToString>(
+        &self,
+        query: Q,
+        parameters: StatementParams,
+    ) -> impl Stream<Item = error::Result<Row>> + '_ {
+        let query = query.to_string();
+
+        stream::try_unfold(Some(parameters), move |parameters| {
+            let query = query.clone();
+            async move {
+                let mut parameters = match parameters {
+                    Some(parameters) => parameters,
+                    None => return Ok(None),
+                };
+
+                let page = self.query_rows(query, parameters.clone()).await?;
+                let parameters = page.paging_state.map(|paging_state| {
+                    parameters.query_params.paging_state = Some(paging_state);
+                    parameters
+                });
+
+                error::Result::Ok(Some((
+                    stream::iter(page.rows.into_iter().map(Ok)),
+                    parameters,
+                )))
+            }
+        })
+        .try_flatten()
+    }
+
     /// Executes a query with query parameters.
     pub async fn query_with_params<Q: ToString>(
         &self,
         query: Q,
         parameters: StatementParams,
     ) -> error::Result<Envelope> {
+        let query = query.to_string();
+        let query_params =
+            with_default_page_size(Cow::Owned(parameters.query_params), self.default_page_size)
+                .into_owned();
+
+        query_params.verify()?;
+        verify_consistency(&query, query_params.consistency)?;
+        log_unsupported_params(&query_params, self.protocol_version());
+
+        let query_hash = self.verify_paging_state(&query, &query_params)?;
+
         let is_idempotent = parameters.is_idempotent;
-        let consistency = parameters.query_params.consistency;
+        let consistency = query_params.consistency;
         let keyspace = parameters.keyspace;
         let token = parameters.token;
+        let custom_payload = parameters.custom_payload;
         let routing_key = parameters
             .routing_key
             .as_ref()
-            .map(|values| serialize_routing_key(values, self.version));
+            .map(|values| serialize_routing_key(values))
+            .transpose()?;
 
         let query = Query {
-            query: query.to_string(),
-            params: parameters.query_params,
+            query,
+            params: query_params,
         };
 
         let flags = prepare_flags(
@@ -537,19 +757,52 @@ impl<
             parameters.beta_protocol,
         );
 
-        let envelope = Envelope::new_query(query, flags, self.version);
+        let envelope = Envelope::new_query(query, flags, self.protocol_version())
+            .with_custom_payload(custom_payload);
 
-        self.send_envelope(
-            envelope,
-            is_idempotent,
-            keyspace.as_deref(),
-            token,
-            routing_key.as_deref(),
-            Some(consistency),
-            parameters.speculative_execution_policy.as_ref(),
-            parameters.retry_policy.as_ref(),
-        )
-        .await
+        let result = self
+            .send_envelope(
+                envelope,
+                is_idempotent,
+                keyspace.as_deref(),
+                token,
+                routing_key.as_deref(),
+                Some(consistency),
+                parameters.speculative_execution_policy.as_ref(),
+                parameters.retry_policy.as_ref(),
+                parameters.request_timeout,
+            )
+            .await;
+
+        self.record_paging_state(query_hash, &result);
+        result
+    }
+
+    /// Verifies the paging state in given parameters, if any, was not returned for a different
+    /// query. Returns the query hash to record the resulting paging state with, if paging state
+    /// validation is enabled.
+    fn verify_paging_state(
+        &self,
+        query: &str,
+        query_params: &QueryParams,
+    ) -> error::Result<Option<u64>> {
+        self.paging_state_validator
+            .as_ref()
+            .map(|validator| {
+                let query_hash = PagingStateValidator::query_hash(query);
+                validator
+                    .verify(query_hash, query_params.paging_state.as_ref())
+                    .map(|_| query_hash)
+            })
+            .transpose()
+    }
+
+    fn record_paging_state(&self, query_hash: Option<u64>, result: &error::Result<Envelope>) {
+        if let (Some(validator), Some(query_hash), Ok(envelope)) =
+            (&self.paging_state_validator, query_hash, result)
+        {
+            validator.record(query_hash, envelope);
+        }
     }
 
     /// Returns currently set global keyspace.
@@ -578,19 +831,136 @@ impl<
         self.event_sender.subscribe()
     }
 
+    /// Creates a new stream of server events, i.e. topology, status and schema changes. Events
+    /// missed due to the stream not being polled fast enough are skipped.
+    #[inline]
+    pub fn create_event_stream(&self) -> impl Stream<Item = ServerEvent> + Send + 'static {
+        event_stream(self.create_event_receiver())
+    }
+
     /// Returns current retry policy.
     #[inline]
     pub fn retry_policy(&self) -> &dyn RetryPolicy {
         self.retry_policy.as_ref()
     }
 
+    /// Returns protocol version used by all connections. Starts with the configured version,
+    /// which is lowered to V4 if a server doesn't support V5. Compression negotiated for a given
+    /// connection is available via [`CdrsTransport::compression`], since the driver falls back to
+    /// no compression for nodes not supporting the configured one.
+    #[inline]
+    pub fn protocol_version(&self) -> Version {
+        self.version.current()
+    }
+
+    /// Sends an `OPTIONS` request to a node chosen by the load balancing strategy and returns
+    /// options supported by the server, e.g. CQL versions, compression algorithms and protocol
+    /// versions. Nodes in a cluster can differ, e.g. during a rolling upgrade.
+    pub async fn supported_options(&self) -> error::Result<BodyResSupported> {
+        let envelope = Envelope::new_req_options(self.protocol_version());
+
+        self.send_envelope(envelope, true, None, None, None, None, None, None, None)
+            .await?
+            .response_body()?
+            .into_supported()
+            .ok_or_else(|| "Unexpected response to OPTIONS request!".into())
+    }
+
+    /// Gracefully shuts the session down. New requests are rejected with an error, while
+    /// in-flight ones are given up to `drain_timeout` to complete. Afterwards, the control
+    /// connection is closed, along with pooled connections to known nodes, once no longer used.
+    /// Returns [`TimeoutOperation::Shutdown`](error::TimeoutOperation::Shutdown) error describing
+    /// pending requests if they didn't complete in time; the connections are closed nevertheless.
+    pub async fn shutdown(&self, drain_timeout: Duration) -> error::Result<()> {
+        self.is_shut_down.store(true, Ordering::SeqCst);
+
+        let result = timeout(drain_timeout, self.in_flight_requests.drained())
+            .await
+            .map_err(|_| error::Error::Timeout {
+                operation: error::TimeoutOperation::Shutdown(self.in_flight_requests.pending()),
+                elapsed: drain_timeout,
+            });
+
+        self.control_connection_handle.abort();
+        self.cluster_metadata_manager.clear_metadata();
+
+        result
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn send_envelope(
         &self,
         envelope: Envelope,
         is_idempotent: bool,
         keyspace: Option<&str>,
-        token: Option<Murmur3Token>,
+        token: Option<Token>,
+        routing_key: Option<&[u8]>,
+        consistency: Option<Consistency>,
+        speculative_execution_policy: Option<&Arc<dyn SpeculativeExecutionPolicy + Send + Sync>>,
+        retry_policy: Option<&Arc<dyn RetryPolicy + Send + Sync>>,
+        request_timeout: Option<Duration>,
+    ) -> error::Result<Envelope> {
+        // register the request before checking the flag, so shutdown can't miss it
+        let envelope = Arc::new(envelope);
+        let _in_flight_request = self.in_flight_requests.start(envelope.clone());
+        if self.is_shut_down.load(Ordering::SeqCst) {
+            return Err("Session has been shut down!".into());
+        }
+
+        // compute the token once, so it can be reported along with the nodes chosen for the
+        // request
+        let token = token.or_else(|| {
+            routing_key.map(|routing_key| {
+                self.cluster_metadata()
+                    .token_map()
+                    .partitioner()
+                    .generate_token(routing_key)
+            })
+        });
+
+        let span = debug_span!("request", token = token.as_ref().map(field::display));
+        let result = self
+            .send_envelope_without_timeout(
+                &envelope,
+                is_idempotent,
+                keyspace,
+                token,
+                routing_key,
+                consistency,
+                speculative_execution_policy,
+                retry_policy,
+            )
+            .instrument(span);
+
+        let result = match request_timeout {
+            Some(request_timeout) => {
+                timeout(request_timeout, result)
+                    .await
+                    .unwrap_or(Err(error::Error::Timeout {
+                        operation: error::TimeoutOperation::Request,
+                        elapsed: request_timeout,
+                    }))
+            }
+            None => result.await,
+        };
+
+        // warnings are also available to the caller in the response envelope
+        if let Ok(envelope) = &result {
+            for warning in envelope.warnings() {
+                warn!(%warning, "Server warning.");
+            }
+        }
+
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn send_envelope_without_timeout(
+        &self,
+        envelope: &Envelope,
+        is_idempotent: bool,
+        keyspace: Option<&str>,
+        token: Option<Token>,
         routing_key: Option<&[u8]>,
         consistency: Option<Consistency>,
         speculative_execution_policy: Option<&Arc<dyn SpeculativeExecutionPolicy + Send + Sync>>,
@@ -650,11 +1020,12 @@ impl<
             Some(speculative_execution_policy) if is_idempotent => {
                 let shared_query_plan = SharedQueryPlan::new(query_plan.into_iter());
 
+                let start = Instant::now();
                 let mut context = Context::new(1);
                 let mut async_tasks = FuturesUnordered::new();
                 async_tasks.push(send_envelope(
                     &shared_query_plan,
-                    &envelope,
+                    envelope,
                     is_idempotent,
                     retry_policy.new_session(),
                 ));
@@ -679,7 +1050,7 @@ impl<
                                 context.running_executions += 1;
                                 async_tasks.push(send_envelope(
                                     &shared_query_plan,
-                                    &envelope,
+                                    envelope,
                                     is_idempotent,
                                     retry_policy.new_session(),
                                 ));
@@ -691,9 +1062,13 @@ impl<
                             match result {
                                 Some(result) => {
                                     match result {
-                                        Err(error::Error::Io(_)) | Err(error::Error::Timeout(_)) => {
+                                        Err(error::Error::Io(_)) | Err(error::Error::Timeout { .. }) => {
                                             last_error = Some(result);
                                         },
+                                        Ok(_) => {
+                                            speculative_execution_policy.on_execution_completed(start.elapsed());
+                                            return result;
+                                        }
                                         _ => return result,
                                     }
                                 }
@@ -712,7 +1087,7 @@ impl<
             }
             _ => send_envelope(
                 query_plan.into_iter(),
-                &envelope,
+                envelope,
                 is_idempotent,
                 retry_policy.new_session(),
             )
@@ -731,27 +1106,26 @@ impl<
             .unwrap_or_else(|| self.retry_policy.as_ref())
     }
 
-    #[allow(clippy::too_many_arguments)]
     fn new(
-        load_balancing: LB,
+        config: SessionConfig<T, CM, LB>,
         keyspace_holder: Arc<KeyspaceHolder>,
         keyspace_receiver: watch::Receiver<Option<String>>,
-        retry_policy: Box<dyn RetryPolicy + Send + Sync>,
-        reconnection_policy: Arc<dyn ReconnectionPolicy + Send + Sync>,
-        node_distance_evaluator: Box<dyn NodeDistanceEvaluator + Send + Sync>,
-        speculative_execution_policy: Option<Box<dyn SpeculativeExecutionPolicy + Send + Sync>>,
         contact_points: Vec<SocketAddr>,
         connection_manager: CM,
-        event_channel_capacity: usize,
-        version: Version,
-        connection_pool_config: ConnectionPoolConfig,
+        version: Arc<VersionHolder>,
         beta_protocol: bool,
     ) -> Self {
+        if let Some(keyspace) = config.keyspace {
+            keyspace_holder.update_current_keyspace_without_notification(keyspace);
+        }
+
+        let options = config.options;
         let connection_pool_factory = Arc::new(ConnectionPoolFactory::new(
-            connection_pool_config,
-            version,
+            config.connection_pool_config,
+            version.clone(),
             connection_manager,
             keyspace_receiver,
+            options.metrics,
         ));
 
         let contact_points = contact_points
@@ -774,11 +1148,11 @@ impl<
             .collect_vec();
 
         let load_balancing = Arc::new(InitializingWrapperLoadBalancingStrategy::new(
-            load_balancing,
+            config.load_balancing,
             contact_points.clone(),
         ));
 
-        let (event_sender, event_receiver) = channel(event_channel_capacity);
+        let (event_sender, event_receiver) = channel(config.event_channel_capacity);
 
         let session_context = Arc::new(SessionContext::default());
 
@@ -786,8 +1160,8 @@ impl<
             contact_points.clone(),
             connection_pool_factory,
             session_context.clone(),
-            node_distance_evaluator,
-            version,
+            config.node_distance_evaluator,
+            version.clone(),
             beta_protocol,
         ));
 
@@ -798,23 +1172,36 @@ impl<
         let control_connection = ControlConnection::new(
             load_balancing.clone(),
             contact_points,
-            reconnection_policy.clone(),
+            config.reconnection_policy,
             cluster_metadata_manager.clone(),
             event_sender.clone(),
             session_context,
-            version,
+            version.clone(),
         );
 
         let control_connection_handle = tokio::spawn(control_connection.run());
 
+        let prepared_statement_cache = Arc::new(PreparedStatementCache::new(
+            options.prepared_statement_cache_size,
+        ));
+        prepared_statement_cache
+            .clone()
+            .clear_on_schema_change(event_sender.subscribe());
+
         Session {
             load_balancing,
             keyspace_holder,
-            retry_policy,
-            speculative_execution_policy,
+            retry_policy: config.retry_policy,
+            speculative_execution_policy: config.speculative_execution_policy,
             control_connection_handle,
             event_sender,
             cluster_metadata_manager,
+            reprepare_on_unprepared: options.reprepare_on_unprepared,
+            prepared_statement_cache,
+            default_page_size: options.default_page_size,
+            paging_state_validator: options.paging_state_validation.then(Default::default),
+            in_flight_requests: Default::default(),
+            is_shut_down: AtomicBool::new(false),
             _transport: Default::default(),
             _connection_manager: Default::default(),
             version,
@@ -845,7 +1232,9 @@ pub struct SpeculativeExecutionPolicyWrapper(pub Box<dyn SpeculativeExecutionPol
 ///
 /// The config object supplied differs from the [`NodeTcpConfig`] and [`NodeRustlsConfig`]
 /// objects in that it is not expected to include an address. Instead the same configuration
-/// will be applied to all connections across the cluster.
+/// will be applied to all connections across the cluster. Session-level settings, which are not
+/// related to connections, are given by `options`.
+#[allow(clippy::too_many_arguments)]
 pub async fn connect_generic<T, C, A, CM, LB>(
     config: &C,
     initial_nodes: A,
@@ -854,6 +1243,7 @@ pub async fn connect_generic<T, C, A, CM, LB>(
     reconnection_policy: ReconnectionPolicyWrapper,
     node_distance_evaluator: NodeDistanceEvaluatorWrapper,
     speculative_execution_policy: Option<SpeculativeExecutionPolicyWrapper>,
+    options: SessionOptions,
 ) -> error::Result<Session<T, CM, LB>>
 where
     A: IntoIterator<Item = SocketAddr>,
@@ -862,33 +1252,117 @@ where
     C: GenericClusterConfig<T, CM>,
     LB: LoadBalancingStrategy<T, CM> + Sized + Send + Sync + 'static,
 {
+    options
+        .verify()
+        .map_err(|error| error::Error::General(error.to_string()))?;
+
+    let session_config = SessionConfig {
+        retry_policy: retry_policy.0,
+        reconnection_policy: reconnection_policy.0,
+        node_distance_evaluator: node_distance_evaluator.0,
+        speculative_execution_policy: speculative_execution_policy.map(|policy| policy.0),
+        event_channel_capacity: config.event_channel_capacity(),
+        connection_pool_config: config.connection_pool_config(),
+        options,
+        ..SessionConfig::new(load_balancing)
+    };
+
     let (keyspace_holder, keyspace_receiver) = create_keyspace_holder();
-    let connection_manager = config.create_manager(keyspace_holder.clone()).await?;
+    let version = Arc::new(VersionHolder::new(config.version()));
+    let connection_manager = config
+        .create_manager(keyspace_holder.clone(), version.clone())
+        .await?;
     Ok(Session::new(
-        load_balancing,
+        session_config,
         keyspace_holder,
         keyspace_receiver,
-        retry_policy.0,
-        reconnection_policy.0,
-        node_distance_evaluator.0,
-        speculative_execution_policy.map(|policy| policy.0),
         initial_nodes.into_iter().collect(),
         connection_manager,
-        config.event_channel_capacity(),
-        config.version(),
-        config.connection_pool_config(),
+        version,
         config.beta_protocol(),
     ))
 }
 
+/// Session-level settings, which are not related to individual connections. Used directly by
+/// [`connect_generic`] and set through [`SessionBuilder`] otherwise.
+#[derive(Clone)]
+pub struct SessionOptions {
+    reprepare_on_unprepared: bool,
+    metrics: Arc<dyn Metrics>,
+    prepared_statement_cache_size: usize,
+    default_page_size: Option<CInt>,
+    paging_state_validation: bool,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        SessionOptions {
+            reprepare_on_unprepared: true,
+            metrics: Arc::new(NoopMetrics),
+            prepared_statement_cache_size: DEFAULT_PREPARED_STATEMENT_CACHE_SIZE,
+            default_page_size: None,
+            paging_state_validation: false,
+        }
+    }
+}
+
+impl SessionOptions {
+    /// See [`SessionBuilder::with_reprepare_on_unprepared`].
+    #[must_use]
+    pub fn with_reprepare_on_unprepared(mut self, reprepare_on_unprepared: bool) -> Self {
+        self.reprepare_on_unprepared = reprepare_on_unprepared;
+        self
+    }
+
+    /// See [`SessionBuilder::with_metrics`].
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// See [`SessionBuilder::with_prepared_statement_cache_size`].
+    #[must_use]
+    pub fn with_prepared_statement_cache_size(
+        mut self,
+        prepared_statement_cache_size: usize,
+    ) -> Self {
+        self.prepared_statement_cache_size = prepared_statement_cache_size;
+        self
+    }
+
+    /// See [`SessionBuilder::with_default_page_size`].
+    #[must_use]
+    pub fn with_default_page_size(mut self, page_size: i32) -> Self {
+        self.default_page_size = Some(page_size);
+        self
+    }
+
+    /// See [`SessionBuilder::with_paging_state_validation`].
+    #[must_use]
+    pub fn with_paging_state_validation(mut self, paging_state_validation: bool) -> Self {
+        self.paging_state_validation = paging_state_validation;
+        self
+    }
+
+    fn verify(&self) -> Result<(), SessionBuildError> {
+        match self.default_page_size {
+            Some(page_size) if page_size <= 0 => Err(SessionBuildError::InvalidDefaultPageSize),
+            _ => Ok(()),
+        }
+    }
+}
+
 struct SessionConfig<
     T: CdrsTransport,
     CM: ConnectionManager<T>,
     LB: LoadBalancingStrategy<T, CM> + Send + Sync,
 > {
     compression: Compression,
+    compression_threshold: usize,
     transport_buffer_size: usize,
     tcp_nodelay: bool,
+    max_in_flight_requests: usize,
     load_balancing: LB,
     retry_policy: Box<dyn RetryPolicy + Send + Sync>,
     reconnection_policy: Arc<dyn ReconnectionPolicy + Send + Sync>,
@@ -897,6 +1371,7 @@ struct SessionConfig<
     event_channel_capacity: usize,
     connection_pool_config: ConnectionPoolConfig,
     keyspace: Option<String>,
+    options: SessionOptions,
     _connection_manager: PhantomData<CM>,
     _transport: PhantomData<T>,
 }
@@ -910,8 +1385,10 @@ impl<
     fn new(load_balancing: LB) -> Self {
         SessionConfig {
             compression: Compression::None,
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
             transport_buffer_size: DEFAULT_TRANSPORT_BUFFER_SIZE,
             tcp_nodelay: true,
+            max_in_flight_requests: MAX_IN_FLIGHT_REQUESTS,
             load_balancing,
             retry_policy: Box::new(DefaultRetryPolicy::default()),
             reconnection_policy: Arc::new(ExponentialReconnectionPolicy::default()),
@@ -920,39 +1397,15 @@ impl<
             event_channel_capacity: DEFAULT_EVENT_CHANNEL_CAPACITY,
             connection_pool_config: Default::default(),
             keyspace: None,
+            options: Default::default(),
             _connection_manager: Default::default(),
             _transport: Default::default(),
         }
     }
 
-    fn into_session(
-        self,
-        keyspace_holder: Arc<KeyspaceHolder>,
-        keyspace_receiver: watch::Receiver<Option<String>>,
-        contact_points: Vec<SocketAddr>,
-        connection_manager: CM,
-        version: Version,
-        beta_protocol: bool,
-    ) -> Session<T, CM, LB> {
-        if let Some(keyspace) = self.keyspace {
-            keyspace_holder.update_current_keyspace_without_notification(keyspace);
-        }
-
-        Session::new(
-            self.load_balancing,
-            keyspace_holder,
-            keyspace_receiver,
-            self.retry_policy,
-            self.reconnection_policy,
-            self.node_distance_evaluator,
-            self.speculative_execution_policy,
-            contact_points,
-            connection_manager,
-            self.event_channel_capacity,
-            version,
-            self.connection_pool_config,
-            beta_protocol,
-        )
+    fn verify(&self, version: Version) -> Result<(), SessionBuildError> {
+        verify_compression_configuration(version, self.compression)?;
+        self.options.verify()
     }
 }
 
@@ -961,6 +1414,8 @@ impl<
 pub enum SessionBuildError {
     #[error("Given compression type is not supported for selected protocol!")]
     CompressionTypeNotSupported,
+    #[error("Default page size must be positive!")]
+    InvalidDefaultPageSize,
 }
 
 /// Builder for easy `Session` creation. Requires static `LoadBalancingStrategy`, but otherwise, other
@@ -976,6 +1431,13 @@ pub trait SessionBuilder<
     #[must_use]
     fn with_compression(self, compression: Compression) -> Self;
 
+    /// Sets minimum envelope body size, in bytes, which gets compressed. Smaller envelopes are sent
+    /// uncompressed, since they don't benefit from compression. Only applies to protocols older
+    /// than V5, which compress individual envelopes. Defaults to
+    /// [`DEFAULT_COMPRESSION_THRESHOLD`].
+    #[must_use]
+    fn with_compression_threshold(self, compression_threshold: usize) -> Self;
+
     /// Set new retry policy.
     #[must_use]
     fn with_retry_policy(self, retry_policy: Box<dyn RetryPolicy + Send + Sync>) -> Self;
@@ -1018,6 +1480,12 @@ pub trait SessionBuilder<
     #[must_use]
     fn with_tcp_nodelay(self, tcp_nodelay: bool) -> Self;
 
+    /// Sets the maximum number of concurrent requests on a single connection. When reached,
+    /// subsequent requests wait for responses to previous ones. Values are capped at
+    /// `MAX_IN_FLIGHT_REQUESTS`, which is also the default.
+    #[must_use]
+    fn with_max_in_flight_requests(self, max_in_flight_requests: usize) -> Self;
+
     /// Sets event channel capacity. If the driver receives more server events than the capacity,
     /// some events might get dropped. This can result in the driver operating in a sub-optimal way.
     #[must_use]
@@ -1041,6 +1509,37 @@ pub trait SessionBuilder<
     #[must_use]
     fn with_beta_protocol(self, beta_protocol: bool) -> Self;
 
+    /// Sets if prepared statements should be transparently re-prepared and executed again when a
+    /// node responds with an `UNPREPARED` error, e.g. after a restart. Enabled by default.
+    #[must_use]
+    fn with_reprepare_on_unprepared(self, reprepare_on_unprepared: bool) -> Self;
+
+    /// Sets the metrics hook notified about requests and connections.
+    #[must_use]
+    fn with_metrics(self, metrics: Arc<dyn Metrics>) -> Self;
+
+    /// Sets the maximum number of prepared statements cached by query string and keyspace, to
+    /// avoid preparing them again. Least recently used statements are evicted first. Use `0` to
+    /// disable caching. Defaults to [`DEFAULT_PREPARED_STATEMENT_CACHE_SIZE`].
+    #[must_use]
+    fn with_prepared_statement_cache_size(self, prepared_statement_cache_size: usize) -> Self;
+
+    /// Sets the default page size for queries and prepared statements, which don't set one
+    /// explicitly, e.g. with `StatementParamsBuilder::with_page_size`. An explicit page size always
+    /// takes precedence. Not set by default, in which case results are not paged; setting it
+    /// prevents unbounded queries from fetching whole tables at once. Note: when set, methods
+    /// which don't fetch subsequent pages, e.g. `Session::query` or `Session::query_rows`, return
+    /// only the first page of results. Must be positive, otherwise building the session fails.
+    #[must_use]
+    fn with_default_page_size(self, page_size: i32) -> Self;
+
+    /// Enables detecting paging states reused with a different query than the one which returned
+    /// them, which results in an error before sending the request, rather than a confusing server
+    /// error. Only recent paging states returned by this session are tracked. Disabled by default,
+    /// since it adds overhead to every request; meant for development and testing.
+    #[must_use]
+    fn with_paging_state_validation(self, paging_state_validation: bool) -> Self;
+
     /// Builds the resulting session.
     fn build(self) -> Result<Session<T, CM, LB>, SessionBuildError>;
 }
@@ -1076,6 +1575,11 @@ impl<LB: LoadBalancingStrategy<TransportTcp, TcpConnectionManager> + Send + Sync
         self
     }
 
+    fn with_compression_threshold(mut self, compression_threshold: usize) -> Self {
+        self.config.compression_threshold = compression_threshold;
+        self
+    }
+
     fn with_retry_policy(mut self, retry_policy: Box<dyn RetryPolicy + Send + Sync>) -> Self {
         self.config.retry_policy = retry_policy;
         self
@@ -1123,6 +1627,11 @@ impl<LB: LoadBalancingStrategy<TransportTcp, TcpConnectionManager> + Send + Sync
         self
     }
 
+    fn with_max_in_flight_requests(mut self, max_in_flight_requests: usize) -> Self {
+        self.config.max_in_flight_requests = max_in_flight_requests;
+        self
+    }
+
     fn with_event_channel_capacity(mut self, event_channel_capacity: usize) -> Self {
         self.config.event_channel_capacity = event_channel_capacity;
         self
@@ -1143,31 +1652,58 @@ impl<LB: LoadBalancingStrategy<TransportTcp, TcpConnectionManager> + Send + Sync
         self
     }
 
-    fn build(self) -> Result<Session<TransportTcp, TcpConnectionManager, LB>, SessionBuildError> {
-        verify_compression_configuration(self.node_config.version, self.config.compression).map(
-            |()| {
-                let (keyspace_holder, keyspace_receiver) = create_keyspace_holder();
-                let connection_manager = TcpConnectionManager::new(
-                    self.node_config.authenticator_provider,
-                    keyspace_holder.clone(),
-                    self.config.reconnection_policy.clone(),
-                    self.frame_encoder_factory,
-                    self.config.compression,
-                    self.config.transport_buffer_size,
-                    self.config.tcp_nodelay,
-                    self.node_config.version,
-                );
+    fn with_reprepare_on_unprepared(mut self, reprepare_on_unprepared: bool) -> Self {
+        self.config.options.reprepare_on_unprepared = reprepare_on_unprepared;
+        self
+    }
 
-                self.config.into_session(
-                    keyspace_holder,
-                    keyspace_receiver,
-                    self.node_config.contact_points,
-                    connection_manager,
-                    self.node_config.version,
-                    self.node_config.beta_protocol,
-                )
-            },
-        )
+    fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.config.options.metrics = metrics;
+        self
+    }
+
+    fn with_prepared_statement_cache_size(mut self, prepared_statement_cache_size: usize) -> Self {
+        self.config.options.prepared_statement_cache_size = prepared_statement_cache_size;
+        self
+    }
+
+    fn with_default_page_size(mut self, page_size: i32) -> Self {
+        self.config.options.default_page_size = Some(page_size);
+        self
+    }
+
+    fn with_paging_state_validation(mut self, paging_state_validation: bool) -> Self {
+        self.config.options.paging_state_validation = paging_state_validation;
+        self
+    }
+
+    fn build(self) -> Result<Session<TransportTcp, TcpConnectionManager, LB>, SessionBuildError> {
+        self.config.verify(self.node_config.version).map(|()| {
+            let (keyspace_holder, keyspace_receiver) = create_keyspace_holder();
+            let version = Arc::new(VersionHolder::new(self.node_config.version));
+            let connection_manager = TcpConnectionManager::new(
+                self.node_config.authenticator_provider,
+                keyspace_holder.clone(),
+                self.config.reconnection_policy.clone(),
+                self.frame_encoder_factory,
+                self.config.compression,
+                self.config.compression_threshold,
+                self.config.transport_buffer_size,
+                self.config.tcp_nodelay,
+                self.config.max_in_flight_requests,
+                version.clone(),
+            );
+
+            Session::new(
+                self.config,
+                keyspace_holder,
+                keyspace_receiver,
+                self.node_config.contact_points,
+                connection_manager,
+                version,
+                self.node_config.beta_protocol,
+            )
+        })
     }
 }
 
@@ -1206,6 +1742,11 @@ impl<
         self
     }
 
+    fn with_compression_threshold(mut self, compression_threshold: usize) -> Self {
+        self.config.compression_threshold = compression_threshold;
+        self
+    }
+
     fn with_retry_policy(mut self, retry_policy: Box<dyn RetryPolicy + Send + Sync>) -> Self {
         self.config.retry_policy = retry_policy;
         self
@@ -1253,6 +1794,11 @@ impl<
         self
     }
 
+    fn with_max_in_flight_requests(mut self, max_in_flight_requests: usize) -> Self {
+        self.config.max_in_flight_requests = max_in_flight_requests;
+        self
+    }
+
     fn with_event_channel_capacity(mut self, event_channel_capacity: usize) -> Self {
         self.config.event_channel_capacity = event_channel_capacity;
         self
@@ -1273,42 +1819,98 @@ impl<
         self
     }
 
+    fn with_reprepare_on_unprepared(mut self, reprepare_on_unprepared: bool) -> Self {
+        self.config.options.reprepare_on_unprepared = reprepare_on_unprepared;
+        self
+    }
+
+    fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.config.options.metrics = metrics;
+        self
+    }
+
+    fn with_prepared_statement_cache_size(mut self, prepared_statement_cache_size: usize) -> Self {
+        self.config.options.prepared_statement_cache_size = prepared_statement_cache_size;
+        self
+    }
+
+    fn with_default_page_size(mut self, page_size: i32) -> Self {
+        self.config.options.default_page_size = Some(page_size);
+        self
+    }
+
+    fn with_paging_state_validation(mut self, paging_state_validation: bool) -> Self {
+        self.config.options.paging_state_validation = paging_state_validation;
+        self
+    }
+
     fn build(
         self,
     ) -> Result<Session<TransportRustls, RustlsConnectionManager, LB>, SessionBuildError> {
-        verify_compression_configuration(self.node_config.version, self.config.compression).map(
-            |()| {
-                let (keyspace_holder, keyspace_receiver) = create_keyspace_holder();
-                let connection_manager = RustlsConnectionManager::new(
-                    self.node_config.dns_name,
-                    self.node_config.authenticator_provider,
-                    self.node_config.config,
-                    keyspace_holder.clone(),
-                    self.config.reconnection_policy.clone(),
-                    self.frame_encoder_factory,
-                    self.config.compression,
-                    self.config.transport_buffer_size,
-                    self.config.tcp_nodelay,
-                    self.node_config.version,
-                );
-
-                self.config.into_session(
-                    keyspace_holder,
-                    keyspace_receiver,
-                    self.node_config.contact_points,
-                    connection_manager,
-                    self.node_config.version,
-                    self.node_config.beta_protocol,
-                )
-            },
-        )
+        self.config.verify(self.node_config.version).map(|()| {
+            let (keyspace_holder, keyspace_receiver) = create_keyspace_holder();
+            let version = Arc::new(VersionHolder::new(self.node_config.version));
+            let connection_manager = RustlsConnectionManager::new(
+                self.node_config.dns_name,
+                self.node_config.authenticator_provider,
+                self.node_config.config,
+                keyspace_holder.clone(),
+                self.config.reconnection_policy.clone(),
+                self.frame_encoder_factory,
+                self.config.compression,
+                self.config.compression_threshold,
+                self.config.transport_buffer_size,
+                self.config.tcp_nodelay,
+                self.config.max_in_flight_requests,
+                version.clone(),
+            );
+
+            Session::new(
+                self.config,
+                keyspace_holder,
+                keyspace_receiver,
+                self.node_config.contact_points,
+                connection_manager,
+                version,
+                self.node_config.beta_protocol,
+            )
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cluster::session::prepare_flags;
-    use cassandra_protocol::frame::Flags;
+    use crate::authenticators::NoneAuthenticatorProvider;
+    use crate::cluster::connection_manager::MockConnectionManager;
+    use crate::cluster::connection_pool::ConnectionPoolConfig;
+    use crate::cluster::session::{
+        connect_generic, envelope_page, event_stream, prepare_flags, with_default_page_size,
+        InFlightRequests, NodeDistanceEvaluatorWrapper, ReconnectionPolicyWrapper,
+        RetryPolicyWrapper, Session, SessionBuildError, SessionBuilder, SessionOptions,
+        TcpSessionBuilder,
+    };
+    use crate::cluster::{GenericClusterConfig, KeyspaceHolder, NodeTcpConfig, VersionHolder};
+    use crate::future::BoxFuture;
+    use crate::load_balancing::node_distance_evaluator::AllLocalNodeDistanceEvaluator;
+    use crate::load_balancing::RoundRobinLoadBalancingStrategy;
+    use crate::retry::{ConstantReconnectionPolicy, DefaultRetryPolicy};
+    use crate::transport::MockCdrsTransport;
+    use arc_swap::ArcSwapOption;
+    use cassandra_protocol::events::ServerEvent;
+    use cassandra_protocol::frame::events::{StatusChange, StatusChangeType};
+    use cassandra_protocol::frame::message_result::{
+        BodyResResultRows, ResultKind, RowsMetadata, RowsMetadataFlags,
+    };
+    use cassandra_protocol::frame::Serialize;
+    use cassandra_protocol::frame::{Direction, Envelope, Flags, Opcode, Version};
+    use cassandra_protocol::query::{PreparedQuery, QueryParams};
+    use cassandra_protocol::types::{CBytes, CBytesShort, CInet};
+    use futures::{FutureExt, StreamExt};
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::broadcast::channel;
+    use tokio::time::timeout;
 
     #[test]
     fn prepare_flags_test() {
@@ -1321,4 +1923,241 @@ mod tests {
         assert!(all.contains(Flags::WARNING));
         assert!(all.contains(Flags::BETA));
     }
+
+    #[tokio::test]
+    async fn event_stream_test() {
+        let (sender, receiver) = channel(1);
+        let stream = event_stream(receiver);
+
+        let event = |port| {
+            ServerEvent::StatusChange(StatusChange {
+                change_type: StatusChangeType::Up,
+                addr: CInet::new(([127, 0, 0, 1], port).into()),
+            })
+        };
+
+        // the first event is skipped due to lagging
+        sender.send(event(1)).unwrap();
+        sender.send(event(2)).unwrap();
+        drop(sender);
+
+        assert_eq!(stream.collect::<Vec<_>>().await, vec![event(2)]);
+    }
+
+    #[tokio::test]
+    async fn in_flight_requests_test() {
+        let in_flight_requests = InFlightRequests::default();
+        in_flight_requests.drained().await;
+
+        let options = Arc::new(Envelope::new_req_options(Version::V4));
+        let first = in_flight_requests.start(options.clone());
+        let second = in_flight_requests.start(options);
+        assert_eq!(in_flight_requests.count(), 2);
+
+        drop(first);
+        let pending = in_flight_requests.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].opcode, Opcode::Options);
+
+        assert!(
+            timeout(Duration::from_millis(10), in_flight_requests.drained())
+                .await
+                .is_err()
+        );
+
+        let drained = in_flight_requests.drained();
+        drop(second);
+        timeout(Duration::from_secs(1), drained).await.unwrap();
+        assert_eq!(in_flight_requests.count(), 0);
+    }
+
+    #[test]
+    fn envelope_page_test() {
+        let void = Envelope::new(
+            Version::V4,
+            Direction::Response,
+            Flags::empty(),
+            Opcode::Result,
+            0,
+            vec![0, 0, 0, 1],
+            None,
+            vec![],
+        );
+        let page = envelope_page(void).unwrap();
+        assert!(page.rows.is_empty());
+        assert!(!page.has_more_pages());
+    }
+
+    #[test]
+    fn envelope_page_with_paging_state_test() {
+        let rows = BodyResResultRows {
+            metadata: RowsMetadata {
+                flags: RowsMetadataFlags::HAS_MORE_PAGES | RowsMetadataFlags::NO_METADATA,
+                columns_count: 1,
+                paging_state: Some(CBytes::new(vec![1, 2, 3])),
+                new_metadata_id: None,
+                global_table_spec: None,
+                col_specs: vec![],
+            },
+            rows_count: 1,
+            rows_content: vec![vec![CBytes::new(vec![0, 0, 0, 1])]],
+            protocol_version: Version::V4,
+        };
+
+        let mut body = ResultKind::Rows.serialize_to_vec(Version::V4);
+        body.extend(rows.serialize_to_vec(Version::V4));
+
+        let envelope = Envelope::new(
+            Version::V4,
+            Direction::Response,
+            Flags::empty(),
+            Opcode::Result,
+            0,
+            body,
+            None,
+            vec![],
+        );
+
+        let page = envelope_page(envelope).unwrap();
+        assert_eq!(page.rows.len(), 1);
+        assert_eq!(page.paging_state, Some(CBytes::new(vec![1, 2, 3])));
+    }
+
+    #[test]
+    fn should_reject_non_positive_default_page_size() {
+        let node_config = NodeTcpConfig {
+            contact_points: vec![],
+            authenticator_provider: Arc::new(NoneAuthenticatorProvider),
+            version: Version::V4,
+            beta_protocol: false,
+        };
+
+        let result = TcpSessionBuilder::new(RoundRobinLoadBalancingStrategy::new(), node_config)
+            .with_default_page_size(0)
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(SessionBuildError::InvalidDefaultPageSize)
+        );
+    }
+
+    struct MockClusterConfig;
+
+    impl GenericClusterConfig<MockCdrsTransport, MockConnectionManager<MockCdrsTransport>>
+        for MockClusterConfig
+    {
+        fn create_manager(
+            &self,
+            _keyspace_holder: Arc<KeyspaceHolder>,
+            _version_holder: Arc<VersionHolder>,
+        ) -> BoxFuture<
+            '_,
+            cassandra_protocol::error::Result<MockConnectionManager<MockCdrsTransport>>,
+        > {
+            async { Ok(MockConnectionManager::new()) }.boxed()
+        }
+
+        fn event_channel_capacity(&self) -> usize {
+            1
+        }
+
+        fn version(&self) -> Version {
+            Version::V4
+        }
+
+        fn connection_pool_config(&self) -> ConnectionPoolConfig {
+            Default::default()
+        }
+    }
+
+    async fn connect_mock_generic(
+        options: SessionOptions,
+    ) -> cassandra_protocol::error::Result<
+        Session<
+            MockCdrsTransport,
+            MockConnectionManager<MockCdrsTransport>,
+            RoundRobinLoadBalancingStrategy<
+                MockCdrsTransport,
+                MockConnectionManager<MockCdrsTransport>,
+            >,
+        >,
+    > {
+        connect_generic(
+            &MockClusterConfig,
+            vec![],
+            RoundRobinLoadBalancingStrategy::new(),
+            RetryPolicyWrapper(Box::new(DefaultRetryPolicy)),
+            ReconnectionPolicyWrapper(Arc::new(ConstantReconnectionPolicy::default())),
+            NodeDistanceEvaluatorWrapper(Box::new(AllLocalNodeDistanceEvaluator)),
+            None,
+            options,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn should_apply_generic_session_options() {
+        let options = SessionOptions::default()
+            .with_default_page_size(10)
+            .with_reprepare_on_unprepared(false)
+            .with_paging_state_validation(true);
+        let session = connect_mock_generic(options).await.unwrap();
+        assert_eq!(session.default_page_size, Some(10));
+        assert!(!session.reprepare_on_unprepared);
+        assert!(session.paging_state_validator.is_some());
+
+        let options = SessionOptions::default().with_default_page_size(0);
+        assert!(connect_mock_generic(options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_bypass_prepared_statement_cache_for_tracing_and_warnings() {
+        let session = connect_mock_generic(SessionOptions::default())
+            .await
+            .unwrap();
+
+        let query = "SELECT * FROM ks.t";
+        let prepared = PreparedQuery {
+            id: CBytesShort::new(vec![1]),
+            query: query.into(),
+            keyspace: None,
+            pk_indexes: vec![],
+            result_metadata_id: ArcSwapOption::empty(),
+        };
+        session
+            .prepared_statement_cache
+            .insert(None, prepared.clone());
+
+        // there are no nodes, so only cached statements can be prepared
+        assert_eq!(session.prepare(query).await.unwrap(), prepared);
+        assert!(session
+            .prepare_tw(query, None, true, false, false)
+            .await
+            .is_err());
+        assert!(session
+            .prepare_tw(query, None, false, true, false)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn with_default_page_size_test() {
+        let unset = QueryParams::default();
+        assert_eq!(
+            with_default_page_size(Cow::Borrowed(&unset), Some(100)).page_size,
+            Some(100)
+        );
+        let unchanged = with_default_page_size(Cow::Borrowed(&unset), None);
+        assert!(matches!(unchanged, Cow::Borrowed(_)));
+        assert_eq!(unchanged.page_size, None);
+
+        let explicit = QueryParams {
+            page_size: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            with_default_page_size(Cow::Owned(explicit), Some(100)).page_size,
+            Some(10)
+        );
+    }
 }
@@ -4,9 +4,12 @@ use std::ops::Deref;
 use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
 use tokio::time::sleep;
+use tracing::*;
 
-use crate::cluster::connection_manager::{startup, ConnectionManager};
-use crate::cluster::KeyspaceHolder;
+use crate::cluster::connection_manager::{
+    connect_with_version_fallback, negotiate_compression, startup, ConnectionManager,
+};
+use crate::cluster::{KeyspaceHolder, VersionHolder};
 use crate::frame_encoding::FrameEncodingFactory;
 use crate::future::BoxFuture;
 use crate::retry::ReconnectionPolicy;
@@ -22,9 +25,11 @@ pub struct TcpConnectionManager {
     reconnection_policy: Arc<dyn ReconnectionPolicy + Send + Sync>,
     frame_encoder_factory: Box<dyn FrameEncodingFactory + Send + Sync>,
     compression: Compression,
+    compression_threshold: usize,
     buffer_size: usize,
     tcp_nodelay: bool,
-    version: Version,
+    max_in_flight_requests: usize,
+    version_holder: Arc<VersionHolder>,
 }
 
 impl ConnectionManager<TransportTcp> for TcpConnectionManager {
@@ -62,9 +67,11 @@ impl TcpConnectionManager {
         reconnection_policy: Arc<dyn ReconnectionPolicy + Send + Sync>,
         frame_encoder_factory: Box<dyn FrameEncodingFactory + Send + Sync>,
         compression: Compression,
+        compression_threshold: usize,
         buffer_size: usize,
         tcp_nodelay: bool,
-        version: Version,
+        max_in_flight_requests: usize,
+        version_holder: Arc<VersionHolder>,
     ) -> Self {
         Self {
             authenticator_provider,
@@ -72,42 +79,92 @@ impl TcpConnectionManager {
             reconnection_policy,
             frame_encoder_factory,
             compression,
+            compression_threshold,
             buffer_size,
             tcp_nodelay,
-            version,
+            max_in_flight_requests,
+            version_holder,
         }
     }
 
-    async fn establish_connection(
+    async fn create_transport(
         &self,
         event_handler: Option<Sender<Envelope>>,
         error_handler: Option<Sender<Error>>,
         addr: SocketAddr,
+        compression: Compression,
+        version: Version,
     ) -> Result<TransportTcp> {
-        let transport = TransportTcp::new(
+        Ok(TransportTcp::new(
             addr,
             self.keyspace_holder.clone(),
             event_handler,
             error_handler,
-            self.compression,
+            compression,
+            self.compression_threshold,
             self.frame_encoder_factory
-                .create_encoder(self.version, self.compression),
+                .create_encoder(version, compression),
             self.frame_encoder_factory
-                .create_decoder(self.version, self.compression),
+                .create_decoder(version, compression),
             self.buffer_size,
             self.tcp_nodelay,
+            self.max_in_flight_requests,
         )
-        .await?;
+        .await?)
+    }
+
+    async fn establish_connection(
+        &self,
+        event_handler: Option<Sender<Envelope>>,
+        error_handler: Option<Sender<Error>>,
+        addr: SocketAddr,
+    ) -> Result<TransportTcp> {
+        connect_with_version_fallback(&self.version_holder, addr, |version| {
+            self.establish_connection_with_version(
+                event_handler.clone(),
+                error_handler.clone(),
+                addr,
+                version,
+            )
+        })
+        .await
+    }
+
+    async fn establish_connection_with_version(
+        &self,
+        event_handler: Option<Sender<Envelope>>,
+        error_handler: Option<Sender<Error>>,
+        addr: SocketAddr,
+        version: Version,
+    ) -> Result<TransportTcp> {
+        let mut transport = self
+            .create_transport(
+                event_handler.clone(),
+                error_handler.clone(),
+                addr,
+                self.compression,
+                version,
+            )
+            .await?;
+
+        let compression = negotiate_compression(&transport, self.compression, version).await?;
+        if compression != self.compression {
+            transport = self
+                .create_transport(event_handler, error_handler, addr, compression, version)
+                .await?;
+        }
 
         startup(
             &transport,
             self.authenticator_provider.deref(),
             self.keyspace_holder.deref(),
-            self.compression,
-            self.version,
+            compression,
+            version,
         )
         .await?;
 
+        debug!(%addr, %compression, %version, "Connection established.");
+
         Ok(transport)
     }
 }
@@ -1,4 +1,4 @@
-use cassandra_protocol::token::Murmur3Token;
+use cassandra_protocol::token::{Partitioner, Token};
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Formatter};
 use std::net::SocketAddr;
@@ -10,13 +10,15 @@ use crate::transport::CdrsTransport;
 
 /// Map of tokens to nodes.
 pub struct TokenMap<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static> {
-    token_ring: BTreeMap<Murmur3Token, Arc<Node<T, CM>>>,
+    token_ring: BTreeMap<Token, Arc<Node<T, CM>>>,
+    partitioner: Partitioner,
 }
 
 impl<T: CdrsTransport, CM: ConnectionManager<T>> Clone for TokenMap<T, CM> {
     fn clone(&self) -> Self {
         TokenMap {
             token_ring: self.token_ring.clone(),
+            partitioner: self.partitioner,
         }
     }
 }
@@ -25,6 +27,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Debug for TokenMap<T, CM> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TokenMap")
             .field("token_ring", &self.token_ring)
+            .field("partitioner", &self.partitioner)
             .finish()
     }
 }
@@ -33,28 +36,37 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Default for TokenMap<T, CM> {
     fn default() -> Self {
         TokenMap {
             token_ring: Default::default(),
+            partitioner: Default::default(),
         }
     }
 }
 
 impl<T: CdrsTransport, CM: ConnectionManager<T>> TokenMap<T, CM> {
-    pub fn new(nodes: &NodeMap<T, CM>) -> Self {
+    /// Creates a map of tokens owned by given nodes, which were created by given partitioner.
+    pub fn new(nodes: &NodeMap<T, CM>, partitioner: Partitioner) -> Self {
         TokenMap {
             token_ring: nodes
                 .iter()
                 .flat_map(|(_, node)| {
                     node.tokens()
                         .iter()
-                        .map(move |token| (*token, node.clone()))
+                        .map(move |token| (token.clone(), node.clone()))
                 })
                 .collect(),
+            partitioner,
         }
     }
 
+    /// Returns the partitioner used by the cluster, as reported by `system.local`.
+    #[inline]
+    pub fn partitioner(&self) -> Partitioner {
+        self.partitioner
+    }
+
     /// Returns local nodes starting at given token and going in the direction of replicas.
     pub fn nodes_for_token_capped(
         &self,
-        token: Murmur3Token,
+        token: Token,
         replica_count: usize,
     ) -> impl Iterator<Item = Arc<Node<T, CM>>> + '_ {
         self.token_ring
@@ -65,10 +77,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> TokenMap<T, CM> {
     }
 
     /// Returns local nodes starting at given token and going in the direction of replicas.
-    pub fn nodes_for_token(
-        &self,
-        token: Murmur3Token,
-    ) -> impl Iterator<Item = Arc<Node<T, CM>>> + '_ {
+    pub fn nodes_for_token(&self, token: Token) -> impl Iterator<Item = Arc<Node<T, CM>>> + '_ {
         self.token_ring
             .range(token..)
             .chain(self.token_ring.iter())
@@ -81,7 +90,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> TokenMap<T, CM> {
     pub fn clone_with_node(&self, node: Arc<Node<T, CM>>) -> Self {
         let mut map = self.clone();
         for token in node.tokens() {
-            map.token_ring.insert(*token, node.clone());
+            map.token_ring.insert(token.clone(), node.clone());
         }
 
         map
@@ -97,19 +106,22 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> TokenMap<T, CM> {
                 if node.broadcast_rpc_address() == broadcast_rpc_address {
                     None
                 } else {
-                    Some((*token, node.clone()))
+                    Some((token.clone(), node.clone()))
                 }
             })
             .collect();
 
-        TokenMap { token_ring }
+        TokenMap {
+            token_ring,
+            partitioner: self.partitioner,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use cassandra_protocol::frame::Version;
-    use cassandra_protocol::token::Murmur3Token;
+    use cassandra_protocol::token::{Murmur3Token, Partitioner, Token};
     use itertools::Itertools;
     use lazy_static::lazy_static;
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
@@ -120,7 +132,8 @@ mod tests {
     use crate::cluster::connection_manager::MockConnectionManager;
     use crate::cluster::connection_pool::ConnectionPoolFactory;
     use crate::cluster::topology::{Node, NodeMap};
-    use crate::cluster::TokenMap;
+    use crate::cluster::{TokenMap, VersionHolder};
+    use crate::metrics::NoopMetrics;
     use crate::transport::MockCdrsTransport;
 
     lazy_static! {
@@ -134,9 +147,10 @@ mod tests {
         let connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
         let connection_pool_factory = Arc::new(ConnectionPoolFactory::new(
             Default::default(),
-            Version::V4,
+            Arc::new(VersionHolder::new(Version::V4)),
             connection_manager,
             keyspace_receiver,
+            Arc::new(NoopMetrics),
         ));
 
         let mut nodes = NodeMap::default();
@@ -149,9 +163,9 @@ mod tests {
                 Some(*HOST_ID_1),
                 None,
                 vec![
-                    Murmur3Token::new(-2),
-                    Murmur3Token::new(-1),
-                    Murmur3Token::new(0),
+                    Murmur3Token::new(-2).into(),
+                    Murmur3Token::new(-1).into(),
+                    Murmur3Token::new(0).into(),
                 ],
                 "".into(),
                 "".into(),
@@ -165,7 +179,7 @@ mod tests {
                 None,
                 Some(*HOST_ID_2),
                 None,
-                vec![Murmur3Token::new(20)],
+                vec![Murmur3Token::new(20).into()],
                 "".into(),
                 "".into(),
             )),
@@ -179,9 +193,9 @@ mod tests {
                 Some(*HOST_ID_3),
                 None,
                 vec![
-                    Murmur3Token::new(2),
-                    Murmur3Token::new(1),
-                    Murmur3Token::new(10),
+                    Murmur3Token::new(2).into(),
+                    Murmur3Token::new(1).into(),
+                    Murmur3Token::new(10).into(),
                 ],
                 "".into(),
                 "".into(),
@@ -191,8 +205,8 @@ mod tests {
         nodes
     }
 
-    fn verify_tokens(host_ids: &[Uuid], token: Murmur3Token) {
-        let token_map = TokenMap::new(&prepare_nodes());
+    fn verify_tokens(host_ids: &[Uuid], token: Token) {
+        let token_map = TokenMap::new(&prepare_nodes(), Partitioner::Murmur3);
         let nodes = token_map
             .nodes_for_token_capped(token, host_ids.len())
             .collect_vec();
@@ -207,20 +221,33 @@ mod tests {
     fn should_return_replicas_in_order() {
         verify_tokens(
             &[*HOST_ID_1, *HOST_ID_3, *HOST_ID_3, *HOST_ID_3, *HOST_ID_2],
-            Murmur3Token::new(0),
+            Murmur3Token::new(0).into(),
         );
     }
 
     #[test]
     fn should_return_replicas_in_order_for_non_primary_token() {
-        verify_tokens(&[*HOST_ID_3, *HOST_ID_2], Murmur3Token::new(3));
+        verify_tokens(&[*HOST_ID_3, *HOST_ID_2], Murmur3Token::new(3).into());
     }
 
     #[test]
     fn should_return_replicas_in_a_ring() {
         verify_tokens(
             &[*HOST_ID_2, *HOST_ID_1, *HOST_ID_1, *HOST_ID_1, *HOST_ID_3],
-            Murmur3Token::new(20),
+            Murmur3Token::new(20).into(),
+        );
+    }
+
+    #[test]
+    fn should_return_given_partitioner() {
+        assert_eq!(
+            TokenMap::new(&prepare_nodes(), Partitioner::Random).partitioner(),
+            Partitioner::Random
+        );
+        assert_eq!(
+            TokenMap::<MockCdrsTransport, MockConnectionManager<MockCdrsTransport>>::default()
+                .partitioner(),
+            Partitioner::Murmur3
         );
     }
 }
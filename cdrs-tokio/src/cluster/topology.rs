@@ -8,6 +8,7 @@ mod datacenter_metadata;
 mod keyspace_metadata;
 mod node;
 mod node_distance;
+mod node_latency;
 mod node_state;
 mod replication_strategy;
 
@@ -15,6 +16,7 @@ pub use self::datacenter_metadata::DatacenterMetadata;
 pub use self::keyspace_metadata::KeyspaceMetadata;
 pub use self::node::Node;
 pub use self::node_distance::NodeDistance;
+pub use self::node_latency::NodeLatency;
 pub use self::node_state::NodeState;
 pub use self::replication_strategy::ReplicationStrategy;
 
@@ -1,3 +1,4 @@
+use cassandra_protocol::token::Partitioner;
 use fxhash::FxHashMap;
 use itertools::Itertools;
 use std::net::SocketAddr;
@@ -39,8 +40,12 @@ pub struct ClusterMetadata<T: CdrsTransport + 'static, CM: ConnectionManager<T>
 }
 
 impl<T: CdrsTransport, CM: ConnectionManager<T>> ClusterMetadata<T, CM> {
-    pub fn new(nodes: NodeMap<T, CM>, keyspaces: FxHashMap<String, KeyspaceMetadata>) -> Self {
-        let token_map = TokenMap::new(&nodes);
+    pub fn new(
+        nodes: NodeMap<T, CM>,
+        keyspaces: FxHashMap<String, KeyspaceMetadata>,
+        partitioner: Partitioner,
+    ) -> Self {
+        let token_map = TokenMap::new(&nodes, partitioner);
         let datacenters = build_datacenter_info(&nodes);
         ClusterMetadata {
             nodes,
@@ -121,7 +126,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> ClusterMetadata<T, CM> {
             })
             .collect();
 
-        Self::new(nodes, self.keyspaces.clone())
+        Self::new(nodes, self.keyspaces.clone(), self.token_map.partitioner())
     }
 
     /// Returns all known nodes.
@@ -258,6 +263,8 @@ mod tests {
     use crate::cluster::connection_pool::ConnectionPoolFactory;
     use crate::cluster::topology::cluster_metadata::build_datacenter_info;
     use crate::cluster::topology::Node;
+    use crate::cluster::VersionHolder;
+    use crate::metrics::NoopMetrics;
     use crate::transport::MockCdrsTransport;
 
     #[test]
@@ -266,9 +273,10 @@ mod tests {
         let connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
         let connection_pool_factory = Arc::new(ConnectionPoolFactory::new(
             Default::default(),
-            Version::V4,
+            Arc::new(VersionHolder::new(Version::V4)),
             connection_manager,
             keyspace_receiver,
+            Arc::new(NoopMetrics),
         ));
 
         let mut nodes = FxHashMap::default();
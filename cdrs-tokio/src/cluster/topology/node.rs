@@ -1,19 +1,22 @@
 use atomic::Atomic;
 use cassandra_protocol::error::{Error, Result};
 use cassandra_protocol::frame::Envelope;
-use cassandra_protocol::token::Murmur3Token;
+use cassandra_protocol::token::Token;
 use std::fmt::{Debug, Formatter};
 use std::net::SocketAddr;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::OnceCell;
 use tracing::*;
 use uuid::Uuid;
 
-use crate::cluster::connection_pool::{ConnectionPool, ConnectionPoolFactory};
-use crate::cluster::topology::{NodeDistance, NodeState};
+use crate::cluster::connection_pool::{ConnectionPool, ConnectionPoolFactory, PooledConnection};
+use crate::cluster::topology::node_latency::LatencyTracker;
+use crate::cluster::topology::{NodeDistance, NodeLatency, NodeState};
 use crate::cluster::{ConnectionManager, NodeInfo};
+use crate::metrics::Metrics;
 use crate::transport::CdrsTransport;
 
 /// Metadata about a Cassandra node in the cluster, along with a connection.
@@ -25,9 +28,10 @@ pub struct Node<T: CdrsTransport + 'static, CM: ConnectionManager<T> + 'static>
     distance: Option<NodeDistance>,
     state: Atomic<NodeState>,
     host_id: Option<Uuid>,
-    tokens: Vec<Murmur3Token>,
+    tokens: Vec<Token>,
     rack: String,
     datacenter: String,
+    latency: Arc<LatencyTracker>,
 }
 
 impl<T: CdrsTransport, CM: ConnectionManager<T>> Debug for Node<T, CM> {
@@ -54,7 +58,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
         broadcast_address: Option<SocketAddr>,
         host_id: Option<Uuid>,
         distance: Option<NodeDistance>,
-        tokens: Vec<Murmur3Token>,
+        tokens: Vec<Token>,
         rack: String,
         datacenter: String,
     ) -> Self {
@@ -69,6 +73,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
             tokens,
             rack,
             datacenter,
+            latency: Default::default(),
         }
     }
 
@@ -81,7 +86,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
         host_id: Option<Uuid>,
         distance: Option<NodeDistance>,
         state: NodeState,
-        tokens: Vec<Murmur3Token>,
+        tokens: Vec<Token>,
         rack: String,
         datacenter: String,
     ) -> Self {
@@ -96,6 +101,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
             tokens,
             rack,
             datacenter,
+            latency: Default::default(),
         }
     }
 
@@ -107,7 +113,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
         broadcast_address: Option<SocketAddr>,
         host_id: Option<Uuid>,
         state: NodeState,
-        tokens: Vec<Murmur3Token>,
+        tokens: Vec<Token>,
         rack: String,
         datacenter: String,
     ) -> Self {
@@ -122,6 +128,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
             tokens,
             rack,
             datacenter,
+            latency: Default::default(),
         }
     }
 
@@ -144,6 +151,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
             tokens: Default::default(),
             rack: Default::default(),
             datacenter: Default::default(),
+            latency: Default::default(),
         }
     }
 
@@ -175,7 +183,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
 
     /// Returns tokens associated with the node.
     #[inline]
-    pub fn tokens(&self) -> &[Murmur3Token] {
+    pub fn tokens(&self) -> &[Token] {
         &self.tokens
     }
 
@@ -193,7 +201,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
 
     /// Returns a connection to given node.
     #[inline]
-    pub async fn persistent_connection(&self) -> Result<Arc<T>> {
+    pub async fn persistent_connection(&self) -> Result<PooledConnection<T>> {
         let pool = self
             .connection_pool
             .get_or_try_init(|| {
@@ -238,6 +246,22 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
         self.distance == Some(NodeDistance::Remote)
     }
 
+    /// Returns rolling latency of requests sent to this node, if any were sent.
+    #[inline]
+    pub fn latency(&self) -> Option<NodeLatency> {
+        self.latency.latency()
+    }
+
+    #[inline]
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        self.latency.record(latency);
+    }
+
+    #[inline]
+    pub(crate) fn metrics(&self) -> &dyn Metrics {
+        self.connection_pool_factory.metrics()
+    }
+
     /// Should this node be ignored from establishing connections.
     #[inline]
     pub fn is_ignored(&self) -> bool {
@@ -269,6 +293,11 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
             tokens: node_info.tokens,
             rack: node_info.rack,
             datacenter: node_info.datacenter,
+            latency: if address_changed {
+                Default::default()
+            } else {
+                self.latency.clone()
+            },
         }
     }
 
@@ -286,6 +315,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
             tokens: node_info.tokens,
             rack: node_info.rack,
             datacenter: node_info.datacenter,
+            latency: self.latency.clone(),
         }
     }
 
@@ -307,6 +337,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
             tokens: node_info.tokens,
             rack: node_info.rack,
             datacenter: node_info.datacenter,
+            latency: Default::default(),
         }
     }
 
@@ -323,6 +354,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> Node<T, CM> {
             tokens: self.tokens.clone(),
             rack: self.rack.clone(),
             datacenter: self.datacenter.clone(),
+            latency: self.latency.clone(),
         }
     }
 }
@@ -0,0 +1,71 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Weight of a new measurement in the moving average.
+const NEW_MEASUREMENT_WEIGHT: f64 = 0.25;
+
+/// Snapshot of request latency statistics of a node.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct NodeLatency {
+    /// Exponentially weighted moving average of request latency.
+    pub average: Duration,
+    /// Number of measurements made.
+    pub measurements: u64,
+    /// Time of the last measurement.
+    pub last_update: Instant,
+}
+
+/// Rolling request latency of a node. Shared between node metadata instances describing the same
+/// node, so the statistics survive topology updates.
+#[derive(Default, Debug)]
+pub(crate) struct LatencyTracker {
+    latency: Mutex<Option<NodeLatency>>,
+}
+
+impl LatencyTracker {
+    pub(crate) fn record(&self, latency: Duration) {
+        self.record_at(latency, Instant::now());
+    }
+
+    fn record_at(&self, latency: Duration, now: Instant) {
+        let mut current = self.latency.lock().unwrap();
+        *current = Some(match *current {
+            Some(current) => NodeLatency {
+                average: current.average.mul_f64(1.0 - NEW_MEASUREMENT_WEIGHT)
+                    + latency.mul_f64(NEW_MEASUREMENT_WEIGHT),
+                measurements: current.measurements.saturating_add(1),
+                last_update: now,
+            },
+            None => NodeLatency {
+                average: latency,
+                measurements: 1,
+                last_update: now,
+            },
+        });
+    }
+
+    #[inline]
+    pub(crate) fn latency(&self) -> Option<NodeLatency> {
+        *self.latency.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_average_latency() {
+        let tracker = LatencyTracker::default();
+        assert_eq!(tracker.latency(), None);
+
+        let now = Instant::now();
+        tracker.record_at(Duration::from_millis(100), now);
+        tracker.record_at(Duration::from_millis(200), now);
+
+        let latency = tracker.latency().unwrap();
+        assert_eq!(latency.average, Duration::from_millis(125));
+        assert_eq!(latency.measurements, 2);
+        assert_eq!(latency.last_update, now);
+    }
+}
@@ -0,0 +1,53 @@
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use cassandra_protocol::frame::Version;
+
+/// Holds the protocol version used by a session. Starts with the configured version, which can be
+/// lowered by connection managers when servers don't support it.
+#[derive(Debug)]
+pub struct VersionHolder {
+    version: AtomicU8,
+}
+
+impl VersionHolder {
+    pub fn new(version: Version) -> Self {
+        VersionHolder {
+            version: AtomicU8::new(version.into()),
+        }
+    }
+
+    #[inline]
+    pub fn current(&self) -> Version {
+        // only valid versions are ever stored
+        Version::try_from(self.version.load(Ordering::Relaxed)).unwrap()
+    }
+
+    /// Changes the version to `to`, if it's still `from`. Returns `false` if the version has
+    /// already been changed in the meantime.
+    #[inline]
+    pub fn downgrade(&self, from: Version, to: Version) -> bool {
+        self.version
+            .compare_exchange(from.into(), to.into(), Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cassandra_protocol::frame::Version;
+
+    use crate::cluster::VersionHolder;
+
+    #[test]
+    fn should_downgrade_only_from_current_version() {
+        let holder = VersionHolder::new(Version::V5);
+        assert_eq!(holder.current(), Version::V5);
+
+        assert!(holder.downgrade(Version::V5, Version::V4));
+        assert_eq!(holder.current(), Version::V4);
+
+        assert!(!holder.downgrade(Version::V5, Version::V3));
+        assert_eq!(holder.current(), Version::V4);
+    }
+}
@@ -7,11 +7,13 @@ use cassandra_protocol::compression::Compression;
 use cassandra_protocol::error;
 use cassandra_protocol::frame::message_response::ResponseBody;
 use cassandra_protocol::frame::{
-    Direction, Envelope, Flags, Opcode, Version, LENGTH_LEN, STREAM_LEN,
+    checked_envelope_body_len, Direction, Envelope, Flags, Opcode, Version, LENGTH_LEN,
+    MAX_ENVELOPE_BODY_LEN, STREAM_LEN,
 };
 use cassandra_protocol::types::data_serialization_types::decode_timeuuid;
 use cassandra_protocol::types::{
-    from_cursor_string_list, try_i16_from_bytes, try_i32_from_bytes, UUID_LEN,
+    from_cursor_bytes_map, from_cursor_string_list, try_i16_from_bytes, try_i32_from_bytes,
+    UUID_LEN,
 };
 
 async fn parse_raw_envelope<T: AsyncReadExt + Unpin>(
@@ -36,21 +38,24 @@ async fn parse_raw_envelope<T: AsyncReadExt + Unpin>(
     let flags = Flags::from_bits_truncate(flag_bytes[0]);
     let stream_id = try_i16_from_bytes(&stream_bytes)?;
     let opcode = Opcode::try_from(opcode_bytes[0])?;
-    let length = try_i32_from_bytes(&length_bytes)? as usize;
+    let length = try_i32_from_bytes(&length_bytes)?;
+    let length =
+        checked_envelope_body_len(length).ok_or(error::Error::InvalidEnvelopeBodyLength {
+            length,
+            max: MAX_ENVELOPE_BODY_LEN,
+        })?;
 
     let mut body_bytes = vec![0; length];
 
     cursor.read_exact(&mut body_bytes).await?;
 
-    let full_body = if flags.contains(Flags::COMPRESSION) {
+    let mut full_body = if flags.contains(Flags::COMPRESSION) {
         compressor.decode(body_bytes)?
     } else {
-        Compression::None.decode(body_bytes)?
+        body_bytes
     };
 
-    let body_len = full_body.len();
-
-    // Use cursor to get tracing id, warnings and actual body
+    // Use cursor to get tracing id, warnings, custom payload and actual body
     let mut body_cursor = Cursor::new(full_body.as_slice());
 
     let tracing_id = if flags.contains(Flags::TRACING) {
@@ -68,9 +73,15 @@ async fn parse_raw_envelope<T: AsyncReadExt + Unpin>(
         vec![]
     };
 
-    let mut body = Vec::with_capacity(body_len - body_cursor.position() as usize);
+    let custom_payload = if flags.contains(Flags::CUSTOM_PAYLOAD) {
+        from_cursor_bytes_map(&mut body_cursor, version)?
+    } else {
+        Default::default()
+    };
 
-    std::io::Read::read_to_end(&mut body_cursor, &mut body)?;
+    // reuse the buffer for the actual body, instead of copying it
+    let header_len = body_cursor.position() as usize;
+    full_body.drain(..header_len);
 
     let envelope = Envelope {
         version,
@@ -78,9 +89,10 @@ async fn parse_raw_envelope<T: AsyncReadExt + Unpin>(
         flags,
         opcode,
         stream_id,
-        body,
+        body: full_body,
         tracing_id,
         warnings,
+        custom_payload,
     };
 
     Ok(envelope)
@@ -107,3 +119,57 @@ pub(crate) fn convert_envelope_into_result(
         _ => Ok(envelope),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_parse_body_after_warnings() {
+        for compression in [Compression::None, Compression::Lz4] {
+            let envelope = Envelope::new(
+                Version::V4,
+                Direction::Response,
+                Flags::WARNING,
+                Opcode::Ready,
+                1,
+                vec![0, 1, 0, 2, b'a', b'b', 1, 2, 3],
+                None,
+                vec![],
+            );
+
+            let data = envelope.encode_with(compression).unwrap();
+            let parsed = parse_raw_envelope(&mut data.as_slice(), compression)
+                .await
+                .unwrap();
+
+            assert_eq!(parsed.warnings, vec!["ab".to_string()]);
+            assert_eq!(parsed.body, vec![1, 2, 3]);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_parse_body_after_custom_payload() {
+        let envelope = Envelope::new(
+            Version::V4,
+            Direction::Response,
+            Flags::WARNING | Flags::CUSTOM_PAYLOAD,
+            Opcode::Ready,
+            1,
+            vec![
+                0, 1, 0, 2, b'a', b'b', 0, 1, 0, 1, b'k', 0, 0, 0, 2, 4, 5, 1, 2, 3,
+            ],
+            None,
+            vec![],
+        );
+
+        let data = envelope.encode_with(Compression::None).unwrap();
+        let parsed = parse_raw_envelope(&mut data.as_slice(), Compression::None)
+            .await
+            .unwrap();
+
+        assert_eq!(parsed.warnings, vec!["ab".to_string()]);
+        assert_eq!(parsed.custom_payload().get("k"), Some(&vec![4, 5]));
+        assert_eq!(parsed.body, vec![1, 2, 3]);
+    }
+}
@@ -48,6 +48,7 @@ pub mod load_balancing;
 
 pub mod frame_encoding;
 pub mod future;
+pub mod metrics;
 pub mod retry;
 pub mod speculative_execution;
 pub mod statement;
@@ -1,16 +1,24 @@
+mod dc_aware_round_robin;
 mod initializing_wrapper;
+mod latency_aware;
 pub mod node_distance_evaluator;
 mod random;
 mod request;
 mod round_robin;
+#[cfg(test)]
+mod test_utils;
+mod token_aware;
 mod topology_aware;
 
 use std::sync::Arc;
 
+pub use self::dc_aware_round_robin::DcAwareRoundRobinLoadBalancingStrategy;
 pub(crate) use self::initializing_wrapper::InitializingWrapperLoadBalancingStrategy;
+pub use self::latency_aware::LatencyAwareLoadBalancingStrategy;
 pub use self::random::RandomLoadBalancingStrategy;
 pub use self::request::Request;
 pub use self::round_robin::RoundRobinLoadBalancingStrategy;
+pub use self::token_aware::TokenAwareLoadBalancingStrategy;
 pub use self::topology_aware::TopologyAwareLoadBalancingStrategy;
 use crate::cluster::topology::Node;
 use crate::cluster::{ClusterMetadata, ConnectionManager};
@@ -0,0 +1,164 @@
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cluster::{ClusterMetadata, ConnectionManager};
+use crate::load_balancing::{LoadBalancingStrategy, QueryPlan, Request};
+use crate::transport::CdrsTransport;
+use fxhash::FxHashMap;
+
+/// Datacenter-aware round-robin load balancing. Unignored nodes in the local datacenter are
+/// returned in a round-robin fashion, followed by at most `max_nodes_per_remote_dc` unignored
+/// nodes from each remote datacenter. Remote nodes are never used for requests with a dc-local
+/// consistency, e.g. `LocalQuorum`.
+///
+/// Note: nodes are assigned to datacenters by name, independently of the used
+/// [`NodeDistanceEvaluator`](crate::load_balancing::node_distance_evaluator::NodeDistanceEvaluator).
+pub struct DcAwareRoundRobinLoadBalancingStrategy<T: CdrsTransport, CM: ConnectionManager<T>> {
+    local_dc: String,
+    max_nodes_per_remote_dc: usize,
+    prev_idx: AtomicUsize,
+    _transport: PhantomData<T>,
+    _connection_manager: PhantomData<CM>,
+}
+
+impl<T: CdrsTransport, CM: ConnectionManager<T>> DcAwareRoundRobinLoadBalancingStrategy<T, CM> {
+    /// Creates new strategy for given local datacenter name. Use `0` for
+    /// `max_nodes_per_remote_dc` to only use local nodes.
+    pub fn new(local_dc: String, max_nodes_per_remote_dc: usize) -> Self {
+        DcAwareRoundRobinLoadBalancingStrategy {
+            local_dc,
+            max_nodes_per_remote_dc,
+            prev_idx: AtomicUsize::new(0),
+            _transport: Default::default(),
+            _connection_manager: Default::default(),
+        }
+    }
+
+    /// Returns the local datacenter name.
+    #[inline]
+    pub fn local_dc(&self) -> &str {
+        &self.local_dc
+    }
+}
+
+impl<T: CdrsTransport, CM: ConnectionManager<T>> LoadBalancingStrategy<T, CM>
+    for DcAwareRoundRobinLoadBalancingStrategy<T, CM>
+{
+    fn query_plan(
+        &self,
+        request: Option<Request>,
+        cluster: &ClusterMetadata<T, CM>,
+    ) -> QueryPlan<T, CM> {
+        let (mut local_nodes, mut remote_nodes): (Vec<_>, Vec<_>) = cluster
+            .unignored_nodes()
+            .into_iter()
+            .partition(|node| node.datacenter() == self.local_dc);
+
+        let cur_idx = self.prev_idx.fetch_add(1, Ordering::SeqCst);
+        if !local_nodes.is_empty() {
+            let len = local_nodes.len();
+            local_nodes.rotate_left(cur_idx % len);
+        }
+
+        let local_only = request
+            .and_then(|request| request.consistency)
+            .map(|consistency| consistency.is_dc_local())
+            .unwrap_or(false);
+
+        if local_only || self.max_nodes_per_remote_dc == 0 || remote_nodes.is_empty() {
+            return local_nodes;
+        }
+
+        let len = remote_nodes.len();
+        remote_nodes.rotate_left(cur_idx % len);
+
+        let mut remote_dc_counts: FxHashMap<String, usize> = Default::default();
+        remote_nodes.retain(|node| {
+            let count = remote_dc_counts
+                .entry(node.datacenter().to_string())
+                .or_default();
+
+            *count += 1;
+            *count <= self.max_nodes_per_remote_dc
+        });
+
+        local_nodes.append(&mut remote_nodes);
+        local_nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cassandra_protocol::consistency::Consistency;
+
+    use crate::load_balancing::test_utils::{create_cluster, MockClusterMetadata, TestNode};
+    use crate::load_balancing::{
+        DcAwareRoundRobinLoadBalancingStrategy, LoadBalancingStrategy, Request,
+    };
+
+    fn create_multi_dc_cluster() -> MockClusterMetadata {
+        create_cluster(
+            [(1, "dc1"), (2, "dc1"), (3, "dc2"), (4, "dc2"), (5, "dc3")]
+                .iter()
+                .map(|&(port, datacenter)| TestNode::new(port, datacenter)),
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn should_put_local_nodes_first() {
+        let cluster = create_multi_dc_cluster();
+        let lb = DcAwareRoundRobinLoadBalancingStrategy::new("dc1".into(), 1);
+
+        let query_plan = lb.query_plan(None, &cluster);
+        assert_eq!(query_plan.len(), 4);
+        assert!(query_plan[..2]
+            .iter()
+            .all(|node| node.datacenter() == "dc1"));
+
+        let mut remote_dcs: Vec<_> = query_plan[2..]
+            .iter()
+            .map(|node| node.datacenter())
+            .collect();
+        remote_dcs.sort_unstable();
+        assert_eq!(remote_dcs, vec!["dc2", "dc3"]);
+    }
+
+    #[test]
+    fn should_rotate_local_nodes() {
+        let cluster = create_multi_dc_cluster();
+        let lb = DcAwareRoundRobinLoadBalancingStrategy::new("dc1".into(), 0);
+
+        let first = lb.query_plan(None, &cluster);
+        let second = lb.query_plan(None, &cluster);
+        assert_eq!(first.len(), 2);
+        assert_eq!(
+            first[0].broadcast_rpc_address(),
+            second[1].broadcast_rpc_address()
+        );
+    }
+
+    #[test]
+    fn should_not_use_remote_nodes_for_local_consistency() {
+        let cluster = create_multi_dc_cluster();
+        let lb = DcAwareRoundRobinLoadBalancingStrategy::new("dc1".into(), 2);
+
+        let query_plan = lb.query_plan(
+            Some(Request::new(
+                None,
+                None,
+                None,
+                Some(Consistency::LocalQuorum),
+            )),
+            &cluster,
+        );
+        assert_eq!(query_plan.len(), 2);
+        assert!(query_plan.iter().all(|node| node.datacenter() == "dc1"));
+
+        let query_plan = lb.query_plan(
+            Some(Request::new(None, None, None, Some(Consistency::Quorum))),
+            &cluster,
+        );
+        assert_eq!(query_plan.len(), 5);
+    }
+}
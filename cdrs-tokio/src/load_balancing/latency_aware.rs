@@ -0,0 +1,188 @@
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use crate::cluster::topology::{Node, NodeLatency};
+use crate::cluster::{ClusterMetadata, ConnectionManager};
+use crate::load_balancing::{LoadBalancingStrategy, QueryPlan, Request};
+use crate::transport::CdrsTransport;
+
+/// Latency-aware load balancing strategy wrapping a child strategy. Nodes returned by the child
+/// strategy, whose average latency exceeds `exclusion_threshold` times the latency of the fastest
+/// node, are moved to the end of the query plan. A slow node is penalized until no new
+/// measurements are made for `retry_period`, after which it's given another chance. Only nodes
+/// with at least `min_measurements` latency measurements are taken into account.
+///
+/// Latency is measured by sending requests to nodes, so this strategy relies on nodes being
+/// queried regularly.
+pub struct LatencyAwareLoadBalancingStrategy<
+    T: CdrsTransport,
+    CM: ConnectionManager<T>,
+    LB: LoadBalancingStrategy<T, CM>,
+> {
+    child: LB,
+    exclusion_threshold: f64,
+    retry_period: Duration,
+    min_measurements: u64,
+    _transport: PhantomData<T>,
+    _connection_manager: PhantomData<CM>,
+}
+
+impl<T: CdrsTransport, CM: ConnectionManager<T>, LB: LoadBalancingStrategy<T, CM>>
+    LatencyAwareLoadBalancingStrategy<T, CM, LB>
+{
+    pub fn new(
+        child: LB,
+        exclusion_threshold: f64,
+        retry_period: Duration,
+        min_measurements: u64,
+    ) -> Self {
+        LatencyAwareLoadBalancingStrategy {
+            child,
+            exclusion_threshold,
+            retry_period,
+            min_measurements,
+            _transport: Default::default(),
+            _connection_manager: Default::default(),
+        }
+    }
+
+    fn usable_latency(&self, node: &Node<T, CM>, now: Instant) -> Option<NodeLatency> {
+        node.latency().filter(|latency| {
+            latency.measurements >= self.min_measurements
+                && now.saturating_duration_since(latency.last_update) < self.retry_period
+        })
+    }
+}
+
+impl<T: CdrsTransport, CM: ConnectionManager<T>, LB: LoadBalancingStrategy<T, CM>>
+    LoadBalancingStrategy<T, CM> for LatencyAwareLoadBalancingStrategy<T, CM, LB>
+{
+    fn query_plan(
+        &self,
+        request: Option<Request>,
+        cluster: &ClusterMetadata<T, CM>,
+    ) -> QueryPlan<T, CM> {
+        let query_plan = self.child.query_plan(request, cluster);
+
+        let now = Instant::now();
+        let min_latency = query_plan
+            .iter()
+            .filter_map(|node| self.usable_latency(node, now))
+            .map(|latency| latency.average)
+            .min();
+
+        let max_latency = match min_latency {
+            Some(min_latency) => min_latency.mul_f64(self.exclusion_threshold),
+            None => return query_plan,
+        };
+
+        let (fast, mut slow): (Vec<_>, Vec<_>) = query_plan.into_iter().partition(|node| {
+            self.usable_latency(node, now)
+                .map(|latency| latency.average <= max_latency)
+                .unwrap_or(true)
+        });
+
+        let mut query_plan = fast;
+        query_plan.append(&mut slow);
+        query_plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::load_balancing::test_utils::{
+        create_cluster, ports, MockClusterMetadata, MockConnectionManagerType, TestNode,
+    };
+    use crate::load_balancing::{
+        LatencyAwareLoadBalancingStrategy, LoadBalancingStrategy, QueryPlan, Request,
+    };
+    use crate::transport::MockCdrsTransport;
+
+    /// Returns nodes ordered by port.
+    struct OrderedLoadBalancingStrategy;
+
+    impl LoadBalancingStrategy<MockCdrsTransport, MockConnectionManagerType>
+        for OrderedLoadBalancingStrategy
+    {
+        fn query_plan(
+            &self,
+            _request: Option<Request>,
+            cluster: &MockClusterMetadata,
+        ) -> QueryPlan<MockCdrsTransport, MockConnectionManagerType> {
+            let mut nodes = cluster.unignored_nodes();
+            nodes.sort_unstable_by_key(|node| node.broadcast_rpc_address().port());
+            nodes
+        }
+    }
+
+    fn create_single_dc_cluster() -> MockClusterMetadata {
+        create_cluster(
+            (1..=3).map(|port| TestNode::new(port, "dc1")),
+            Default::default(),
+        )
+    }
+
+    fn record_latency(cluster: &MockClusterMetadata, port: u16, latency: Duration) {
+        cluster
+            .nodes()
+            .values()
+            .find(|node| node.broadcast_rpc_address().port() == port)
+            .unwrap()
+            .record_latency(latency);
+    }
+
+    #[test]
+    fn should_keep_child_order_without_measurements() {
+        let cluster = create_single_dc_cluster();
+        let lb = LatencyAwareLoadBalancingStrategy::new(
+            OrderedLoadBalancingStrategy,
+            2.0,
+            Duration::from_secs(10),
+            1,
+        );
+
+        assert_eq!(ports(&lb.query_plan(None, &cluster)), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn should_deprioritize_slow_nodes() {
+        let cluster = create_single_dc_cluster();
+        let lb = LatencyAwareLoadBalancingStrategy::new(
+            OrderedLoadBalancingStrategy,
+            2.0,
+            Duration::from_secs(10),
+            1,
+        );
+
+        record_latency(&cluster, 1, Duration::from_millis(100));
+        record_latency(&cluster, 2, Duration::from_millis(10));
+        record_latency(&cluster, 3, Duration::from_millis(15));
+
+        assert_eq!(ports(&lb.query_plan(None, &cluster)), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn should_ignore_nodes_with_few_or_stale_measurements() {
+        let cluster = create_single_dc_cluster();
+        record_latency(&cluster, 1, Duration::from_millis(100));
+        record_latency(&cluster, 2, Duration::from_millis(10));
+
+        let lb = LatencyAwareLoadBalancingStrategy::new(
+            OrderedLoadBalancingStrategy,
+            2.0,
+            Duration::from_secs(10),
+            2,
+        );
+        assert_eq!(ports(&lb.query_plan(None, &cluster)), vec![1, 2, 3]);
+
+        let lb = LatencyAwareLoadBalancingStrategy::new(
+            OrderedLoadBalancingStrategy,
+            2.0,
+            Duration::ZERO,
+            1,
+        );
+        assert_eq!(ports(&lb.query_plan(None, &cluster)), vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,82 @@
+use cassandra_protocol::frame::Version;
+use cassandra_protocol::token::Murmur3Token;
+use fxhash::FxHashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::cluster::connection_manager::MockConnectionManager;
+use crate::cluster::connection_pool::ConnectionPoolFactory;
+use crate::cluster::topology::{KeyspaceMetadata, Node, NodeDistance, NodeState};
+use crate::cluster::{ClusterMetadata, VersionHolder};
+use crate::metrics::NoopMetrics;
+use crate::transport::MockCdrsTransport;
+
+pub(crate) type MockConnectionManagerType = MockConnectionManager<MockCdrsTransport>;
+pub(crate) type MockNode = Node<MockCdrsTransport, MockConnectionManagerType>;
+pub(crate) type MockClusterMetadata = ClusterMetadata<MockCdrsTransport, MockConnectionManagerType>;
+
+/// Test node description: broadcast rpc port, single token and datacenter.
+pub(crate) struct TestNode {
+    pub port: u16,
+    pub token: i64,
+    pub datacenter: &'static str,
+}
+
+impl TestNode {
+    /// Creates a node in given datacenter owning a token equal to its port.
+    pub(crate) fn new(port: u16, datacenter: &'static str) -> Self {
+        TestNode {
+            port,
+            token: port as i64,
+            datacenter,
+        }
+    }
+}
+
+/// Creates cluster metadata with given up, local nodes, all in rack `r1` at `127.0.0.2`.
+pub(crate) fn create_cluster(
+    nodes: impl IntoIterator<Item = TestNode>,
+    keyspaces: FxHashMap<String, KeyspaceMetadata>,
+) -> MockClusterMetadata {
+    let (_, keyspace_receiver) = watch::channel(None);
+    let connection_pool_factory = Arc::new(ConnectionPoolFactory::new(
+        Default::default(),
+        Arc::new(VersionHolder::new(Version::V4)),
+        MockConnectionManagerType::new(),
+        keyspace_receiver,
+        Arc::new(NoopMetrics),
+    ));
+
+    let nodes = nodes
+        .into_iter()
+        .map(|node| {
+            let host_id = Uuid::new_v4();
+            (
+                host_id,
+                Arc::new(Node::new_with_state(
+                    connection_pool_factory.clone(),
+                    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), node.port),
+                    None,
+                    Some(host_id),
+                    Some(NodeDistance::Local),
+                    NodeState::Up,
+                    vec![Murmur3Token::new(node.token).into()],
+                    "r1".into(),
+                    node.datacenter.into(),
+                )),
+            )
+        })
+        .collect();
+
+    ClusterMetadata::new(nodes, keyspaces, Default::default())
+}
+
+/// Returns broadcast rpc ports of nodes in given query plan.
+pub(crate) fn ports(query_plan: &[Arc<MockNode>]) -> Vec<u16> {
+    query_plan
+        .iter()
+        .map(|node| node.broadcast_rpc_address().port())
+        .collect()
+}
@@ -0,0 +1,292 @@
+use std::marker::PhantomData;
+
+use crate::cluster::topology::{KeyspaceMetadata, Node, ReplicationStrategy};
+use crate::cluster::{ClusterMetadata, ConnectionManager};
+use crate::load_balancing::{LoadBalancingStrategy, QueryPlan, Request};
+use crate::transport::CdrsTransport;
+use cassandra_protocol::token::Token;
+use fxhash::{FxHashMap, FxHashSet};
+use itertools::Itertools;
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// Token-aware load balancing strategy wrapping a child strategy. If a request contains a token
+/// or a routing key, and its keyspace metadata is known, replica nodes for the request token
+/// returned by the child strategy are moved to the front of its query plan in random order.
+/// Replicas not returned by the child strategy are never used, so its restrictions, e.g. not
+/// using remote datacenters for dc-local consistencies, are preserved. Otherwise, the query plan
+/// of the child strategy is used as-is.
+///
+/// Contrary to [`TopologyAwareLoadBalancingStrategy`](crate::load_balancing::TopologyAwareLoadBalancingStrategy),
+/// replicas are chosen without taking racks into account.
+pub struct TokenAwareLoadBalancingStrategy<
+    T: CdrsTransport,
+    CM: ConnectionManager<T>,
+    LB: LoadBalancingStrategy<T, CM>,
+> {
+    child: LB,
+    _transport: PhantomData<T>,
+    _connection_manager: PhantomData<CM>,
+}
+
+impl<T: CdrsTransport, CM: ConnectionManager<T>, LB: LoadBalancingStrategy<T, CM>>
+    TokenAwareLoadBalancingStrategy<T, CM, LB>
+{
+    pub fn new(child: LB) -> Self {
+        TokenAwareLoadBalancingStrategy {
+            child,
+            _transport: Default::default(),
+            _connection_manager: Default::default(),
+        }
+    }
+
+    fn replicas(&self, request: &Request, cluster: &ClusterMetadata<T, CM>) -> QueryPlan<T, CM> {
+        let token = request.token.clone().or_else(|| {
+            request.routing_key.map(|routing_key| {
+                cluster
+                    .token_map()
+                    .partitioner()
+                    .generate_token(routing_key)
+            })
+        });
+
+        let keyspace = request
+            .keyspace
+            .and_then(|keyspace| cluster.keyspace(keyspace));
+
+        match (token, keyspace) {
+            (Some(token), Some(keyspace)) => Self::keyspace_replicas(token, keyspace, cluster),
+            _ => vec![],
+        }
+    }
+
+    fn keyspace_replicas(
+        token: Token,
+        keyspace: &KeyspaceMetadata,
+        cluster: &ClusterMetadata<T, CM>,
+    ) -> Vec<Arc<Node<T, CM>>> {
+        // nodes owning multiple tokens can appear multiple times on the ring
+        let nodes = cluster
+            .token_map()
+            .nodes_for_token(token)
+            .unique_by(|node| node.broadcast_rpc_address());
+
+        match &keyspace.replication_strategy {
+            ReplicationStrategy::SimpleStrategy { replication_factor } => {
+                nodes.take(*replication_factor).collect()
+            }
+            ReplicationStrategy::NetworkTopologyStrategy {
+                datacenter_replication_factor,
+            } => {
+                let mut remaining: FxHashMap<&str, usize> = datacenter_replication_factor
+                    .iter()
+                    .map(|(dc, replication_factor)| (dc.as_str(), *replication_factor))
+                    .collect();
+
+                nodes
+                    .filter(|node| match remaining.get_mut(node.datacenter()) {
+                        Some(remaining) if *remaining > 0 => {
+                            *remaining -= 1;
+                            true
+                        }
+                        _ => false,
+                    })
+                    .collect()
+            }
+            ReplicationStrategy::Other => nodes.take(1).collect(),
+        }
+    }
+}
+
+impl<T: CdrsTransport, CM: ConnectionManager<T>, LB: LoadBalancingStrategy<T, CM>>
+    LoadBalancingStrategy<T, CM> for TokenAwareLoadBalancingStrategy<T, CM, LB>
+{
+    fn query_plan(
+        &self,
+        request: Option<Request>,
+        cluster: &ClusterMetadata<T, CM>,
+    ) -> QueryPlan<T, CM> {
+        let replicas = match &request {
+            Some(request) => self.replicas(request, cluster),
+            None => vec![],
+        };
+
+        let child_plan = self.child.query_plan(request, cluster);
+        if replicas.is_empty() {
+            return child_plan;
+        }
+
+        let replicas: FxHashSet<_> = replicas
+            .iter()
+            .map(|node| node.broadcast_rpc_address())
+            .collect();
+
+        let (mut query_plan, mut non_replicas): (Vec<_>, Vec<_>) = child_plan
+            .into_iter()
+            .partition(|node| replicas.contains(&node.broadcast_rpc_address()));
+
+        query_plan.shuffle(&mut thread_rng());
+        query_plan.append(&mut non_replicas);
+        query_plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cassandra_protocol::token::Murmur3Token;
+    use fxhash::FxHashMap;
+
+    use crate::cluster::topology::{KeyspaceMetadata, ReplicationStrategy};
+    use crate::load_balancing::test_utils::{create_cluster, ports, MockClusterMetadata, TestNode};
+    use cassandra_protocol::consistency::Consistency;
+
+    use crate::load_balancing::{
+        DcAwareRoundRobinLoadBalancingStrategy, LoadBalancingStrategy, Request,
+        RoundRobinLoadBalancingStrategy, TokenAwareLoadBalancingStrategy,
+    };
+
+    fn create_replicated_cluster() -> MockClusterMetadata {
+        let mut datacenter_replication_factor = FxHashMap::default();
+        datacenter_replication_factor.insert("dc1".into(), 1);
+        datacenter_replication_factor.insert("dc2".into(), 1);
+
+        let mut keyspaces = FxHashMap::default();
+        keyspaces.insert(
+            "k1".into(),
+            KeyspaceMetadata::new(ReplicationStrategy::SimpleStrategy {
+                replication_factor: 2,
+            }),
+        );
+        keyspaces.insert(
+            "k2".into(),
+            KeyspaceMetadata::new(ReplicationStrategy::NetworkTopologyStrategy {
+                datacenter_replication_factor,
+            }),
+        );
+
+        create_cluster(
+            [(1, "dc1"), (2, "dc2"), (3, "dc1"), (4, "dc1")]
+                .iter()
+                .map(|&(port, datacenter)| TestNode::new(port, datacenter)),
+            keyspaces,
+        )
+    }
+
+    #[test]
+    fn should_delegate_without_token() {
+        let cluster = create_replicated_cluster();
+        let lb = TokenAwareLoadBalancingStrategy::new(RoundRobinLoadBalancingStrategy::new());
+
+        assert_eq!(lb.query_plan(None, &cluster).len(), 4);
+        assert_eq!(
+            lb.query_plan(Some(Request::new(Some("k1"), None, None, None)), &cluster)
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn should_prioritize_simple_strategy_replicas() {
+        let cluster = create_replicated_cluster();
+        let lb = TokenAwareLoadBalancingStrategy::new(RoundRobinLoadBalancingStrategy::new());
+
+        let query_plan = lb.query_plan(
+            Some(Request::new(
+                Some("k1"),
+                Some(Murmur3Token::new(2).into()),
+                None,
+                None,
+            )),
+            &cluster,
+        );
+
+        let mut ports = ports(&query_plan);
+        assert_eq!(ports.len(), 4);
+
+        ports[..2].sort_unstable();
+        assert_eq!(ports[..2], [2, 3]);
+    }
+
+    #[test]
+    fn should_prioritize_network_topology_strategy_replicas() {
+        let cluster = create_replicated_cluster();
+        let lb = TokenAwareLoadBalancingStrategy::new(RoundRobinLoadBalancingStrategy::new());
+
+        let query_plan = lb.query_plan(
+            Some(Request::new(
+                Some("k2"),
+                Some(Murmur3Token::new(3).into()),
+                None,
+                None,
+            )),
+            &cluster,
+        );
+
+        let mut ports = ports(&query_plan);
+        assert_eq!(ports.len(), 4);
+
+        // first dc1 node is 3, first dc2 node is 2 after wrapping around the ring
+        ports[..2].sort_unstable();
+        assert_eq!(ports[..2], [2, 3]);
+    }
+
+    #[test]
+    fn should_prefer_token_over_routing_key() {
+        let cluster = create_replicated_cluster();
+        let lb = TokenAwareLoadBalancingStrategy::new(RoundRobinLoadBalancingStrategy::new());
+
+        let routing_key = [0, 0, 0, 1];
+        let query_plan = lb.query_plan(
+            Some(Request::new(Some("k1"), None, Some(&routing_key), None)),
+            &cluster,
+        );
+
+        let mut routing_key_ports = ports(&query_plan);
+        routing_key_ports[..2].sort_unstable();
+        assert_eq!(routing_key_ports[..2], [1, 2]);
+
+        let query_plan = lb.query_plan(
+            Some(Request::new(
+                Some("k1"),
+                Some(Murmur3Token::new(2).into()),
+                Some(&routing_key),
+                None,
+            )),
+            &cluster,
+        );
+
+        let mut token_ports = ports(&query_plan);
+        token_ports[..2].sort_unstable();
+        assert_eq!(token_ports[..2], [2, 3]);
+    }
+
+    #[test]
+    fn should_only_prioritize_replicas_returned_by_child() {
+        let cluster = create_replicated_cluster();
+        let lb = TokenAwareLoadBalancingStrategy::new(DcAwareRoundRobinLoadBalancingStrategy::new(
+            "dc1".into(),
+            1,
+        ));
+
+        let request = |consistency| {
+            Some(Request::new(
+                Some("k2"),
+                Some(Murmur3Token::new(3).into()),
+                None,
+                Some(consistency),
+            ))
+        };
+
+        // the dc2 replica must not be used for a dc-local consistency
+        let local_ports = ports(&lb.query_plan(request(Consistency::LocalQuorum), &cluster));
+        assert_eq!(local_ports.len(), 3);
+        assert_eq!(local_ports[0], 3);
+        assert!(!local_ports.contains(&2));
+
+        let mut all_ports = ports(&lb.query_plan(request(Consistency::Quorum), &cluster));
+        assert_eq!(all_ports.len(), 4);
+
+        all_ports[..2].sort_unstable();
+        assert_eq!(all_ports[..2], [2, 3]);
+    }
+}
@@ -3,7 +3,7 @@ use crate::cluster::{ClusterMetadata, ConnectionManager};
 use crate::load_balancing::{LoadBalancingStrategy, QueryPlan, Request};
 use crate::transport::CdrsTransport;
 use cassandra_protocol::consistency::Consistency;
-use cassandra_protocol::token::Murmur3Token;
+use cassandra_protocol::token::Token;
 use fxhash::{FxHashMap, FxHashSet};
 use itertools::Itertools;
 use rand::prelude::*;
@@ -72,9 +72,15 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> TopologyAwareLoadBalancingStrat
         request: Request,
         cluster: &ClusterMetadata<T, CM>,
     ) -> QueryPlan<T, CM> {
-        let token = request
-            .token
-            .or_else(|| request.routing_key.map(Murmur3Token::generate));
+        let routing_key = request.routing_key;
+        let token = request.token.or_else(|| {
+            routing_key.map(|routing_key| {
+                cluster
+                    .token_map()
+                    .partitioner()
+                    .generate_token(routing_key)
+            })
+        });
 
         if let Some(token) = token {
             self.replicas_for_token(token, request.keyspace, request.consistency, cluster)
@@ -85,7 +91,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> TopologyAwareLoadBalancingStrat
 
     fn replicas_for_token(
         &self,
-        token: Murmur3Token,
+        token: Token,
         keyspace: Option<&str>,
         consistency: Option<Consistency>,
         cluster: &ClusterMetadata<T, CM>,
@@ -98,7 +104,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> TopologyAwareLoadBalancingStrat
 
     fn replicas_for_keyspace(
         &self,
-        token: Murmur3Token,
+        token: Token,
         keyspace: &KeyspaceMetadata,
         consistency: Option<Consistency>,
         cluster: &ClusterMetadata<T, CM>,
@@ -121,7 +127,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> TopologyAwareLoadBalancingStrat
 
     fn network_topology_strategy_replicas(
         &self,
-        token: Murmur3Token,
+        token: Token,
         mut datacenter_replication_factor: FxHashMap<String, usize>,
         consistency: Option<Consistency>,
         cluster: &ClusterMetadata<T, CM>,
@@ -240,7 +246,7 @@ impl<T: CdrsTransport, CM: ConnectionManager<T>> TopologyAwareLoadBalancingStrat
 
     fn simple_strategy_replicas(
         &self,
-        token: Murmur3Token,
+        token: Token,
         replica_count: usize,
         cluster: &ClusterMetadata<T, CM>,
     ) -> QueryPlan<T, CM> {
@@ -308,10 +314,11 @@ mod tests {
     use crate::cluster::topology::{
         KeyspaceMetadata, Node, NodeDistance, NodeState, ReplicationStrategy,
     };
-    use crate::cluster::ClusterMetadata;
+    use crate::cluster::{ClusterMetadata, VersionHolder};
     use crate::load_balancing::{
         LoadBalancingStrategy, Request, TopologyAwareLoadBalancingStrategy,
     };
+    use crate::metrics::NoopMetrics;
     use crate::transport::MockCdrsTransport;
 
     lazy_static! {
@@ -328,9 +335,10 @@ mod tests {
         let connection_manager = MockConnectionManager::<MockCdrsTransport>::new();
         let connection_pool_factory = Arc::new(ConnectionPoolFactory::new(
             Default::default(),
-            Version::V4,
+            Arc::new(VersionHolder::new(Version::V4)),
             connection_manager,
             keyspace_receiver,
+            Arc::new(NoopMetrics),
         ));
 
         let mut nodes = FxHashMap::default();
@@ -343,7 +351,7 @@ mod tests {
                 Some(*HOST_ID_1),
                 Some(NodeDistance::Local),
                 NodeState::Up,
-                vec![Murmur3Token::new(1), Murmur3Token::new(2)],
+                vec![Murmur3Token::new(1).into(), Murmur3Token::new(2).into()],
                 "r1".into(),
                 "dc1".into(),
             )),
@@ -357,7 +365,7 @@ mod tests {
                 Some(*HOST_ID_2),
                 Some(NodeDistance::Local),
                 NodeState::Up,
-                vec![Murmur3Token::new(3), Murmur3Token::new(4)],
+                vec![Murmur3Token::new(3).into(), Murmur3Token::new(4).into()],
                 "r1".into(),
                 "dc1".into(),
             )),
@@ -371,7 +379,7 @@ mod tests {
                 Some(*HOST_ID_3),
                 Some(NodeDistance::Local),
                 NodeState::Up,
-                vec![Murmur3Token::new(7)],
+                vec![Murmur3Token::new(7).into()],
                 "r2".into(),
                 "dc1".into(),
             )),
@@ -385,7 +393,7 @@ mod tests {
                 None,
                 None,
                 NodeState::Up,
-                vec![Murmur3Token::new(8)],
+                vec![Murmur3Token::new(8).into()],
                 "r2".into(),
                 "dc1".into(),
             )),
@@ -399,7 +407,7 @@ mod tests {
                 Some(*HOST_ID_4),
                 Some(NodeDistance::Remote),
                 NodeState::Up,
-                vec![Murmur3Token::new(5), Murmur3Token::new(6)],
+                vec![Murmur3Token::new(5).into(), Murmur3Token::new(6).into()],
                 "r1".into(),
                 "dc2".into(),
             )),
@@ -413,7 +421,7 @@ mod tests {
                 None,
                 None,
                 NodeState::Up,
-                vec![Murmur3Token::new(9)],
+                vec![Murmur3Token::new(9).into()],
                 "r1".into(),
                 "dc2".into(),
             )),
@@ -427,7 +435,7 @@ mod tests {
                 Some(*HOST_ID_5),
                 Some(NodeDistance::Remote),
                 NodeState::Up,
-                vec![Murmur3Token::new(0)],
+                vec![Murmur3Token::new(0).into()],
                 "r2".into(),
                 "dc2".into(),
             )),
@@ -465,7 +473,7 @@ mod tests {
             }),
         );
 
-        ClusterMetadata::new(nodes, keyspaces)
+        ClusterMetadata::new(nodes, keyspaces, Default::default())
     }
 
     #[test]
@@ -513,7 +521,12 @@ mod tests {
         let lb = TopologyAwareLoadBalancingStrategy::new(None, false);
 
         let query_plan = lb.query_plan(
-            Some(Request::new(None, Some(Murmur3Token::new(4)), None, None)),
+            Some(Request::new(
+                None,
+                Some(Murmur3Token::new(4).into()),
+                None,
+                None,
+            )),
             &cluster,
         );
         assert_eq!(query_plan.len(), 3);
@@ -531,7 +544,7 @@ mod tests {
         let query_plan = lb.query_plan(
             Some(Request::new(
                 Some("k3"),
-                Some(Murmur3Token::new(4)),
+                Some(Murmur3Token::new(4).into()),
                 None,
                 None,
             )),
@@ -549,7 +562,7 @@ mod tests {
         let query_plan = lb.query_plan(
             Some(Request::new(
                 Some("k1"),
-                Some(Murmur3Token::new(4)),
+                Some(Murmur3Token::new(4).into()),
                 None,
                 None,
             )),
@@ -576,7 +589,7 @@ mod tests {
         let query_plan = lb.query_plan(
             Some(Request::new(
                 Some("k2"),
-                Some(Murmur3Token::new(2)),
+                Some(Murmur3Token::new(2).into()),
                 None,
                 None,
             )),
@@ -609,7 +622,7 @@ mod tests {
         let query_plan = lb.query_plan(
             Some(Request::new(
                 Some("k4"),
-                Some(Murmur3Token::new(2)),
+                Some(Murmur3Token::new(2).into()),
                 None,
                 None,
             )),
@@ -637,7 +650,7 @@ mod tests {
         let query_plan = lb.query_plan(
             Some(Request::new(
                 Some("k4"),
-                Some(Murmur3Token::new(2)),
+                Some(Murmur3Token::new(2).into()),
                 None,
                 None,
             )),
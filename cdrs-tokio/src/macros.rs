@@ -1,8 +1,21 @@
 #[macro_export]
 
-/// Transforms arguments to values consumed by queries.
+/// Transforms arguments to values consumed by queries. Positional arguments build
+/// `QueryValues::SimpleValues`, while `name => value` pairs build `QueryValues::NamedValues`. Each
+/// value is converted using `Into<Value>`.
+///
+/// ```
+/// use cdrs_tokio::query::QueryValues;
+/// use cdrs_tokio::query_values;
+///
+/// let values = query_values!(1i32, "text", 2.5f64);
+/// assert_eq!(values.len(), 3);
+///
+/// let values = query_values!("id" => 1i32, "name" => "text",);
+/// assert!(matches!(values, QueryValues::NamedValues(_)));
+/// ```
 macro_rules! query_values {
-    ($($value:expr),*) => {
+    ($($value:expr),* $(,)?) => {
         {
             use cdrs_tokio::types::value::Value;
             use cdrs_tokio::query::QueryValues;
@@ -13,7 +26,7 @@ macro_rules! query_values {
             QueryValues::SimpleValues(values)
         }
     };
-    ($($name:expr => $value:expr),*) => {
+    ($($name:expr => $value:expr),* $(,)?) => {
         {
             use cdrs_tokio::types::value::Value;
             use cdrs_tokio::query::QueryValues;
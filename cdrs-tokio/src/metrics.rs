@@ -0,0 +1,40 @@
+//! Hooks for collecting driver metrics, e.g. for exporting them to a monitoring system.
+
+use cassandra_protocol::error::Error;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Receives notifications about requests and connections. Callbacks are invoked on the hot path,
+/// so they should be fast and must not block. All callbacks do nothing by default.
+///
+/// Every request sent to a node results in exactly one `on_request_start` call, followed by
+/// exactly one of `on_request_success`, `on_request_error` or `on_request_cancel`, which allows
+/// tracking in-flight requests. Retries and speculative executions are reported as separate
+/// requests.
+pub trait Metrics: Send + Sync {
+    /// Called when a request is about to be sent to given node.
+    fn on_request_start(&self, _node: SocketAddr) {}
+
+    /// Called when a response to a request has been received from given node.
+    fn on_request_success(&self, _node: SocketAddr, _latency: Duration) {}
+
+    /// Called when a request sent to given node failed.
+    fn on_request_error(&self, _node: SocketAddr, _error: &Error, _latency: Duration) {}
+
+    /// Called when a request sent to given node was abandoned before receiving a response, e.g.
+    /// because another speculative execution completed first or the request timed out.
+    fn on_request_cancel(&self, _node: SocketAddr, _latency: Duration) {}
+
+    /// Called when a new pooled connection to given node has been established.
+    fn on_connection_open(&self, _node: SocketAddr) {}
+
+    /// Called when a pooled connection to given node is discarded, either because it's being
+    /// replaced, or the pool is no longer used.
+    fn on_connection_close(&self, _node: SocketAddr) {}
+}
+
+/// Metrics implementation ignoring all notifications.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
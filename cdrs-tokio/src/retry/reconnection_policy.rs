@@ -123,10 +123,12 @@ impl ReconnectionSchedule for ExponentialReconnectionSchedule {
 
         let jitter = thread_rng().gen_range(85..116);
 
+        // not using clamp(), since it panics when base delay exceeds max delay
         Some(
             (delay / 100)
                 .saturating_mul(jitter)
-                .clamp(self.base_delay, self.max_delay),
+                .max(self.base_delay)
+                .min(self.max_delay),
         )
     }
 }
@@ -146,6 +148,7 @@ impl ExponentialReconnectionSchedule {
 mod tests {
     use crate::retry::reconnection_policy::ExponentialReconnectionSchedule;
     use crate::retry::ReconnectionSchedule;
+    use std::time::Duration;
 
     #[test]
     fn should_reach_max_exponential_delay_without_panic() {
@@ -158,4 +161,17 @@ mod tests {
 
         schedule.next_delay();
     }
+
+    #[test]
+    fn should_cap_exponential_delay_below_base_delay() {
+        let mut schedule = ExponentialReconnectionSchedule::new(
+            Duration::from_secs(10),
+            Duration::from_secs(1),
+            5,
+        );
+
+        for _ in 0..7 {
+            assert_eq!(schedule.next_delay(), Some(Duration::from_secs(1)));
+        }
+    }
 }
@@ -1,13 +1,18 @@
 use derive_more::Display;
 
+use cassandra_protocol::consistency::Consistency;
 use cassandra_protocol::error::Error;
 use cassandra_protocol::frame::message_error::{
-    AdditionalErrorInfo, ErrorBody, ReadTimeoutError, WriteTimeoutError, WriteType,
+    AdditionalErrorInfo, ErrorBody, ReadTimeoutError, UnavailableError, WriteTimeoutError,
+    WriteType,
 };
 
 #[derive(Debug, PartialEq, Eq, Ord, PartialOrd, Hash, Copy, Clone, Display)]
 pub enum RetryDecision {
     RetrySameNode,
+    /// Retry on the same node, with given consistency.
+    #[display(fmt = "RetrySameNodeWithConsistency({})", _0)]
+    RetrySameNodeWithConsistency(Consistency),
     RetryNextNode,
     DontRetry,
 }
@@ -71,6 +76,7 @@ impl RetrySession for DefaultRetrySession {
     fn decide(&mut self, query_info: QueryInfo) -> RetryDecision {
         match query_info.error {
             Error::Io(_)
+            | Error::Timeout { .. }
             | Error::General(_)
             | Error::Server {
                 body:
@@ -167,3 +173,192 @@ impl RetrySession for DefaultRetrySession {
         }
     }
 }
+
+/// A retry policy that retries with a lower consistency level when there is a chance a retry
+/// with given number of known live replicas might succeed. This trades consistency for
+/// availability and should be used with caution, since the final consistency level of a query is
+/// only known to the server. Behaviour based on [DataStax Java Driver](https://docs.datastax.com/en/developer/java-driver/4.10/manual/core/retries/)
+#[derive(Default)]
+pub struct DowngradingConsistencyRetryPolicy;
+
+impl RetryPolicy for DowngradingConsistencyRetryPolicy {
+    fn new_session(&self) -> Box<dyn RetrySession + Send + Sync> {
+        Box::new(DowngradingConsistencyRetrySession::default())
+    }
+}
+
+#[derive(Default)]
+pub struct DowngradingConsistencyRetrySession {
+    was_retry: bool,
+}
+
+impl DowngradingConsistencyRetrySession {
+    fn downgrade(current: Consistency, known_ok: i32) -> RetryDecision {
+        if known_ok >= 3 {
+            RetryDecision::RetrySameNodeWithConsistency(Consistency::Three)
+        } else if known_ok == 2 {
+            RetryDecision::RetrySameNodeWithConsistency(Consistency::Two)
+        } else if known_ok == 1 || current == Consistency::EachQuorum {
+            // EACH_QUORUM with no live replicas in some dc can still succeed at ONE
+            RetryDecision::RetrySameNodeWithConsistency(Consistency::One)
+        } else {
+            RetryDecision::DontRetry
+        }
+    }
+}
+
+impl RetrySession for DowngradingConsistencyRetrySession {
+    fn decide(&mut self, query_info: QueryInfo) -> RetryDecision {
+        let additional_info = match query_info.error {
+            Error::Server { body, .. } => &body.additional_info,
+            _ => return DefaultRetrySession::default().decide(query_info),
+        };
+
+        if self.was_retry {
+            return RetryDecision::DontRetry;
+        }
+
+        self.was_retry = true;
+
+        match additional_info {
+            AdditionalErrorInfo::ReadTimeout(error @ ReadTimeoutError { .. }) => {
+                if error.cl.is_serial() {
+                    RetryDecision::DontRetry
+                } else if error.received < error.block_for {
+                    Self::downgrade(error.cl, error.received)
+                } else if !error.replica_has_responded() {
+                    RetryDecision::RetrySameNode
+                } else {
+                    RetryDecision::DontRetry
+                }
+            }
+            AdditionalErrorInfo::WriteTimeout(error @ WriteTimeoutError { .. })
+                if query_info.is_idempotent =>
+            {
+                match error.write_type {
+                    WriteType::UnloggedBatch => Self::downgrade(error.cl, error.received),
+                    WriteType::BatchLog => RetryDecision::RetrySameNode,
+                    _ => RetryDecision::DontRetry,
+                }
+            }
+            AdditionalErrorInfo::Unavailable(error @ UnavailableError { .. }) => {
+                if error.cl.is_serial() {
+                    RetryDecision::RetryNextNode
+                } else {
+                    Self::downgrade(error.cl, error.alive)
+                }
+            }
+            _ => {
+                self.was_retry = false;
+                DefaultRetrySession::default().decide(query_info)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cassandra_protocol::consistency::Consistency;
+    use cassandra_protocol::error::{Error, TimeoutOperation};
+    use cassandra_protocol::frame::message_error::{
+        AdditionalErrorInfo, ErrorBody, UnavailableError, WriteTimeoutError, WriteType,
+    };
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::time::Duration;
+
+    use crate::retry::{
+        DefaultRetryPolicy, DowngradingConsistencyRetryPolicy, QueryInfo, RetryDecision,
+        RetryPolicy,
+    };
+
+    fn server_error(additional_info: AdditionalErrorInfo) -> Error {
+        Error::Server {
+            body: ErrorBody {
+                error_code: 0,
+                message: "".into(),
+                additional_info,
+            },
+            addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 9042),
+        }
+    }
+
+    #[test]
+    fn should_downgrade_on_unavailable_once() {
+        let error = server_error(AdditionalErrorInfo::Unavailable(UnavailableError {
+            cl: Consistency::Quorum,
+            required: 3,
+            alive: 2,
+        }));
+
+        let mut session = DowngradingConsistencyRetryPolicy.new_session();
+        assert_eq!(
+            session.decide(QueryInfo {
+                error: &error,
+                is_idempotent: false,
+            }),
+            RetryDecision::RetrySameNodeWithConsistency(Consistency::Two)
+        );
+        assert_eq!(
+            session.decide(QueryInfo {
+                error: &error,
+                is_idempotent: false,
+            }),
+            RetryDecision::DontRetry
+        );
+    }
+
+    #[test]
+    fn should_downgrade_idempotent_unlogged_batch_only() {
+        let error = server_error(AdditionalErrorInfo::WriteTimeout(WriteTimeoutError {
+            cl: Consistency::All,
+            received: 1,
+            block_for: 3,
+            write_type: WriteType::UnloggedBatch,
+        }));
+
+        assert_eq!(
+            DowngradingConsistencyRetryPolicy
+                .new_session()
+                .decide(QueryInfo {
+                    error: &error,
+                    is_idempotent: true,
+                }),
+            RetryDecision::RetrySameNodeWithConsistency(Consistency::One)
+        );
+        assert_eq!(
+            DowngradingConsistencyRetryPolicy
+                .new_session()
+                .decide(QueryInfo {
+                    error: &error,
+                    is_idempotent: false,
+                }),
+            RetryDecision::DontRetry
+        );
+    }
+
+    #[test]
+    fn should_retry_idempotent_connect_timeout_on_next_node() {
+        let error = Error::Timeout {
+            operation: TimeoutOperation::Connect(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                9042,
+            )),
+            elapsed: Duration::from_secs(1),
+        };
+
+        assert_eq!(
+            DefaultRetryPolicy.new_session().decide(QueryInfo {
+                error: &error,
+                is_idempotent: true,
+            }),
+            RetryDecision::RetryNextNode
+        );
+        assert_eq!(
+            DefaultRetryPolicy.new_session().decide(QueryInfo {
+                error: &error,
+                is_idempotent: false,
+            }),
+            RetryDecision::DontRetry
+        );
+    }
+}
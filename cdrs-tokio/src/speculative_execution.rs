@@ -11,6 +11,8 @@
 //! execution will trigger retries independently.
 
 use derive_more::Constructor;
+use std::collections::VecDeque;
+use std::sync::Mutex;
 use std::time::Duration;
 
 /// Current speculative execution context.
@@ -27,6 +29,10 @@ pub trait SpeculativeExecutionPolicy {
     /// Returns the time until a speculative request is sent to the next node. `None` means there
     /// should not be another execution.
     fn execution_interval(&self, context: &Context) -> Option<Duration>;
+
+    /// Called when a speculatively executed request completes successfully, with the time it took
+    /// to get the response. Policies can use this information to adapt to observed latencies.
+    fn on_execution_completed(&self, _latency: Duration) {}
 }
 
 /// A policy that schedules a configurable number of speculative executions, separated by a fixed
@@ -46,3 +52,101 @@ impl SpeculativeExecutionPolicy for ConstantSpeculativeExecutionPolicy {
         }
     }
 }
+
+/// A policy that schedules a configurable number of speculative executions, separated by a delay
+/// equal to given percentile of recently observed latencies. No speculative executions are
+/// scheduled until enough latencies are observed to fill the sample window.
+#[derive(Debug)]
+pub struct PercentileSpeculativeExecutionPolicy {
+    max_executions: usize,
+    percentile: f64,
+    window_size: usize,
+    latencies: Mutex<VecDeque<Duration>>,
+}
+
+impl PercentileSpeculativeExecutionPolicy {
+    /// Creates a new policy using given percentile (in the range of `0.0..=100.0`) of last
+    /// `window_size` latencies.
+    pub fn new(max_executions: usize, percentile: f64, window_size: usize) -> Self {
+        assert!(
+            (0.0..=100.0).contains(&percentile),
+            "Percentile must be in range 0-100, got: {}",
+            percentile
+        );
+
+        PercentileSpeculativeExecutionPolicy {
+            max_executions,
+            percentile,
+            window_size: window_size.max(1),
+            latencies: Mutex::new(VecDeque::with_capacity(window_size)),
+        }
+    }
+
+    fn percentile_latency(&self) -> Option<Duration> {
+        let mut latencies = {
+            let latencies = self.latencies.lock().unwrap();
+            if latencies.len() < self.window_size {
+                return None;
+            }
+
+            latencies.iter().copied().collect::<Vec<_>>()
+        };
+
+        latencies.sort_unstable();
+
+        let index = ((self.percentile / 100.0) * latencies.len() as f64).ceil() as usize;
+        latencies
+            .get(index.saturating_sub(1).min(latencies.len() - 1))
+            .copied()
+    }
+}
+
+impl SpeculativeExecutionPolicy for PercentileSpeculativeExecutionPolicy {
+    fn execution_interval(&self, context: &Context) -> Option<Duration> {
+        if context.running_executions < self.max_executions {
+            self.percentile_latency()
+        } else {
+            None
+        }
+    }
+
+    fn on_execution_completed(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock().unwrap();
+        if latencies.len() == self.window_size {
+            latencies.pop_front();
+        }
+
+        latencies.push_back(latency);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::speculative_execution::{
+        Context, PercentileSpeculativeExecutionPolicy, SpeculativeExecutionPolicy,
+    };
+
+    #[test]
+    fn should_not_speculate_without_enough_samples() {
+        let policy = PercentileSpeculativeExecutionPolicy::new(2, 99.0, 10);
+        policy.on_execution_completed(Duration::from_millis(1));
+
+        assert_eq!(policy.execution_interval(&Context::new(1)), None);
+    }
+
+    #[test]
+    fn should_use_percentile_of_recent_latencies() {
+        let policy = PercentileSpeculativeExecutionPolicy::new(2, 90.0, 10);
+        for latency in 1..=20 {
+            policy.on_execution_completed(Duration::from_millis(latency));
+        }
+
+        assert_eq!(
+            policy.execution_interval(&Context::new(1)),
+            Some(Duration::from_millis(19))
+        );
+        assert_eq!(policy.execution_interval(&Context::new(2)), None);
+    }
+}
@@ -1,8 +1,12 @@
 use crate::retry::RetryPolicy;
+use cassandra_protocol::error::Result;
+use cassandra_protocol::frame::CustomPayload;
+use cassandra_protocol::query::utils::serialize_routing_key;
 use cassandra_protocol::query::QueryParams;
-use cassandra_protocol::token::Murmur3Token;
+use cassandra_protocol::token::{Partitioner, Token};
 use cassandra_protocol::types::value::Value;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::speculative_execution::SpeculativeExecutionPolicy;
 
@@ -19,15 +23,19 @@ pub struct StatementParams {
     pub keyspace: Option<String>,
     /// The token to use for token-aware routing. A load balancer may use this information to
     /// determine which nodes to contact. Takes precedence over `routing_key`.
-    pub token: Option<Murmur3Token>,
+    pub token: Option<Token>,
     /// The partition key to use for token-aware routing. A load balancer may use this information
     /// to determine which nodes to contact. Alternative to `token`. Note: prepared statements
-    /// with bound primary key values take precedence over this field.
+    /// with bound primary key values take precedence over this field. Multi-column partition keys
+    /// need all their values, in partition key order.
     pub routing_key: Option<Vec<Value>>,
     /// Should tracing be enabled.
     pub tracing: bool,
     /// Should warnings be enabled.
     pub warnings: bool,
+    /// Custom payload sent with the request, available since protocol V4. Ignored for older
+    /// protocol versions.
+    pub custom_payload: CustomPayload,
     /// Custom statement speculative execution policy.
     pub speculative_execution_policy: Option<Arc<dyn SpeculativeExecutionPolicy + Send + Sync>>,
     /// Custom statement retry policy.
@@ -35,4 +43,32 @@ pub struct StatementParams {
     /// Enable beta protocol features. Server will respond with ERROR if protocol version is marked
     /// as beta on server and client does not provide this flag.
     pub beta_protocol: bool,
+    /// Client-side timeout for the whole request, including retries and speculative executions.
+    /// Independent of server-side read/write timeouts.
+    pub request_timeout: Option<Duration>,
+}
+
+impl StatementParams {
+    /// Sets the routing key for a table with a single-column partition key.
+    #[inline]
+    pub fn set_single_routing_key(&mut self, value: impl Into<Value>) {
+        self.routing_key = Some(vec![value.into()]);
+    }
+
+    /// Computes the token a load balancer will use for token-aware routing with given
+    /// partitioner: either the explicitly set `token` or the one derived from `routing_key`.
+    /// Returns `None` if neither is set, or an error if the routing key cannot be serialized.
+    pub fn compute_token(&self, partitioner: Partitioner) -> Result<Option<Token>> {
+        if let Some(token) = &self.token {
+            return Ok(Some(token.clone()));
+        }
+
+        self.routing_key
+            .as_ref()
+            .map(|routing_key| {
+                serialize_routing_key(routing_key)
+                    .map(|routing_key| partitioner.generate_token(&routing_key))
+            })
+            .transpose()
+    }
 }
@@ -1,9 +1,11 @@
 use cassandra_protocol::consistency::Consistency;
-use cassandra_protocol::query::{QueryFlags, QueryParams, QueryValues};
-use cassandra_protocol::token::Murmur3Token;
+use cassandra_protocol::frame::CustomPayload;
+use cassandra_protocol::query::{QueryFlags, QueryParams, QueryValues, TimestampGenerator};
+use cassandra_protocol::token::Token;
 use cassandra_protocol::types::value::Value;
 use cassandra_protocol::types::{CBytes, CInt, CLong};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::retry::RetryPolicy;
 use crate::speculative_execution::SpeculativeExecutionPolicy;
@@ -13,7 +15,7 @@ use crate::statement::StatementParams;
 pub struct StatementParamsBuilder {
     consistency: Consistency,
     flags: Option<QueryFlags>,
-    values: Option<QueryValues>,
+    values: Option<Arc<QueryValues>>,
     with_names: bool,
     page_size: Option<CInt>,
     paging_state: Option<CBytes>,
@@ -22,13 +24,15 @@ pub struct StatementParamsBuilder {
     is_idempotent: bool,
     keyspace: Option<String>,
     now_in_seconds: Option<CInt>,
-    token: Option<Murmur3Token>,
+    token: Option<Token>,
     routing_key: Option<Vec<Value>>,
     tracing: bool,
     warnings: bool,
+    custom_payload: CustomPayload,
     speculative_execution_policy: Option<Arc<dyn SpeculativeExecutionPolicy + Send + Sync>>,
     retry_policy: Option<Arc<dyn RetryPolicy + Send + Sync>>,
     beta_protocol: bool,
+    request_timeout: Option<Duration>,
 }
 
 impl StatementParamsBuilder {
@@ -52,7 +56,14 @@ impl StatementParamsBuilder {
 
     /// Sets new statement values.
     #[must_use]
-    pub fn with_values(mut self, values: QueryValues) -> Self {
+    pub fn with_values(self, values: QueryValues) -> Self {
+        self.with_shared_values(Arc::new(values))
+    }
+
+    /// Sets new statement values kept in shared storage, which can be reused by multiple
+    /// statements without copying.
+    #[must_use]
+    pub fn with_shared_values(mut self, values: Arc<QueryValues>) -> Self {
         self.with_names = values.has_names();
         self.values = Some(values);
         self.flags = self.flags.or_else(|| {
@@ -98,13 +109,19 @@ impl StatementParamsBuilder {
         self
     }
 
-    /// Sets new timestamp.
+    /// Sets new timestamp, in microseconds since the Unix epoch.
     #[must_use]
     pub fn with_timestamp(mut self, timestamp: i64) -> Self {
         self.timestamp = Some(timestamp);
         self
     }
 
+    /// Sets new timestamp taken from given generator.
+    #[must_use]
+    pub fn with_generated_timestamp(self, generator: &dyn TimestampGenerator) -> Self {
+        self.with_timestamp(generator.next_timestamp())
+    }
+
     /// Sets new keyspace.
     #[must_use]
     pub fn with_keyspace(mut self, keyspace: String) -> Self {
@@ -112,9 +129,10 @@ impl StatementParamsBuilder {
         self
     }
 
-    /// Sets new token for routing.
+    /// Sets new token for routing, e.g. to reach replicas owning a token range without knowing
+    /// its partition keys. Takes precedence over the routing key.
     #[must_use]
-    pub fn with_token(mut self, token: Murmur3Token) -> Self {
+    pub fn with_token(mut self, token: Token) -> Self {
         self.token = Some(token);
         self
     }
@@ -126,7 +144,17 @@ impl StatementParamsBuilder {
         self
     }
 
-    /// Marks the statement as idempotent or not
+    /// Sets new explicit routing key for a table with a single-column partition key. Use
+    /// `with_routing_key` for multi-column keys.
+    #[must_use]
+    pub fn with_single_routing_key(mut self, value: impl Into<Value>) -> Self {
+        self.routing_key = Some(vec![value.into()]);
+        self
+    }
+
+    /// Marks the statement as idempotent or not. See
+    /// [`infer_idempotence`](cassandra_protocol::query::utils::infer_idempotence) for a
+    /// best-effort way of determining it from the statement text.
     #[must_use]
     pub fn idempotent(mut self, value: bool) -> Self {
         self.is_idempotent = value;
@@ -150,6 +178,14 @@ impl StatementParamsBuilder {
         self
     }
 
+    /// Enables server-side tracing. The trace id returned in the response envelope can be used to
+    /// query `system_traces.sessions` and `system_traces.events`.
+    #[must_use]
+    pub fn with_tracing(mut self, tracing: bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
     /// Sets beta protocol usage flag
     #[must_use]
     pub fn with_beta_protocol(mut self, beta_protocol: bool) -> Self {
@@ -164,6 +200,21 @@ impl StatementParamsBuilder {
         self
     }
 
+    /// Sets client-side request timeout. When exceeded, the request fails with an
+    /// `Error::Timeout` for `TimeoutOperation::Request`.
+    #[must_use]
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = Some(request_timeout);
+        self
+    }
+
+    /// Sets custom payload sent with the request. Requires protocol V4 or above.
+    #[must_use]
+    pub fn with_custom_payload(mut self, custom_payload: CustomPayload) -> Self {
+        self.custom_payload = custom_payload;
+        self
+    }
+
     #[must_use]
     pub fn build(self) -> StatementParams {
         StatementParams {
@@ -184,9 +235,11 @@ impl StatementParamsBuilder {
             routing_key: self.routing_key,
             tracing: self.tracing,
             warnings: self.warnings,
+            custom_payload: self.custom_payload,
             speculative_execution_policy: self.speculative_execution_policy,
             retry_policy: self.retry_policy,
             beta_protocol: self.beta_protocol,
+            request_timeout: self.request_timeout,
         }
     }
 }
@@ -30,7 +30,7 @@ use tokio::io::{
     WriteHalf,
 };
 use tokio::net::TcpStream;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
 use tokio::task::JoinHandle;
 #[cfg(feature = "rust-tls")]
 use tokio_rustls::TlsConnector as RustlsConnector;
@@ -47,6 +47,10 @@ use crate::Result;
 
 const INITIAL_STREAM_ID: i16 = 1;
 
+/// Maximum number of concurrent requests on a single connection, limited by the number of
+/// available positive stream ids.
+pub const MAX_IN_FLIGHT_REQUESTS: usize = i16::MAX as usize;
+
 /// General CDRS transport trait.
 pub trait CdrsTransport: Send + Sync {
     /// Schedules data envelope for writing and waits for a response. Handshake envelopes need to
@@ -62,6 +66,9 @@ pub trait CdrsTransport: Send + Sync {
 
     /// Returns associated node address.
     fn address(&self) -> SocketAddr;
+
+    /// Returns compression used by this connection, as negotiated with the server during startup.
+    fn compression(&self) -> Compression;
 }
 
 #[cfg(test)]
@@ -79,6 +86,8 @@ mock! {
         fn is_broken(&self) -> bool;
 
         fn address(&self) -> SocketAddr;
+
+        fn compression(&self) -> Compression;
     }
 }
 
@@ -95,10 +104,12 @@ impl TransportTcp {
         event_handler: Option<mpsc::Sender<Envelope>>,
         error_handler: Option<mpsc::Sender<Error>>,
         compression: Compression,
+        compression_threshold: usize,
         frame_encoder: Box<dyn FrameEncoder + Send + Sync>,
         frame_decoder: Box<dyn FrameDecoder + Send + Sync>,
         buffer_size: usize,
         tcp_nodelay: bool,
+        max_in_flight_requests: usize,
     ) -> io::Result<TransportTcp> {
         TcpStream::connect(addr).await.and_then(move |socket| {
             socket.set_nodelay(tcp_nodelay)?;
@@ -108,9 +119,11 @@ impl TransportTcp {
                 inner: AsyncTransport::new(
                     addr,
                     compression,
+                    compression_threshold,
                     frame_encoder,
                     frame_decoder,
                     buffer_size,
+                    max_in_flight_requests,
                     read_half,
                     write_half,
                     event_handler,
@@ -142,6 +155,11 @@ impl CdrsTransport for TransportTcp {
     fn address(&self) -> SocketAddr {
         self.inner.addr()
     }
+
+    #[inline]
+    fn compression(&self) -> Compression {
+        self.inner.compression
+    }
 }
 
 #[cfg(feature = "rust-tls")]
@@ -160,10 +178,12 @@ impl TransportRustls {
         event_handler: Option<mpsc::Sender<Envelope>>,
         error_handler: Option<mpsc::Sender<Error>>,
         compression: Compression,
+        compression_threshold: usize,
         frame_encoder: Box<dyn FrameEncoder + Send + Sync>,
         frame_decoder: Box<dyn FrameDecoder + Send + Sync>,
         buffer_size: usize,
         tcp_nodelay: bool,
+        max_in_flight_requests: usize,
     ) -> io::Result<Self> {
         let stream = TcpStream::connect(addr).await?;
         stream.set_nodelay(tcp_nodelay)?;
@@ -176,9 +196,11 @@ impl TransportRustls {
             inner: AsyncTransport::new(
                 addr,
                 compression,
+                compression_threshold,
                 frame_encoder,
                 frame_decoder,
                 buffer_size,
+                max_in_flight_requests,
                 read_half,
                 write_half,
                 event_handler,
@@ -210,12 +232,19 @@ impl CdrsTransport for TransportRustls {
     fn address(&self) -> SocketAddr {
         self.inner.addr()
     }
+
+    #[inline]
+    fn compression(&self) -> Compression {
+        self.inner.compression
+    }
 }
 
 struct AsyncTransport {
     addr: SocketAddr,
     compression: Compression,
+    compression_threshold: usize,
     write_sender: mpsc::Sender<Request>,
+    in_flight_permits: Arc<Semaphore>,
     is_broken: Arc<AtomicBool>,
     processing_handle: JoinHandle<()>,
 }
@@ -231,9 +260,11 @@ impl AsyncTransport {
     fn new<T: AsyncRead + AsyncWrite + Send + 'static>(
         addr: SocketAddr,
         compression: Compression,
+        compression_threshold: usize,
         frame_encoder: Box<dyn FrameEncoder + Send + Sync>,
         frame_decoder: Box<dyn FrameDecoder + Send + Sync>,
         buffer_size: usize,
+        max_in_flight_requests: usize,
         read_half: ReadHalf<T>,
         write_half: WriteHalf<T>,
         event_handler: Option<mpsc::Sender<Envelope>>,
@@ -260,7 +291,11 @@ impl AsyncTransport {
         AsyncTransport {
             addr,
             compression,
+            compression_threshold,
             write_sender,
+            in_flight_permits: Arc::new(Semaphore::new(
+                max_in_flight_requests.clamp(1, MAX_IN_FLIGHT_REQUESTS),
+            )),
             is_broken,
             processing_handle,
         }
@@ -281,15 +316,24 @@ impl AsyncTransport {
 
         // leave stream id empty for now and generate it later
 
-        // handshake messages are never compressed
-        let data = if handshake {
+        // handshake messages are never compressed, and small ones don't benefit from compression
+        let data = if handshake || envelope.body.len() < self.compression_threshold {
             envelope.encode_with(Compression::None)?
         } else {
             envelope.encode_with(self.compression)?
         };
 
+        // wait for a free slot if the connection is saturated - the permit is released when the
+        // response arrives, which also frees its stream id
+        let permit = self
+            .in_flight_permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| Error::General("Connection closed when writing data!".into()))?;
+
         self.write_sender
-            .send(Request::new(data, sender, handshake))
+            .send(Request::new(data, sender, handshake, permit))
             .await
             .map_err(|_| Error::General("Connection closed when writing data!".into()))?;
 
@@ -449,7 +493,7 @@ impl AsyncTransport {
                 frame_stream_ids.push(stream_id);
 
                 request.set_stream_id(stream_id);
-                response_handler_map.add_handler(stream_id, request.handler);
+                response_handler_map.add_handler(stream_id, request.handler, request.permit);
 
                 if request.handshake {
                     // handshake messages are not framed, so let's just write them directly
@@ -579,8 +623,13 @@ impl AsyncTransport {
 
 type ResponseHandler = oneshot::Sender<Result<Envelope>>;
 
+struct PendingResponse {
+    handler: ResponseHandler,
+    _permit: OwnedSemaphorePermit,
+}
+
 struct ResponseHandlerMap {
-    stream_handlers: Mutex<FxHashMap<StreamId, ResponseHandler>>,
+    stream_handlers: Mutex<FxHashMap<StreamId, PendingResponse>>,
     available_stream_id: AtomicI16,
 }
 
@@ -594,17 +643,25 @@ impl ResponseHandlerMap {
     }
 
     #[inline]
-    pub fn add_handler(&self, stream_id: StreamId, handler: ResponseHandler) {
-        self.stream_handlers
-            .lock()
-            .unwrap()
-            .insert(stream_id, handler);
+    pub fn add_handler(
+        &self,
+        stream_id: StreamId,
+        handler: ResponseHandler,
+        permit: OwnedSemaphorePermit,
+    ) {
+        self.stream_handlers.lock().unwrap().insert(
+            stream_id,
+            PendingResponse {
+                handler,
+                _permit: permit,
+            },
+        );
     }
 
     pub fn send_response(&self, stream_id: StreamId, response: Result<Envelope>) -> Result<()> {
         match self.stream_handlers.lock().unwrap().remove(&stream_id) {
-            Some(handler) => {
-                let _ = handler.send(response);
+            Some(pending) => {
+                let _ = pending.handler.send(response);
                 Ok(())
             }
             // unmatched stream - probably a bug somewhere
@@ -616,12 +673,24 @@ impl ResponseHandlerMap {
     }
 
     pub fn signal_general_error(&self, error: &Error) {
-        for (_, handler) in self.stream_handlers.lock().unwrap().drain() {
-            let _ = handler.send(Err(error.clone()));
+        for (_, pending) in self.stream_handlers.lock().unwrap().drain() {
+            let _ = pending.handler.send(Err(error.clone()));
         }
     }
 
+    /// Returns the next stream id not used by any pending request. In-flight requests are limited
+    /// to `MAX_IN_FLIGHT_REQUESTS`, so a free id always exists.
     pub fn next_stream_id(&self) -> StreamId {
+        let stream_handlers = self.stream_handlers.lock().unwrap();
+        loop {
+            let stream_id = self.next_candidate_stream_id();
+            if !stream_handlers.contains_key(&stream_id) {
+                return stream_id;
+            }
+        }
+    }
+
+    fn next_candidate_stream_id(&self) -> StreamId {
         loop {
             let stream = self.available_stream_id.fetch_add(1, Ordering::Relaxed);
             if stream < 0 {
@@ -646,6 +715,7 @@ struct Request {
     data: Vec<u8>,
     handler: ResponseHandler,
     handshake: bool,
+    permit: OwnedSemaphorePermit,
 }
 
 impl Request {
@@ -654,3 +724,31 @@ impl Request {
         self.data[2..4].copy_from_slice(&stream_d.to_be_bytes());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_skip_stream_ids_in_use() {
+        let map = ResponseHandlerMap::new();
+        let permits = Arc::new(Semaphore::new(1));
+
+        let stream_id = map.next_stream_id();
+        let (handler, _receiver) = oneshot::channel();
+        map.add_handler(
+            stream_id,
+            handler,
+            permits.clone().try_acquire_owned().unwrap(),
+        );
+        assert_eq!(permits.available_permits(), 0);
+
+        // wrap around to the pending stream id
+        map.available_stream_id.store(stream_id, Ordering::Relaxed);
+        assert_ne!(map.next_stream_id(), stream_id);
+
+        map.send_response(stream_id, Err(Error::General("".into())))
+            .unwrap();
+        assert_eq!(permits.available_permits(), 1);
+    }
+}
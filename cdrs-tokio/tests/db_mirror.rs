@@ -0,0 +1,391 @@
+#![cfg(feature = "derive")]
+
+use cdrs_tokio::frame::message_result::{
+    BodyResResultRows, ColSpec, ColType, ColTypeOption, RowsMetadata, RowsMetadataFlags,
+};
+use cdrs_tokio::frame::{Serialize, Version};
+use cdrs_tokio::query::QueryValues;
+use cdrs_tokio::types::rows::Row;
+use cdrs_tokio::types::value::Value;
+use cdrs_tokio::types::CBytes;
+use cdrs_tokio::DbMirror;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq, DbMirror)]
+struct User {
+    #[partition_key]
+    id: i32,
+    #[clustering_key]
+    name: String,
+    age: i32,
+    #[column_name = "email_address"]
+    email: String,
+}
+
+fn user() -> User {
+    User {
+        id: 1,
+        name: "john".into(),
+        age: 30,
+        email: "john@example.com".into(),
+    }
+}
+
+#[test]
+fn update_query() {
+    assert_eq!(
+        User::update_query(),
+        "update User set age = ?, email_address = ? where id = ? and name = ?"
+    );
+}
+
+#[test]
+fn update_query_values() {
+    assert_eq!(
+        user().into_update_query_values(),
+        QueryValues::SimpleValues(vec![
+            Value::from(30),
+            Value::from("john@example.com"),
+            Value::from(1),
+            Value::from("john"),
+        ])
+    );
+}
+
+#[test]
+fn delete_query() {
+    assert_eq!(
+        User::delete_query(),
+        "delete from User where id = ? and name = ?"
+    );
+}
+
+#[test]
+fn select_by_primary_key_query() {
+    assert_eq!(
+        User::select_by_primary_key_query(),
+        "select * from User where id = ? and name = ?"
+    );
+}
+
+#[test]
+fn select_all_query() {
+    assert_eq!(User::select_all_query(), "select * from User");
+}
+
+#[test]
+fn primary_key_values() {
+    assert_eq!(
+        user().into_primary_key_values(),
+        QueryValues::SimpleValues(vec![Value::from(1), Value::from("john")])
+    );
+}
+
+#[test]
+fn insert_query() {
+    assert_eq!(
+        User::insert_query(),
+        "insert into User(id, name, age, email_address) values (?, ?, ?, ?)"
+    );
+}
+
+#[test]
+fn insert_if_not_exists_query() {
+    assert_eq!(
+        User::insert_if_not_exists_query(),
+        "insert into User(id, name, age, email_address) values (?, ?, ?, ?) if not exists"
+    );
+}
+
+#[test]
+fn insert_query_with_ttl() {
+    assert_eq!(
+        User::insert_query_with_ttl(),
+        "insert into User(id, name, age, email_address) values (?, ?, ?, ?) using ttl ? and timestamp ?"
+    );
+}
+
+#[test]
+fn query_values_with_ttl() {
+    assert_eq!(
+        user().into_query_values_with_ttl(3600, 1_000_000),
+        QueryValues::SimpleValues(vec![
+            Value::from(1),
+            Value::from("john"),
+            Value::from(30),
+            Value::from("john@example.com"),
+            Value::from(3600),
+            Value::from(1_000_000_i64),
+        ])
+    );
+}
+
+#[test]
+fn query_values() {
+    let values = match user().into_query_values() {
+        QueryValues::NamedValues(values) => values,
+        _ => panic!("expected named values"),
+    };
+
+    assert_eq!(values.len(), 4);
+    assert_eq!(values["email_address"], Value::from("john@example.com"));
+    assert_eq!(values["age"], Value::from(30));
+}
+
+#[test]
+fn positional_query_values() {
+    assert_eq!(
+        user().into_positional_query_values(),
+        QueryValues::SimpleValues(vec![
+            Value::from(1),
+            Value::from("john"),
+            Value::from(30),
+            Value::from("john@example.com"),
+        ])
+    );
+}
+
+#[test]
+fn try_from_row() {
+    let column = |name: &str, id| ColSpec {
+        table_spec: None,
+        name: name.into(),
+        col_type: ColTypeOption { id, value: None },
+    };
+    let value = |value: &dyn Serialize| CBytes::new(value.serialize_to_vec(Version::V4));
+
+    let rows = Row::from_body(BodyResResultRows {
+        metadata: RowsMetadata {
+            flags: RowsMetadataFlags::empty(),
+            columns_count: 4,
+            paging_state: None,
+            new_metadata_id: None,
+            global_table_spec: None,
+            col_specs: vec![
+                column("id", ColType::Int),
+                column("name", ColType::Varchar),
+                column("age", ColType::Int),
+                column("email_address", ColType::Varchar),
+            ],
+        },
+        rows_count: 1,
+        rows_content: vec![vec![
+            value(&1),
+            CBytes::new(b"john".to_vec()),
+            value(&30),
+            CBytes::new(b"john@example.com".to_vec()),
+        ]],
+        protocol_version: Version::V4,
+    });
+
+    assert_eq!(User::try_from_row(&rows[0]).unwrap(), user());
+}
+
+#[derive(Clone, DbMirror)]
+struct Profile {
+    #[partition_key]
+    id: i32,
+    nickname: Option<String>,
+    #[null_if_none]
+    avatar: Option<String>,
+}
+
+#[test]
+fn optional_values() {
+    let profile = Profile {
+        id: 1,
+        nickname: None,
+        avatar: None,
+    };
+
+    assert_eq!(
+        profile.clone().into_positional_query_values(),
+        QueryValues::SimpleValues(vec![Value::new(1), Value::NotSet, Value::Null])
+    );
+
+    let profile = Profile {
+        nickname: Some("j".into()),
+        avatar: Some("a.png".into()),
+        ..profile
+    };
+
+    assert_eq!(
+        profile.into_update_query_values(),
+        QueryValues::SimpleValues(vec![Value::new("j"), Value::new("a.png"), Value::new(1)])
+    );
+}
+
+#[derive(DbMirror)]
+#[keyspace = "test_ks"]
+#[allow(dead_code)]
+struct Event {
+    #[partition_key]
+    id: i32,
+    name: String,
+}
+
+#[test]
+fn keyspace_qualified_queries() {
+    assert_eq!(
+        Event::insert_query(),
+        "insert into test_ks.Event(id, name) values (?, ?)"
+    );
+    assert_eq!(
+        Event::update_query(),
+        "update test_ks.Event set name = ? where id = ?"
+    );
+    assert_eq!(Event::select_all_query(), "select * from test_ks.Event");
+}
+
+#[derive(Clone, DbMirror)]
+struct Playlist {
+    #[partition_key]
+    id: i32,
+    songs: Vec<String>,
+    tags: HashSet<String>,
+    ratings: HashMap<String, i32>,
+}
+
+#[test]
+fn collection_queries() {
+    assert_eq!(
+        Playlist::append_to_songs_query(),
+        "update Playlist set songs = songs + ? where id = ?"
+    );
+    assert_eq!(
+        Playlist::prepend_to_songs_query(),
+        "update Playlist set songs = ? + songs where id = ?"
+    );
+    assert_eq!(
+        Playlist::remove_from_songs_query(),
+        "update Playlist set songs = songs - ? where id = ?"
+    );
+    assert_eq!(
+        Playlist::append_to_tags_query(),
+        "update Playlist set tags = tags + ? where id = ?"
+    );
+    assert_eq!(
+        Playlist::remove_from_ratings_query(),
+        "update Playlist set ratings = ratings - ? where id = ?"
+    );
+}
+
+#[test]
+fn collection_update_values() {
+    let playlist = Playlist {
+        id: 1,
+        songs: vec!["a".into()],
+        tags: vec!["b".to_string()].into_iter().collect(),
+        ratings: HashMap::new(),
+    };
+
+    assert_eq!(
+        playlist.clone().into_songs_update_values(),
+        QueryValues::SimpleValues(vec![Value::new(vec!["a"]), Value::new(1)])
+    );
+    assert_eq!(
+        playlist.clone().into_tags_update_values(),
+        QueryValues::SimpleValues(vec![Value::new(vec!["b"]), Value::new(1)])
+    );
+    assert_eq!(
+        playlist.into_ratings_update_values(),
+        QueryValues::SimpleValues(vec![
+            Value::new(HashMap::<String, i32>::new()),
+            Value::new(1)
+        ])
+    );
+}
+
+#[test]
+fn map_remove_values() {
+    let playlist = Playlist {
+        id: 1,
+        songs: vec![],
+        tags: HashSet::new(),
+        ratings: vec![("a".to_string(), 5)].into_iter().collect(),
+    };
+
+    assert_eq!(
+        playlist.into_ratings_remove_values(),
+        QueryValues::SimpleValues(vec![Value::new(vec!["a"]), Value::new(1)])
+    );
+}
+
+#[derive(Clone, DbMirror)]
+struct SortedPlaylist {
+    #[partition_key]
+    id: i32,
+    tags: BTreeSet<String>,
+    ratings: BTreeMap<String, i32>,
+    votes: Option<BTreeMap<i32, i32>>,
+}
+
+#[test]
+fn sorted_collection_methods() {
+    assert_eq!(
+        SortedPlaylist::append_to_tags_query(),
+        "update SortedPlaylist set tags = tags + ? where id = ?"
+    );
+    assert_eq!(
+        SortedPlaylist::remove_from_ratings_query(),
+        "update SortedPlaylist set ratings = ratings - ? where id = ?"
+    );
+
+    let playlist = SortedPlaylist {
+        id: 1,
+        tags: vec!["c".to_string()].into_iter().collect(),
+        ratings: vec![("b".to_string(), 1), ("a".to_string(), 2)]
+            .into_iter()
+            .collect(),
+        votes: None,
+    };
+
+    assert_eq!(
+        playlist.clone().into_tags_update_values(),
+        QueryValues::SimpleValues(vec![Value::new(vec!["c"]), Value::new(1)])
+    );
+    assert_eq!(
+        playlist.clone().into_ratings_remove_values(),
+        QueryValues::SimpleValues(vec![Value::new(vec!["a", "b"]), Value::new(1)])
+    );
+    assert_eq!(
+        playlist.into_votes_remove_values(),
+        QueryValues::SimpleValues(vec![Value::NotSet, Value::new(1)])
+    );
+}
+
+#[derive(DbMirror)]
+struct PageViews {
+    #[partition_key]
+    page: String,
+    #[counter]
+    views: i64,
+    #[counter]
+    unique_views: i64,
+}
+
+#[test]
+fn counter_queries() {
+    assert_eq!(
+        PageViews::increment_query(),
+        "update PageViews set views = views + ?, unique_views = unique_views + ? where page = ?"
+    );
+    assert_eq!(
+        PageViews::decrement_query(),
+        "update PageViews set views = views - ?, unique_views = unique_views - ? where page = ?"
+    );
+
+    let views = PageViews {
+        page: "index".into(),
+        views: 2,
+        unique_views: 1,
+    };
+    assert_eq!(
+        views.into_update_query_values(),
+        QueryValues::SimpleValues(vec![
+            Value::new(2i64),
+            Value::new(1i64),
+            Value::new("index")
+        ])
+    );
+}
@@ -9,6 +9,10 @@ use cdrs_tokio::load_balancing::RoundRobinLoadBalancingStrategy;
 #[cfg(feature = "e2e-tests")]
 use cdrs_tokio::retry::NeverReconnectionPolicy;
 #[cfg(feature = "e2e-tests")]
+use cdrs_tokio::statement::StatementParamsBuilder;
+#[cfg(feature = "e2e-tests")]
+use futures::TryStreamExt;
+#[cfg(feature = "e2e-tests")]
 use std::sync::Arc;
 
 #[tokio::test]
@@ -68,3 +72,53 @@ async fn paged_query() {
 
     assert!(!query_pager.has_more());
 }
+
+#[tokio::test]
+#[cfg(feature = "e2e-tests")]
+async fn query_stream() {
+    let cluster_config = NodeTcpConfigBuilder::new()
+        .with_contact_point("127.0.0.1:9042".into())
+        .with_authenticator_provider(Arc::new(NoneAuthenticatorProvider))
+        .build()
+        .await
+        .unwrap();
+    let lb = RoundRobinLoadBalancingStrategy::new();
+    let session = TcpSessionBuilder::new(lb, cluster_config)
+        .with_reconnection_policy(Arc::new(NeverReconnectionPolicy::default()))
+        .build()
+        .unwrap();
+
+    session
+        .query(
+            "CREATE KEYSPACE IF NOT EXISTS test_ks WITH REPLICATION = { \
+                                       'class' : 'SimpleStrategy', 'replication_factor' : 1 };",
+        )
+        .await
+        .expect("Keyspace creation error");
+
+    session
+        .query("create table if not exists test_ks.stream_user (user_id int primary key)")
+        .await
+        .expect("Could not create table");
+
+    for i in 0..=9 {
+        session
+            .query(format!(
+                "insert into test_ks.stream_user(user_id) values ({})",
+                i
+            ))
+            .await
+            .expect("Could not insert row");
+    }
+
+    let rows = session
+        .query_stream(
+            "SELECT * FROM test_ks.stream_user",
+            StatementParamsBuilder::new().with_page_size(3).build(),
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("stream rows");
+
+    assert_eq!(rows.len(), 10);
+}
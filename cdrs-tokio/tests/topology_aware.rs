@@ -95,3 +95,69 @@ async fn query_topology_aware() {
         "should find at least one element for each criteria"
     );
 }
+
+#[tokio::test]
+#[cfg(feature = "e2e-tests")]
+async fn exec_prepared_topology_aware() {
+    // partition key values are bound to prepared statements, so the load balancer can compute the
+    // token without an explicit routing key
+
+    let cluster_config = NodeTcpConfigBuilder::new()
+        .with_contact_point("127.0.0.1:9042".into())
+        .with_authenticator_provider(Arc::new(NoneAuthenticatorProvider))
+        .build()
+        .await
+        .unwrap();
+    let session = TcpSessionBuilder::new(
+        TopologyAwareLoadBalancingStrategy::new(None, false),
+        cluster_config,
+    )
+    .with_reconnection_policy(Arc::new(NeverReconnectionPolicy::default()))
+    .with_node_distance_evaluator(Box::new(TopologyAwareNodeDistanceEvaluator::new(
+        "datacenter1".into(),
+    )))
+    .build()
+    .unwrap();
+
+    let create_keyspace_query = "CREATE KEYSPACE IF NOT EXISTS cdrs_test WITH \
+         replication = {'class': 'NetworkTopologyStrategy', 'datacenter1': 1} \
+         AND durable_writes = false";
+    session.query(create_keyspace_query).await.unwrap();
+
+    let cql = "CREATE TABLE IF NOT EXISTS cdrs_test.test_exec_prepared_routing \
+             (id text, part int, value int, PRIMARY KEY ((id, part)))";
+    session.query(cql).await.unwrap();
+
+    let insert = session
+        .prepare(
+            "INSERT INTO cdrs_test.test_exec_prepared_routing \
+             (value, id, part) VALUES (?, ?, ?)",
+        )
+        .await
+        .unwrap();
+    assert_eq!(insert.pk_indexes, vec![1, 2]);
+
+    let values = query_values!(10, "1".to_string(), 2);
+    assert!(insert.routing_key(&values).unwrap().is_some());
+
+    session.exec_with_values(&insert, values).await.unwrap();
+
+    let select = session
+        .prepare("SELECT value FROM cdrs_test.test_exec_prepared_routing WHERE id = ? AND part = ?")
+        .await
+        .unwrap();
+
+    let rows = session
+        .exec_with_values(&select, query_values!("1".to_string(), 2))
+        .await
+        .expect("select error")
+        .response_body()
+        .expect("get body error")
+        .into_rows()
+        .expect("converting into rows error");
+
+    assert_eq!(rows.len(), 1);
+
+    let value: i32 = rows[0].get_r_by_name("value").expect("value");
+    assert_eq!(value, 10);
+}